@@ -0,0 +1,80 @@
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{
+    stream::{ItemBuffer, CHUNK_SIZE, DEFAULT_MAX_ITEM_LEN},
+    BencodeError, Item,
+};
+
+/// Decodes one item from an async reader, reading chunks until the item is complete.
+///
+/// Bytes read past the end of the item are discarded, so the reader should only hold the item,
+/// such as a tracker response body or a length-prefixed message limited with [`AsyncReadExt::take`]
+pub async fn decode_async<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Item, BencodeError> {
+    decode_async_with_max_len(reader, DEFAULT_MAX_ITEM_LEN).await
+}
+
+/// Decodes one item from an async reader, failing with [`BencodeError::ItemTooLong`] once more than `max_len` bytes
+/// have been read without completing it
+pub async fn decode_async_with_max_len<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Item, BencodeError> {
+    let mut buffer = ItemBuffer::new(max_len);
+    let mut chunk = [0; CHUNK_SIZE];
+
+    loop {
+        let read = reader.read(&mut chunk).await?;
+        buffer.extend(&chunk[..read]);
+
+        if let Some(item) = buffer.take_item(read == 0)? {
+            return Ok(item);
+        }
+        if read == 0 {
+            return Err(BencodeError::UnexpectedEof { offset: 0 });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode_async() {
+        let data = b"d3:cow3:moo4:spaml1:a1:bee";
+        let (mut client, mut server) = tokio::io::duplex(4);
+
+        let writer = async move {
+            for chunk in data.chunks(3) {
+                server.write_all(chunk).await.unwrap();
+            }
+        };
+        let (item, ()) = tokio::join!(decode_async(&mut client), writer);
+
+        let item = item.unwrap();
+        assert_eq!(item["cow"], Item::ByteArray(b"moo".to_vec()));
+        assert_eq!(item.encode(), data);
+    }
+
+    #[tokio::test]
+    async fn test_decode_async_errors() {
+        assert!(matches!(
+            decode_async(&mut &b""[..]).await,
+            Err(BencodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert!(matches!(
+            decode_async(&mut &b"l4:spam"[..]).await,
+            Err(BencodeError::UnexpectedEof { offset: 7 })
+        ));
+        assert!(matches!(
+            decode_async(&mut &b"x"[..]).await,
+            Err(BencodeError::InvalidItem { offset: 0 })
+        ));
+        assert!(matches!(
+            decode_async_with_max_len(&mut &b"l4:spam4:eggse"[..], 8).await,
+            Err(BencodeError::ItemTooLong { max_len: 8 })
+        ));
+    }
+}
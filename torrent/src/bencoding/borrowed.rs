@@ -0,0 +1,195 @@
+use alloc::{string::ToString, vec::Vec};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{cut, map},
+    multi::{many0, many1},
+    sequence::{pair, terminated},
+    Finish,
+};
+
+use super::{
+    error::{DecodeError, DecodeErrorKind},
+    parse_bytearray, parse_container_start, parse_integer, BEncoding, BencodeError, Dictionary,
+    Item, ParseResult, Rules, WithCapacity,
+};
+
+/// Map used to store borrowed dictionaries, which keeps keys in their original order with the `ordered` feature
+#[cfg(not(feature = "ordered"))]
+pub type DictionaryRef<'a> = alloc::collections::BTreeMap<&'a str, ItemRef<'a>>;
+/// Map used to store borrowed dictionaries, which keeps keys in their original order with the `ordered` feature
+#[cfg(feature = "ordered")]
+pub type DictionaryRef<'a> = indexmap::IndexMap<&'a str, ItemRef<'a>>;
+
+/// Represents a single BEncode item borrowing its byte arrays and keys from the input
+#[derive(Debug, PartialEq, Clone)]
+pub enum ItemRef<'a> {
+    ByteArray(&'a [u8]),
+    Integer(i64),
+    Dictionary(DictionaryRef<'a>),
+    List(Vec<ItemRef<'a>>),
+}
+
+impl ItemRef<'_> {
+    /// Copies all borrowed data, producing an owned item
+    pub fn to_owned(&self) -> Item {
+        match self {
+            ItemRef::ByteArray(bytes) => Item::ByteArray(bytes.to_vec()),
+            ItemRef::Integer(number) => Item::Integer(*number),
+            ItemRef::Dictionary(dict) => Item::Dictionary(
+                dict.iter()
+                    .map(|(key, value)| (key.to_string(), value.to_owned()))
+                    .collect::<Dictionary>(),
+            ),
+            ItemRef::List(items) => Item::List(items.iter().map(ItemRef::to_owned).collect()),
+        }
+    }
+}
+
+impl BEncoding {
+    /// Decodes a byte array without copying, with every item borrowing from the input
+    pub fn decode_borrowed(bytes: &[u8]) -> Result<Vec<ItemRef<'_>>, BencodeError> {
+        many1(|input| parse_item_ref(input, Rules::default()))(bytes)
+            .finish()
+            .map(|(_remaining, items)| items)
+            .map_err(|err| BencodeError::from_decode(err, bytes))
+    }
+
+    /// Decodes a byte array containing exactly one item without copying, returning an error if any bytes remain
+    pub fn decode_borrowed_strict(bytes: &[u8]) -> Result<ItemRef<'_>, BencodeError> {
+        let (remaining, item) = parse_item_ref(bytes, Rules::default())
+            .finish()
+            .map_err(|err| BencodeError::from_decode(err, bytes))?;
+
+        if remaining.is_empty() {
+            Ok(item)
+        } else {
+            Err(BencodeError::from_decode(
+                DecodeError::new(remaining, DecodeErrorKind::TrailingData),
+                bytes,
+            ))
+        }
+    }
+}
+
+/// Parse a BENcoded list of the form `l<element>*e`, borrowing from the input
+fn parse_list_ref(input: &[u8], rules: Rules) -> ParseResult<'_, Vec<ItemRef<'_>>> {
+    let (input, rules) = parse_container_start(BEncoding::LIST_START, rules)(input)?;
+
+    cut(terminated(
+        many0(move |input| parse_item_ref(input, rules)),
+        tag(BEncoding::END),
+    ))(input)
+}
+
+/// Parse a BENcoded dict of the form `d(<element key><element value>)*e`, borrowing from the input
+fn parse_dictionary_ref(input: &[u8], rules: Rules) -> ParseResult<'_, DictionaryRef<'_>> {
+    let (input, rules) = parse_container_start(BEncoding::DICT_START, rules)(input)?;
+
+    let (input, pairs) = cut(terminated(
+        many0(pair(parse_bytearray, move |input| {
+            parse_item_ref(input, rules)
+        })),
+        tag(BEncoding::END),
+    ))(input)?;
+
+    let mut dict = DictionaryRef::with_capacity_hint(pairs.len());
+    for (key, value) in pairs {
+        let string = core::str::from_utf8(key)
+            .map_err(|_| nom::Err::Failure(DecodeError::new(key, DecodeErrorKind::NonUtf8Key)))?;
+
+        dict.insert(string, value);
+    }
+
+    Ok((input, dict))
+}
+
+/// Parse any BEncoded item, borrowing from the input
+fn parse_item_ref(input: &[u8], rules: Rules) -> ParseResult<'_, ItemRef<'_>> {
+    alt((
+        map(parse_integer, ItemRef::Integer),
+        map(|input| parse_list_ref(input, rules), ItemRef::List),
+        map(
+            |input| parse_dictionary_ref(input, rules),
+            ItemRef::Dictionary,
+        ),
+        map(parse_bytearray, ItemRef::ByteArray),
+    ))(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    /// Checks every byte array and key in an item points into `input` rather than a copy
+    fn borrows_from(item: &ItemRef, input: &[u8]) -> bool {
+        let contains =
+            |slice: &[u8]| input.as_ptr_range().contains(&slice.as_ptr()) || slice.is_empty();
+
+        match item {
+            ItemRef::ByteArray(bytes) => contains(bytes),
+            ItemRef::Integer(_) => true,
+            ItemRef::Dictionary(dict) => dict
+                .iter()
+                .all(|(key, value)| contains(key.as_bytes()) && borrows_from(value, input)),
+            ItemRef::List(items) => items.iter().all(|item| borrows_from(item, input)),
+        }
+    }
+
+    #[test]
+    fn test_borrowed() {
+        let input = b"d3:cow3:moo4:spaml1:ai1eee";
+        let items = BEncoding::decode_borrowed(input).unwrap();
+
+        assert_eq!(
+            items,
+            vec![ItemRef::Dictionary(DictionaryRef::from([
+                ("cow", ItemRef::ByteArray(b"moo")),
+                (
+                    "spam",
+                    ItemRef::List(vec![ItemRef::ByteArray(b"a"), ItemRef::Integer(1)])
+                )
+            ]))]
+        );
+        assert!(borrows_from(&items[0], input));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_borrowed_file() {
+        let data = std::fs::read("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        let items = BEncoding::decode_borrowed(&data).unwrap();
+
+        assert!(borrows_from(&items[0], &data));
+        assert_eq!(
+            items[0].to_owned(),
+            BEncoding::decode(&data).unwrap().items[0]
+        );
+    }
+
+    #[test]
+    fn test_borrowed_strict() {
+        assert_eq!(
+            BEncoding::decode_borrowed_strict(b"4:spam").unwrap(),
+            ItemRef::ByteArray(b"spam")
+        );
+        assert!(matches!(
+            BEncoding::decode_borrowed_strict(b"4:spami1e"),
+            Err(BencodeError::TrailingData { offset: 6 })
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_errors() {
+        assert!(matches!(
+            BEncoding::decode_borrowed(b"d2:\xff\xfei1ee"),
+            Err(BencodeError::NonUtf8Key { offset: 3 })
+        ));
+        assert!(matches!(
+            BEncoding::decode_borrowed(b"l4:spam"),
+            Err(BencodeError::UnexpectedEof { .. })
+        ));
+    }
+}
@@ -0,0 +1,411 @@
+use serde::{
+    de::{
+        self, value::SeqDeserializer, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess,
+        Unexpected, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+
+use super::{BEncoding, ItemRef, SerdeError};
+
+/// Deserializes a value from a byte array containing exactly one BEncoded item, borrowing strings and bytes from the input
+pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, SerdeError> {
+    let item = BEncoding::decode_borrowed_strict(bytes)?;
+
+    T::deserialize(Deserializer::new(item))
+}
+
+/// Deserializes serde data types from a decoded item.
+///
+/// Byte arrays can be deserialized as strings if valid UTF-8 or as bytes, and integers as any numeric type they fit in
+#[derive(Debug)]
+pub struct Deserializer<'de> {
+    item: ItemRef<'de>,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(item: ItemRef<'de>) -> Self {
+        Self { item }
+    }
+
+    /// Returns the item as unexpected, for error messages
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self.item {
+            ItemRef::ByteArray(bytes) => Unexpected::Bytes(bytes),
+            ItemRef::Integer(number) => Unexpected::Signed(number),
+            ItemRef::Dictionary(_) => Unexpected::Map,
+            ItemRef::List(_) => Unexpected::Seq,
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for Deserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.item {
+            ItemRef::ByteArray(bytes) => visitor.visit_borrowed_bytes(bytes),
+            ItemRef::Integer(number) => visitor.visit_i64(number),
+            ItemRef::List(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter().map(Deserializer::new));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+
+                Ok(value)
+            }
+            ItemRef::Dictionary(dict) => visitor.visit_map(MapDeserializer {
+                entries: dict.into_iter().collect::<Vec<_>>().into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.item {
+            ItemRef::Integer(0) => visitor.visit_bool(false),
+            ItemRef::Integer(1) => visitor.visit_bool(true),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.item {
+            ItemRef::ByteArray(bytes) => match std::str::from_utf8(bytes) {
+                Ok(string) => visitor.visit_borrowed_str(string),
+                Err(_) => Err(de::Error::invalid_value(self.unexpected(), &visitor)),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.item {
+            // allows byte arrays to be deserialized into collections such as `Vec<u8>`
+            ItemRef::ByteArray(bytes) => {
+                let mut seq = SeqDeserializer::<_, SerdeError>::new(bytes.iter().copied());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+
+                Ok(value)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        // bencode has no null, so missing values are represented by missing dictionary keys
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.item {
+            // unit variants are represented by their name
+            ItemRef::ByteArray(_) => visitor.visit_enum(EnumDeserializer {
+                variant: self,
+                value: None,
+            }),
+            // other variants are represented by a dictionary with a single key
+            ItemRef::Dictionary(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.into_iter().next().expect("length was checked");
+
+                visitor.visit_enum(EnumDeserializer {
+                    variant: Deserializer::new(ItemRef::ByteArray(variant.as_bytes())),
+                    value: Some(Deserializer::new(value)),
+                })
+            }
+            _ => Err(de::Error::invalid_type(self.unexpected(), &visitor)),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf unit unit_struct map struct
+    }
+}
+
+/// Provides the entries of a dictionary to a visitor, deserializing keys as strings
+struct MapDeserializer<'de, I> {
+    entries: I,
+    /// Value of the entry whose key was most recently visited
+    value: Option<ItemRef<'de>>,
+}
+
+impl<'de, I: Iterator<Item = (&'de str, ItemRef<'de>)>> MapAccess<'de> for MapDeserializer<'de, I> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Deserializer::new(ItemRef::ByteArray(key.as_bytes())))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <SerdeError as de::Error>::custom("value requested before key"))?;
+
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.entries.size_hint().1
+    }
+}
+
+/// Provides an enum variant, and its contents if it is not a unit variant, to a visitor
+struct EnumDeserializer<'de> {
+    variant: Deserializer<'de>,
+    value: Option<Deserializer<'de>>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = SerdeError;
+    type Variant = VariantDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), SerdeError> {
+        Ok((
+            seed.deserialize(self.variant)?,
+            VariantDeserializer { value: self.value },
+        ))
+    }
+}
+
+/// Provides the contents of an enum variant, which unit variants don't have
+struct VariantDeserializer<'de> {
+    value: Option<Deserializer<'de>>,
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => Err(de::Error::invalid_type(value.unexpected(), &"unit variant")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value {
+            Some(value) => de::Deserializer::deserialize_seq(value, visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value {
+            Some(value) => de::Deserializer::deserialize_map(value, visitor),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::bencoding::BencodeError;
+
+    #[derive(Debug, Deserialize)]
+    struct Torrent<'a> {
+        announce: String,
+        #[serde(rename = "creation date")]
+        creation_date: u32,
+        #[serde(borrow)]
+        info: Info<'a>,
+        comment: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Info<'a> {
+        name: &'a str,
+        length: u64,
+        #[serde(rename = "piece length")]
+        piece_length: u32,
+        pieces: Vec<u8>,
+        private: bool,
+    }
+
+    #[test]
+    fn test_from_bytes_torrent() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        let torrent: Torrent = from_bytes(&data).unwrap();
+
+        assert_eq!(torrent.announce, "udp://tracker.openbittorrent.com:80");
+        assert_eq!(torrent.creation_date, 1327049827);
+        assert_eq!(torrent.comment, None);
+        assert_eq!(torrent.info.name, "sample.txt");
+        assert_eq!(torrent.info.length, 20);
+        assert_eq!(torrent.info.piece_length, 65536);
+        assert_eq!(torrent.info.pieces.len(), 20);
+        assert!(torrent.info.private);
+    }
+
+    #[test]
+    fn test_from_bytes_borrowed() {
+        let data = b"d4:name4:spam6:pieces3:\xff\x00\x01e";
+
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            name: &'a str,
+            pieces: &'a [u8],
+        }
+
+        let borrowed: Borrowed = from_bytes(data).unwrap();
+        assert_eq!(borrowed.name, "spam");
+        assert_eq!(borrowed.pieces, b"\xff\x00\x01");
+        assert!(data.as_ptr_range().contains(&borrowed.pieces.as_ptr()));
+    }
+
+    #[test]
+    fn test_from_bytes_integers() {
+        assert_eq!(from_bytes::<u8>(b"i255e").unwrap(), 255);
+        assert_eq!(from_bytes::<i8>(b"i-128e").unwrap(), -128);
+        assert_eq!(from_bytes::<i64>(b"i-3e").unwrap(), -3);
+
+        assert!(matches!(
+            from_bytes::<u8>(b"i256e"),
+            Err(SerdeError::Message(_))
+        ));
+        assert!(matches!(
+            from_bytes::<u32>(b"i-1e"),
+            Err(SerdeError::Message(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_errors() {
+        assert!(matches!(
+            from_bytes::<String>(b"2:\xff\xfe"),
+            Err(SerdeError::Message(_))
+        ));
+        assert!(matches!(
+            from_bytes::<String>(b"i1e"),
+            Err(SerdeError::Message(_))
+        ));
+        assert!(matches!(
+            from_bytes::<String>(b"4:spam4:eggs"),
+            Err(SerdeError::Decode(BencodeError::TrailingData { offset: 6 }))
+        ));
+        assert!(matches!(
+            from_bytes::<(u8, u8)>(b"li1ei2ei3ee"),
+            Err(SerdeError::Message(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_enum() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        enum Event {
+            Started,
+            Completed { downloaded: u64 },
+            Seen(Vec<String>),
+        }
+
+        assert_eq!(from_bytes::<Event>(b"7:Started").unwrap(), Event::Started);
+        assert_eq!(
+            from_bytes::<Event>(b"d9:Completedd10:downloadedi5eee").unwrap(),
+            Event::Completed { downloaded: 5 }
+        );
+        assert_eq!(
+            from_bytes::<Event>(b"d4:Seenl4:spamee").unwrap(),
+            Event::Seen(vec!["spam".to_string()])
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Display, Formatter};
+
+use super::Item;
+
+/// Longest string, in characters, displayed in full
+const MAX_INLINE_CHARS: usize = 64;
+/// Number of bytes of a binary byte array displayed as hex
+const MAX_HEX_BYTES: usize = 8;
+/// Spaces used per level of nesting
+const INDENT: usize = 2;
+
+/// Writes a byte array, as a quoted string if it is printable UTF-8 and as a hex prefix otherwise
+fn fmt_bytes(bytes: &[u8], f: &mut Formatter<'_>) -> fmt::Result {
+    match core::str::from_utf8(bytes) {
+        Ok(string) if !string.chars().any(char::is_control) => {
+            if string.chars().count() <= MAX_INLINE_CHARS {
+                write!(f, "{string:?}")
+            } else {
+                let prefix: String = string.chars().take(MAX_INLINE_CHARS).collect();
+                write!(f, "{:?}… ({} bytes)", prefix, bytes.len())
+            }
+        }
+        _ => {
+            write!(f, "<{} bytes: ", bytes.len())?;
+            for byte in bytes.iter().take(MAX_HEX_BYTES) {
+                write!(f, "{byte:02x}")?;
+            }
+            if bytes.len() > MAX_HEX_BYTES {
+                write!(f, "…")?;
+            }
+            write!(f, ">")
+        }
+    }
+}
+
+/// Writes the entries of a dictionary in sorted key order
+fn fmt_entries<'a, K: AsRef<[u8]> + Ord>(
+    entries: impl Iterator<Item = (K, &'a Item)>,
+    f: &mut Formatter<'_>,
+    depth: usize,
+) -> fmt::Result {
+    let mut entries: Vec<_> = entries.collect();
+    if entries.is_empty() {
+        return write!(f, "{{}}");
+    }
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    writeln!(f, "{{")?;
+    for (i, (key, value)) in entries.iter().enumerate() {
+        write!(f, "{:width$}", "", width = (depth + 1) * INDENT)?;
+        fmt_bytes(key.as_ref(), f)?;
+        write!(f, ": ")?;
+        value.fmt_indented(f, depth + 1)?;
+        writeln!(f, "{}", if i + 1 < entries.len() { "," } else { "" })?;
+    }
+    write!(f, "{:width$}}}", "", width = depth * INDENT)
+}
+
+impl Item {
+    /// Writes the item, with nested items indented one level deeper than `depth`
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> fmt::Result {
+        match self {
+            Item::Integer(number) => write!(f, "{number}"),
+            Item::ByteArray(bytes) => fmt_bytes(bytes, f),
+            Item::List(items) if items.is_empty() => write!(f, "[]"),
+            Item::List(items) => {
+                writeln!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    write!(f, "{:width$}", "", width = (depth + 1) * INDENT)?;
+                    item.fmt_indented(f, depth + 1)?;
+                    writeln!(f, "{}", if i + 1 < items.len() { "," } else { "" })?;
+                }
+                write!(f, "{:width$}]", "", width = depth * INDENT)
+            }
+            Item::Dictionary(dict) => fmt_entries(
+                dict.iter().map(|(key, value)| (key.as_str(), value)),
+                f,
+                depth,
+            ),
+            Item::RawDictionary(dict) => fmt_entries(
+                dict.iter().map(|(key, value)| (key.as_slice(), value)),
+                f,
+                depth,
+            ),
+        }
+    }
+}
+
+impl Display for Item {
+    /// Writes a compact, indented view of the item, abbreviating long strings and binary data
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bencoding::{Dictionary, RawDictionary};
+    use alloc::{borrow::ToOwned, format, string::ToString, vec};
+
+    #[test]
+    fn test_display_scalars() {
+        assert_eq!(Item::Integer(-3).to_string(), "-3");
+        assert_eq!(Item::ByteArray(b"spam".to_vec()).to_string(), "\"spam\"");
+        assert_eq!(
+            Item::ByteArray(vec![0xff, 0x00, 0x10]).to_string(),
+            "<3 bytes: ff0010>"
+        );
+        assert_eq!(
+            Item::ByteArray((0..20).collect()).to_string(),
+            "<20 bytes: 0001020304050607…>"
+        );
+        assert_eq!(
+            Item::ByteArray("a".repeat(70).into_bytes()).to_string(),
+            format!("\"{}\"… (70 bytes)", "a".repeat(64))
+        );
+        assert_eq!(Item::List(vec![]).to_string(), "[]");
+        assert_eq!(Item::Dictionary(Dictionary::new()).to_string(), "{}");
+    }
+
+    #[test]
+    fn test_display_nested() {
+        let item = Item::Dictionary(Dictionary::from([
+            (
+                "spam".to_owned(),
+                Item::List(vec![Item::Integer(1), Item::Integer(2)]),
+            ),
+            ("cow".to_owned(), Item::ByteArray(b"moo".to_vec())),
+        ]));
+
+        assert_eq!(
+            item.to_string(),
+            "{\n  \"cow\": \"moo\",\n  \"spam\": [\n    1,\n    2\n  ]\n}"
+        );
+
+        let raw = Item::RawDictionary(RawDictionary::from([(vec![0xff], Item::Integer(1))]));
+        assert_eq!(raw.to_string(), "{\n  <1 bytes: ff>: 1\n}");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_display_torrent() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        let (torrent, _) = super::super::BEncoding::decode_with_remainder(&data).unwrap();
+        let display = torrent.to_string();
+
+        assert!(display.contains("\"announce\": \"udp://tracker.openbittorrent.com:80\","));
+        assert!(display.contains("    \"pieces\": <20 bytes: "));
+        assert!(display.len() < 400);
+    }
+}
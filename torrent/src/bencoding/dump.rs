@@ -0,0 +1,113 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Bytes shown on each row, matching `hexdump -C`
+const ROW_LEN: usize = 16;
+/// Column the ASCII part of each row starts at, after the offset and hex bytes
+const ASCII_COLUMN: usize = 61;
+
+/// Column the hex digits of the byte at `index` within a row start at, with an extra space after the eighth byte
+fn hex_column(index: usize) -> usize {
+    10 + index * 3 + usize::from(index >= ROW_LEN / 2)
+}
+
+/// Renders the bytes within `window` of `offset` in the style of `hexdump -C`, with a line of carets marking the byte at `offset`.
+///
+/// The range shown is widened to whole rows, and an offset at the end of the input is marked after the last byte
+pub fn dump_context(bytes: &[u8], offset: usize, window: usize) -> String {
+    let offset = offset.min(bytes.len());
+    let start = offset.saturating_sub(window) / ROW_LEN * ROW_LEN;
+    let end = offset.saturating_add(window).min(bytes.len());
+
+    let mut output = String::new();
+    let mut row_start = start;
+    while row_start <= end {
+        let row = &bytes[row_start..(row_start + ROW_LEN).min(bytes.len())];
+
+        let mut line = String::new();
+        let _ = write!(line, "{row_start:08x}  ");
+        for index in 0..ROW_LEN {
+            match row.get(index) {
+                Some(byte) => {
+                    let _ = write!(line, "{byte:02x} ");
+                }
+                None => line.push_str("   "),
+            }
+            if index == ROW_LEN / 2 - 1 {
+                line.push(' ');
+            }
+        }
+        line.push_str(" |");
+        line.extend(row.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+        line.push('|');
+
+        output.push_str(line.trim_end());
+        output.push('\n');
+
+        if (row_start..row_start + ROW_LEN).contains(&offset) {
+            let index = offset - row_start;
+            let mut marker = String::new();
+            marker.extend(core::iter::repeat_n(' ', hex_column(index)));
+            marker.push_str("^^");
+            marker.extend(core::iter::repeat_n(
+                ' ',
+                ASCII_COLUMN + index - marker.len(),
+            ));
+            marker.push('^');
+
+            output.push_str(&marker);
+            output.push('\n');
+        }
+
+        row_start += ROW_LEN;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_dump_context() {
+        assert_eq!(
+            dump_context(b"d4:spami1x2ee", 9, 4),
+            "00000000  64 34 3a 73 70 61 6d 69  31 78 32 65 65           |d4:spami1x2ee|\n\
+             \x20                                     ^^                              ^\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_context_rows() {
+        let bytes: Vec<u8> = (0..64).collect();
+        let dump = dump_context(&bytes, 40, 8);
+        let lines: Vec<_> = dump.lines().collect();
+
+        // only the rows holding bytes 32 to 48 are shown, with the marker after the row holding the offset
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("00000020  20 21 22 23 24 25 26 27  28 29"));
+        assert!(lines[0].ends_with("| !\"#$%&'()*+,-./|"));
+        assert_eq!(lines[1].find("^^"), Some(hex_column(8)));
+        assert!(lines[2].starts_with("00000030  30 31"));
+    }
+
+    #[test]
+    fn test_dump_context_end() {
+        let dump = dump_context(b"i12", 3, 16);
+
+        assert_eq!(
+            dump,
+            "00000000  69 31 32                                          |i12|\n\
+             \x20                  ^^                                           ^\n"
+        );
+        assert_eq!(dump_context(b"", 0, 16).lines().count(), 2);
+    }
+}
@@ -0,0 +1,352 @@
+use alloc::vec::Vec;
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use super::{BEncoding, Item};
+
+/// Destination for encoded bytes, so encoding into a `Vec` does not depend on `std::io`
+trait Output {
+    type Error;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl Output for Vec<u8> {
+    type Error = Infallible;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Adapts an [`std::io::Write`] into an [`Output`]
+#[cfg(feature = "std")]
+struct Writer<'a, W>(&'a mut W);
+
+#[cfg(feature = "std")]
+impl<W: Write> Output for Writer<'_, W> {
+    type Error = std::io::Error;
+
+    fn put(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.0.write_all(bytes)
+    }
+}
+
+impl BEncoding {
+    /// Encodes every item in order, producing canonical BEncode
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for item in &self.items {
+            let Ok(()) = item.encode_into(&mut buffer);
+        }
+
+        buffer
+    }
+
+    /// Returns the number of bytes [`BEncoding::encode`] would produce, without encoding or allocating
+    pub fn encoded_len(&self) -> usize {
+        self.items.iter().map(Item::encoded_len).sum()
+    }
+
+    /// Encodes every item in order, writing the output incrementally to `writer`
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for item in &self.items {
+            item.encode_to_writer(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Encodes every item in order and writes the result to a file
+    #[cfg(feature = "std")]
+    pub fn encode_to_path(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.encode_to_writer(&mut writer)?;
+
+        writer.flush()
+    }
+}
+
+impl Item {
+    /// Encodes the item as canonical BEncode, with dictionary keys in ascending order.
+    ///
+    /// With the `ordered` feature, dictionary keys are instead kept in their original order
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let Ok(()) = self.encode_into(&mut buffer);
+
+        buffer
+    }
+
+    /// Returns the number of bytes [`Item::encode`] would produce, without encoding or allocating
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Item::ByteArray(bytes) => bytearray_len(bytes),
+            Item::Integer(number) => {
+                let sign = usize::from(*number < 0);
+                2 + sign + decimal_len(number.unsigned_abs())
+            }
+            Item::List(items) => 2 + items.iter().map(Item::encoded_len).sum::<usize>(),
+            // key order doesn't affect the length, so entries are never sorted
+            Item::Dictionary(dict) => {
+                2 + dict
+                    .iter()
+                    .map(|(key, value)| bytearray_len(key.as_bytes()) + value.encoded_len())
+                    .sum::<usize>()
+            }
+            Item::RawDictionary(dict) => {
+                2 + dict
+                    .iter()
+                    .map(|(key, value)| bytearray_len(key) + value.encoded_len())
+                    .sum::<usize>()
+            }
+        }
+    }
+
+    /// Encodes the item as BEncode, writing the output incrementally to `writer`
+    #[cfg(feature = "std")]
+    pub fn encode_to_writer<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.encode_into(&mut Writer(writer))
+    }
+
+    /// Encodes the item into any [`Output`]
+    fn encode_into<O: Output>(&self, output: &mut O) -> Result<(), O::Error> {
+        match self {
+            Item::ByteArray(bytes) => encode_bytearray(bytes, output),
+            Item::Integer(number) => {
+                output.put(BEncoding::NUMBER_START.as_bytes())?;
+                if *number < 0 {
+                    output.put(BEncoding::NEGATIVE.as_bytes())?;
+                }
+                encode_decimal(number.unsigned_abs(), output)?;
+                output.put(BEncoding::END.as_bytes())
+            }
+            Item::List(items) => {
+                output.put(BEncoding::LIST_START.as_bytes())?;
+                for item in items {
+                    item.encode_into(output)?;
+                }
+                output.put(BEncoding::END.as_bytes())
+            }
+            Item::Dictionary(dict) => {
+                // only references are sorted, so values are never copied
+                #[cfg_attr(feature = "ordered", allow(unused_mut))]
+                let mut entries: Vec<_> = dict.iter().collect();
+                #[cfg(not(feature = "ordered"))]
+                entries.sort_unstable_by_key(|(key, _)| key.as_bytes());
+
+                output.put(BEncoding::DICT_START.as_bytes())?;
+                for (key, value) in entries {
+                    encode_bytearray(key.as_bytes(), output)?;
+                    value.encode_into(output)?;
+                }
+                output.put(BEncoding::END.as_bytes())
+            }
+            Item::RawDictionary(dict) => {
+                #[cfg_attr(feature = "ordered", allow(unused_mut))]
+                let mut entries: Vec<_> = dict.iter().collect();
+                #[cfg(not(feature = "ordered"))]
+                entries.sort_unstable_by_key(|(key, _)| key.as_slice());
+
+                output.put(BEncoding::DICT_START.as_bytes())?;
+                for (key, value) in entries {
+                    encode_bytearray(key, output)?;
+                    value.encode_into(output)?;
+                }
+                output.put(BEncoding::END.as_bytes())
+            }
+        }
+    }
+}
+
+/// Writes a non-negative number in decimal without allocating
+fn encode_decimal<O: Output>(mut number: u64, output: &mut O) -> Result<(), O::Error> {
+    // u64::MAX has 20 digits
+    let mut digits = [0; 20];
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (number % 10) as u8;
+        number /= 10;
+
+        if number == 0 {
+            break;
+        }
+    }
+
+    output.put(&digits[start..])
+}
+
+/// Number of digits needed to write a number in decimal
+fn decimal_len(number: u64) -> usize {
+    number
+        .checked_ilog10()
+        .map_or(1, |digits| digits as usize + 1)
+}
+
+/// Length of a byte array once written as `<length>:<data>`
+fn bytearray_len(bytes: &[u8]) -> usize {
+    decimal_len(bytes.len() as u64) + 1 + bytes.len()
+}
+
+/// Writes a byte array of the form `<length>:<data>`
+fn encode_bytearray<O: Output>(bytes: &[u8], output: &mut O) -> Result<(), O::Error> {
+    encode_decimal(bytes.len() as u64, output)?;
+    output.put(BEncoding::ARRAY_SEP.as_bytes())?;
+    output.put(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bencoding::Dictionary;
+    use alloc::{borrow::ToOwned, vec};
+
+    /// Decodes a single item and encodes it again
+    fn round_trip(input: &[u8]) -> Vec<u8> {
+        BEncoding::decode_strict(input).unwrap().items[0].encode()
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(Item::Integer(42).encode(), b"i42e");
+        assert_eq!(Item::Integer(-42).encode(), b"i-42e");
+        assert_eq!(Item::Integer(0).encode(), b"i0e");
+        assert_eq!(Item::ByteArray(b"spam".to_vec()).encode(), b"4:spam");
+        assert_eq!(Item::ByteArray(vec![]).encode(), b"0:");
+        assert_eq!(
+            Item::List(vec![Item::ByteArray(b"spam".to_vec()), Item::Integer(10)]).encode(),
+            b"l4:spami10ee"
+        );
+        assert_eq!(
+            Item::Dictionary(Dictionary::from([
+                ("cow".to_owned(), Item::ByteArray(b"moo".to_vec())),
+                ("spam".to_owned(), Item::ByteArray(b"eggs".to_vec())),
+            ]))
+            .encode(),
+            b"d3:cow3:moo4:spam4:eggse"
+        );
+    }
+
+    #[test]
+    fn test_encode_integer_limits() {
+        assert_eq!(Item::Integer(i64::MAX).encode(), b"i9223372036854775807e");
+        assert_eq!(Item::Integer(i64::MIN).encode(), b"i-9223372036854775808e");
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        let items = [
+            Item::Integer(0),
+            Item::Integer(9),
+            Item::Integer(10),
+            Item::Integer(-1),
+            Item::Integer(i64::MAX),
+            Item::Integer(i64::MIN),
+            Item::ByteArray(vec![]),
+            Item::ByteArray(vec![b'a'; 9]),
+            Item::ByteArray(vec![b'a'; 10]),
+            Item::List(vec![]),
+            Item::Dictionary(Dictionary::new()),
+        ];
+        for item in &items {
+            assert_eq!(item.encoded_len(), item.encode().len(), "{item:?}");
+        }
+
+        for input in [
+            b"l4:spami-42eli0eee".as_slice(),
+            b"d3:cowd3:mood4:deepli1eeee4:spam4:eggse",
+            b"d1:bi1e1:ad1:zle1:yi100eee",
+        ] {
+            let encoding = BEncoding::decode(input).unwrap();
+            assert_eq!(encoding.encoded_len(), input.len());
+            assert_eq!(encoding.encoded_len(), encoding.encode().len());
+        }
+
+        let raw = BEncoding::decode_raw_keys(b"d1:a1:x2:\xff\xfe1:ve").unwrap();
+        assert_eq!(raw.encoded_len(), raw.encode().len());
+
+        #[cfg(feature = "std")]
+        {
+            let torrent =
+                BEncoding::decode_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+            assert_eq!(torrent.encoded_len(), torrent.encode().len());
+        }
+    }
+
+    #[test]
+    fn test_encode_document() {
+        let encoding = BEncoding::decode(b"i1e4:spamli2ee").unwrap();
+        assert_eq!(encoding.encode(), b"i1e4:spamli2ee");
+
+        #[cfg(feature = "std")]
+        {
+            let path = std::env::temp_dir().join("torrent-encode-to-path.torrent");
+            encoding.encode_to_path(&path).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), b"i1e4:spamli2ee");
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_encode_to_writer() {
+        let item = BEncoding::decode_path("../archlinux-2022.10.01-x86_64.iso.torrent")
+            .unwrap()
+            .items
+            .remove(0);
+
+        let mut buffer = Vec::new();
+        item.encode_to_writer(&mut buffer).unwrap();
+        assert_eq!(buffer, item.encode());
+    }
+
+    #[test]
+    fn test_encode_raw_keys() {
+        let encoding = BEncoding::decode_raw_keys(b"d1:a1:x2:\xff\xfe1:ve").unwrap();
+        assert_eq!(encoding.encode(), b"d1:a1:x2:\xff\xfe1:ve");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_round_trip_file() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        assert_eq!(BEncoding::decode(&data).unwrap().encode(), data);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(
+            round_trip(b"d3:cow3:moo4:spam4:eggse"),
+            b"d3:cow3:moo4:spam4:eggse"
+        );
+        assert_eq!(
+            round_trip(b"d4:infod6:lengthi20e4:name4:spamee"),
+            b"d4:infod6:lengthi20e4:name4:spamee"
+        );
+        assert_eq!(round_trip(b"l0:i-1eli0eee"), b"l0:i-1eli0eee");
+    }
+
+    #[test]
+    #[cfg(not(feature = "ordered"))]
+    fn test_round_trip_sorts_keys() {
+        assert_eq!(round_trip(b"d1:b1:x1:a1:ye"), b"d1:a1:y1:b1:xe");
+    }
+
+    #[test]
+    #[cfg(feature = "ordered")]
+    fn test_round_trip_preserves_order() {
+        assert_eq!(round_trip(b"d1:b1:x1:a1:ye"), b"d1:b1:x1:a1:ye");
+        assert_eq!(
+            round_trip(b"d4:spamd1:zi1e1:yi2ee3:cow3:mooe"),
+            b"d4:spamd1:zi1e1:yi2ee3:cow3:mooe"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use alloc::string::String;
+use core::{fmt::Display, num::ParseIntError, str::Utf8Error};
+
+use nom::error::{ErrorKind, FromExternalError, ParseError};
+
+/// Represents an error encountered while decoding BEncode, with `offset` being the position in the input it occured at
+#[derive(Debug)]
+pub enum BencodeError {
+    /// Input ended before an item was complete
+    UnexpectedEof { offset: usize },
+    /// Integer was malformed, had redundant leading zeros or didn't fit in an `i64`
+    InvalidInteger { offset: usize },
+    /// Input didn't start a valid item
+    InvalidItem { offset: usize },
+    /// Dictionary key was not valid UTF-8
+    NonUtf8Key { offset: usize },
+    /// Dictionary contained the same key more than once
+    DuplicateKey { key: String, offset: usize },
+    /// Dictionary keys were not in strictly ascending order
+    UnsortedKeys { offset: usize },
+    /// Bytes remained after the item
+    TrailingData { offset: usize },
+    /// Lists and dictionaries were nested too deeply
+    DepthExceeded { offset: usize },
+    /// Byte string was longer than allowed by [`super::DecodeOptions::max_length`]
+    LengthExceeded { offset: usize },
+    /// Item read from a stream was longer than its decoder allows
+    ItemTooLong { max_len: usize },
+    /// Encoding the decoded item again differed from the input, from [`super::BEncoding::decode_checked`]
+    NonCanonical { diff_offset: usize },
+    /// Failed to read input
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl BencodeError {
+    /// Converts a parser error into a public error, given the full input that was being parsed
+    pub(crate) fn from_decode(err: DecodeError<'_>, input: &[u8]) -> Self {
+        // the remaining input is always a subslice of the original input
+        let offset = err.input.as_ptr() as usize - input.as_ptr() as usize;
+
+        match err.kind {
+            DecodeErrorKind::DepthExceeded => Self::DepthExceeded { offset },
+            DecodeErrorKind::LengthExceeded => Self::LengthExceeded { offset },
+            DecodeErrorKind::TrailingData => Self::TrailingData { offset },
+            DecodeErrorKind::Nom(ErrorKind::Eof) => Self::UnexpectedEof { offset },
+            // these point at a key, which may be empty without the input having ended
+            DecodeErrorKind::DuplicateKey => Self::DuplicateKey {
+                key: String::from_utf8_lossy(err.input).into_owned(),
+                offset,
+            },
+            DecodeErrorKind::UnsortedKeys => Self::UnsortedKeys { offset },
+            _ if err.input.is_empty() => Self::UnexpectedEof { offset },
+            DecodeErrorKind::InvalidInteger => Self::InvalidInteger { offset },
+            DecodeErrorKind::NonUtf8Key => Self::NonUtf8Key { offset },
+            DecodeErrorKind::Nom(_) => Self::InvalidItem { offset },
+        }
+    }
+
+    /// Returns the byte offset into the input the error occured at, if any
+    pub fn at_offset(&self) -> Option<usize> {
+        match *self {
+            Self::UnexpectedEof { offset }
+            | Self::InvalidInteger { offset }
+            | Self::InvalidItem { offset }
+            | Self::NonUtf8Key { offset }
+            | Self::DuplicateKey { offset, .. }
+            | Self::UnsortedKeys { offset }
+            | Self::TrailingData { offset }
+            | Self::DepthExceeded { offset }
+            | Self::LengthExceeded { offset }
+            | Self::NonCanonical {
+                diff_offset: offset,
+            } => Some(offset),
+            Self::ItemTooLong { .. } => None,
+            #[cfg(feature = "std")]
+            Self::Io(_) => None,
+        }
+    }
+}
+
+impl BencodeError {
+    /// Displays the error followed by a hexdump of the input around the offset it occured at, if it has one
+    pub fn display_with_context<'a>(&'a self, input: &'a [u8]) -> impl Display + 'a {
+        struct WithContext<'a>(&'a BencodeError, &'a [u8]);
+
+        impl Display for WithContext<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(f, "{}", self.0)?;
+
+                match self.0.at_offset() {
+                    Some(offset) => write!(
+                        f,
+                        "\n{}",
+                        super::dump_context(self.1, offset, CONTEXT_WINDOW).trim_end()
+                    ),
+                    None => Ok(()),
+                }
+            }
+        }
+
+        WithContext(self, input)
+    }
+}
+
+/// Bytes either side of an error shown by [`BencodeError::display_with_context`]
+const CONTEXT_WINDOW: usize = 32;
+
+impl Display for BencodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedEof { offset } => write!(f, "unexpected end of input at byte {offset}"),
+            Self::InvalidInteger { offset } => write!(f, "invalid integer at byte {offset}"),
+            Self::InvalidItem { offset } => write!(f, "invalid item at byte {offset}"),
+            Self::NonUtf8Key { offset } => {
+                write!(f, "dictionary key is not valid UTF-8 at byte {offset}")
+            }
+            Self::DuplicateKey { key, offset } => {
+                write!(f, "duplicate dictionary key {key:?} at byte {offset}")
+            }
+            Self::UnsortedKeys { offset } => {
+                write!(f, "dictionary keys are not sorted at byte {offset}")
+            }
+            Self::TrailingData { offset } => write!(f, "trailing data after item at byte {offset}"),
+            Self::DepthExceeded { offset } => {
+                write!(f, "maximum nesting depth exceeded at byte {offset}")
+            }
+            Self::LengthExceeded { offset } => {
+                write!(f, "maximum byte string length exceeded at byte {offset}")
+            }
+            Self::ItemTooLong { max_len } => {
+                write!(f, "item is longer than the maximum of {max_len} bytes")
+            }
+            Self::NonCanonical { diff_offset } => {
+                write!(
+                    f,
+                    "input is not canonical, differing from its encoding at byte {diff_offset}"
+                )
+            }
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "failed to read input: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for BencodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for BencodeError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Represents an error encountered while converting between BEncode and serde data types
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SerdeError {
+    /// Input was not valid BEncode
+    Decode(BencodeError),
+    /// Error reported by a serde implementation, such as a missing field
+    Message(String),
+}
+
+#[cfg(feature = "serde")]
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode: {err}"),
+            Self::Message(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SerdeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            Self::Message(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<BencodeError> for SerdeError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for SerdeError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for SerdeError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+/// Error produced by the parsers, holding the remaining input at the point of failure
+#[derive(Debug, PartialEq)]
+pub(crate) struct DecodeError<'a> {
+    pub input: &'a [u8],
+    pub kind: DecodeErrorKind,
+}
+
+/// Reason a parser failed
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum DecodeErrorKind {
+    /// Generic nom failure, such as an unexpected byte
+    Nom(ErrorKind),
+    InvalidInteger,
+    NonUtf8Key,
+    /// Input points at the repeated key
+    DuplicateKey,
+    /// Input points at the first key out of order
+    UnsortedKeys,
+    TrailingData,
+    DepthExceeded,
+    LengthExceeded,
+}
+
+impl<'a> DecodeError<'a> {
+    pub fn new(input: &'a [u8], kind: DecodeErrorKind) -> Self {
+        Self { input, kind }
+    }
+}
+
+impl<'a> ParseError<&'a [u8]> for DecodeError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        Self::new(input, DecodeErrorKind::Nom(kind))
+    }
+
+    fn append(_input: &'a [u8], _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a> FromExternalError<&'a [u8], Utf8Error> for DecodeError<'a> {
+    fn from_external_error(input: &'a [u8], _kind: ErrorKind, _err: Utf8Error) -> Self {
+        Self::new(input, DecodeErrorKind::NonUtf8Key)
+    }
+}
+
+impl<'a> FromExternalError<&'a [u8], ParseIntError> for DecodeError<'a> {
+    fn from_external_error(input: &'a [u8], _kind: ErrorKind, _err: ParseIntError) -> Self {
+        Self::new(input, DecodeErrorKind::InvalidInteger)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::{borrow::ToOwned, string::ToString};
+    #[cfg(feature = "std")]
+    use core::error::Error;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (
+                BencodeError::UnexpectedEof { offset: 1 },
+                "unexpected end of input at byte 1",
+            ),
+            (
+                BencodeError::InvalidInteger { offset: 2 },
+                "invalid integer at byte 2",
+            ),
+            (
+                BencodeError::InvalidItem { offset: 3 },
+                "invalid item at byte 3",
+            ),
+            (
+                BencodeError::NonUtf8Key { offset: 4 },
+                "dictionary key is not valid UTF-8 at byte 4",
+            ),
+            (
+                BencodeError::DuplicateKey {
+                    key: "spam".to_owned(),
+                    offset: 5,
+                },
+                "duplicate dictionary key \"spam\" at byte 5",
+            ),
+            (
+                BencodeError::UnsortedKeys { offset: 6 },
+                "dictionary keys are not sorted at byte 6",
+            ),
+            (
+                BencodeError::TrailingData { offset: 7 },
+                "trailing data after item at byte 7",
+            ),
+            (
+                BencodeError::DepthExceeded { offset: 8 },
+                "maximum nesting depth exceeded at byte 8",
+            ),
+            (
+                BencodeError::LengthExceeded { offset: 9 },
+                "maximum byte string length exceeded at byte 9",
+            ),
+            (
+                BencodeError::ItemTooLong { max_len: 11 },
+                "item is longer than the maximum of 11 bytes",
+            ),
+            (
+                BencodeError::NonCanonical { diff_offset: 10 },
+                "input is not canonical, differing from its encoding at byte 10",
+            ),
+            #[cfg(feature = "std")]
+            (
+                BencodeError::Io(std::io::Error::other("disk on fire")),
+                "failed to read input: disk on fire",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_display_with_context() {
+        let input = b"d4:spami1x2ee";
+        let err = super::super::BEncoding::decode(input).unwrap_err();
+
+        assert_eq!(
+            err.display_with_context(input).to_string(),
+            "invalid integer at byte 9\n\
+             00000000  64 34 3a 73 70 61 6d 69  31 78 32 65 65           |d4:spami1x2ee|\n\
+             \x20                                     ^^                              ^"
+        );
+    }
+
+    #[test]
+    fn test_at_offset() {
+        assert_eq!(BencodeError::InvalidItem { offset: 3 }.at_offset(), Some(3));
+        assert_eq!(
+            BencodeError::NonCanonical { diff_offset: 10 }.at_offset(),
+            Some(10)
+        );
+
+        // the length limit of a stream decoder is not a position in the input
+        let err = BencodeError::ItemTooLong { max_len: 11 };
+        assert_eq!(err.at_offset(), None);
+        assert_eq!(
+            err.display_with_context(b"d4:spami1ee").to_string(),
+            "item is longer than the maximum of 11 bytes"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_source() {
+        assert!(BencodeError::InvalidItem { offset: 0 }.source().is_none());
+
+        let err = super::super::BEncoding::decode_path("../missing.torrent").unwrap_err();
+        assert!(err.source().unwrap().is::<std::io::Error>());
+
+        let boxed: Box<dyn Error> = Box::new(err);
+        assert!(boxed.to_string().starts_with("failed to read input: "));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_display() {
+        let err = SerdeError::Decode(BencodeError::InvalidItem { offset: 0 });
+        assert_eq!(err.to_string(), "failed to decode: invalid item at byte 0");
+        assert!(err.source().unwrap().is::<BencodeError>());
+
+        let err = SerdeError::Message("missing field `name`".to_owned());
+        assert_eq!(err.to_string(), "missing field `name`");
+        assert!(err.source().is_none());
+    }
+}
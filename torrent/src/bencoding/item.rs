@@ -0,0 +1,607 @@
+use alloc::{string::String, vec::Vec};
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    ops::Index,
+};
+
+use super::{Dictionary, Item, RawDictionary};
+
+/// Key used to look up a child of an item, either a dictionary key or a list index
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Key<'a> {
+    Dictionary(&'a str),
+    List(usize),
+}
+
+impl<'a> From<&'a str> for Key<'a> {
+    fn from(key: &'a str) -> Self {
+        Key::Dictionary(key)
+    }
+}
+
+impl From<usize> for Key<'_> {
+    fn from(index: usize) -> Self {
+        Key::List(index)
+    }
+}
+
+impl Item {
+    /// Looks up a dictionary key or list index, returning None if missing or the key doesn't match the item type
+    pub fn get<'a>(&self, key: impl Into<Key<'a>>) -> Option<&Item> {
+        match (self, key.into()) {
+            (Item::Dictionary(dict), Key::Dictionary(key)) => dict.get(key),
+            (Item::RawDictionary(dict), Key::Dictionary(key)) => dict.get(key.as_bytes()),
+            (Item::List(items), Key::List(index)) => items.get(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if the item is an integer
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Item::Integer(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes if the item is a byte array
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Item::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes as a string if the item is a byte array containing valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    /// Returns the elements if the item is a list
+    pub fn as_list(&self) -> Option<&[Item]> {
+        match self {
+            Item::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if the item is a dictionary
+    pub fn as_dict(&self) -> Option<&Dictionary> {
+        match self {
+            Item::Dictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if the item is a dictionary with keys that are not valid UTF-8
+    pub fn as_raw_dict(&self) -> Option<&RawDictionary> {
+        match self {
+            Item::RawDictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for Item {
+    fn from(number: i64) -> Self {
+        Item::Integer(number)
+    }
+}
+
+impl From<&str> for Item {
+    fn from(string: &str) -> Self {
+        Item::ByteArray(string.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Item {
+    fn from(string: String) -> Self {
+        Item::ByteArray(string.into_bytes())
+    }
+}
+
+impl From<&[u8]> for Item {
+    fn from(bytes: &[u8]) -> Self {
+        Item::ByteArray(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for Item {
+    fn from(bytes: Vec<u8>) -> Self {
+        Item::ByteArray(bytes)
+    }
+}
+
+impl From<Vec<Item>> for Item {
+    fn from(items: Vec<Item>) -> Self {
+        Item::List(items)
+    }
+}
+
+impl From<Dictionary> for Item {
+    fn from(dict: Dictionary) -> Self {
+        Item::Dictionary(dict)
+    }
+}
+
+impl Index<&str> for Item {
+    type Output = Item;
+
+    /// Looks up a key in a dictionary.
+    ///
+    /// # Panics
+    /// Panics if the item is not a dictionary, or the key is missing
+    fn index(&self, key: &str) -> &Self::Output {
+        match self {
+            Item::Dictionary(_) | Item::RawDictionary(_) => self
+                .get(key)
+                .unwrap_or_else(|| panic!("key {key:?} not found in dictionary")),
+            _ => panic!("cannot index into non-dictionary with key {key:?}"),
+        }
+    }
+}
+
+impl Index<usize> for Item {
+    type Output = Item;
+
+    /// Looks up an element in a list.
+    ///
+    /// # Panics
+    /// Panics if the item is not a list, or the index is out of bounds
+    fn index(&self, index: usize) -> &Self::Output {
+        match self {
+            Item::List(items) => items.get(index).unwrap_or_else(|| {
+                panic!(
+                    "index {index} out of bounds for list of length {}",
+                    items.len()
+                )
+            }),
+            _ => panic!("cannot index into non-list with index {index}"),
+        }
+    }
+}
+
+impl Item {
+    /// Position of the variant in the total order, which follows declaration order
+    fn rank(&self) -> u8 {
+        match self {
+            Item::ByteArray(_) => 0,
+            Item::Integer(_) => 1,
+            Item::Dictionary(_) => 2,
+            Item::RawDictionary(_) => 3,
+            Item::List(_) => 4,
+        }
+    }
+}
+
+/// Returns the entries of a dictionary sorted by the raw bytes of their keys
+fn sorted_entries<'a, K: AsRef<[u8]> + 'a>(
+    entries: impl Iterator<Item = (&'a K, &'a Item)>,
+) -> Vec<(&'a [u8], &'a Item)> {
+    let mut entries: Vec<_> = entries.map(|(key, value)| (key.as_ref(), value)).collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+
+    entries
+}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Items are ordered by variant in declaration order, then by content.
+///
+/// Byte arrays compare by raw byte value, integers numerically, lists element by element,
+/// and dictionaries as their entries sorted by key, so the order never depends on insertion order
+impl Ord for Item {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Item::ByteArray(a), Item::ByteArray(b)) => a.cmp(b),
+            (Item::Integer(a), Item::Integer(b)) => a.cmp(b),
+            (Item::List(a), Item::List(b)) => a.cmp(b),
+            (Item::Dictionary(a), Item::Dictionary(b)) => {
+                sorted_entries(a.iter()).cmp(&sorted_entries(b.iter()))
+            }
+            (Item::RawDictionary(a), Item::RawDictionary(b)) => {
+                sorted_entries(a.iter()).cmp(&sorted_entries(b.iter()))
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// Dictionaries are hashed as their entries sorted by key, so equal dictionaries hash the same whatever order they iterate in
+impl Hash for Item {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+
+        match self {
+            Item::ByteArray(bytes) => bytes.hash(state),
+            Item::Integer(integer) => integer.hash(state),
+            Item::List(items) => items.hash(state),
+            Item::Dictionary(dict) => sorted_entries(dict.iter()).hash(state),
+            Item::RawDictionary(dict) => sorted_entries(dict.iter()).hash(state),
+        }
+    }
+}
+
+impl Item {
+    /// Overlays another item onto this one, as when patching a torrent.
+    ///
+    /// When both are dictionaries of the same kind, each key of `other` is merged into the matching value here,
+    /// recursing through nested dictionaries, and keys only in `other` are added.
+    /// Any other item, including a list, replaces this one wholesale rather than being combined with it
+    pub fn merge(&mut self, other: &Item) {
+        match (self, other) {
+            (Item::Dictionary(dict), Item::Dictionary(other)) => {
+                for (key, value) in other {
+                    match dict.get_mut(key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            dict.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (Item::RawDictionary(dict), Item::RawDictionary(other)) => {
+                for (key, value) in other {
+                    match dict.get_mut(key) {
+                        Some(existing) => existing.merge(value),
+                        None => {
+                            dict.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (this, other) => *this = other.clone(),
+        }
+    }
+
+    /// Compares items by content, ignoring the order dictionary keys were inserted in at any depth.
+    ///
+    /// Unlike `==`, a [`Item::RawDictionary`] equals a [`Item::Dictionary`] with the same keys, so a re-encode can be checked
+    /// for equivalence however the dictionaries were decoded
+    pub fn eq_unordered(&self, other: &Item) -> bool {
+        fn entries(item: &Item) -> Option<Vec<(&[u8], &Item)>> {
+            match item {
+                Item::Dictionary(dict) => Some(sorted_entries(dict.iter())),
+                Item::RawDictionary(dict) => Some(sorted_entries(dict.iter())),
+                _ => None,
+            }
+        }
+
+        match (self, other) {
+            (Item::List(a), Item::List(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.eq_unordered(b))
+            }
+            _ => match (entries(self), entries(other)) {
+                (Some(a), Some(b)) => {
+                    a.len() == b.len()
+                        && a.iter()
+                            .zip(&b)
+                            .all(|((a_key, a), (b_key, b))| a_key == b_key && a.eq_unordered(b))
+                }
+                _ => self == other,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::{borrow::ToOwned, string::ToString, vec};
+
+    #[test]
+    fn test_accessors() {
+        let integer = Item::Integer(-3);
+        let bytes = Item::ByteArray(b"spam".to_vec());
+        let list = Item::List(vec![Item::Integer(1)]);
+        let dict = Item::Dictionary(Dictionary::from([("cow".to_owned(), Item::Integer(1))]));
+
+        assert_eq!(integer.as_integer(), Some(-3));
+        assert_eq!(bytes.as_integer(), None);
+
+        assert_eq!(bytes.as_bytes(), Some(&b"spam"[..]));
+        assert_eq!(integer.as_bytes(), None);
+
+        assert_eq!(list.as_list(), Some(&[Item::Integer(1)][..]));
+        assert_eq!(dict.as_list(), None);
+
+        assert_eq!(dict.as_dict().unwrap().get("cow"), Some(&Item::Integer(1)));
+        assert_eq!(list.as_dict(), None);
+
+        let raw = Item::RawDictionary(RawDictionary::from([(vec![0xff], Item::Integer(1))]));
+        assert_eq!(
+            raw.as_raw_dict().unwrap().get(&vec![0xff]),
+            Some(&Item::Integer(1))
+        );
+        assert_eq!(dict.as_raw_dict(), None);
+    }
+
+    #[test]
+    fn test_as_str() {
+        assert_eq!(Item::ByteArray(b"spam".to_vec()).as_str(), Some("spam"));
+        assert_eq!(Item::ByteArray("ünïcode".into()).as_str(), Some("ünïcode"));
+        assert_eq!(Item::ByteArray(vec![0xff, 0xfe]).as_str(), None);
+        assert_eq!(Item::Integer(1).as_str(), None);
+    }
+
+    #[test]
+    fn test_from() {
+        assert_eq!(Item::from(-3), Item::Integer(-3));
+        assert_eq!(Item::from("spam"), Item::ByteArray(b"spam".to_vec()));
+        assert_eq!(
+            Item::from("spam".to_owned()),
+            Item::ByteArray(b"spam".to_vec())
+        );
+        assert_eq!(
+            Item::from(&[0xff, 0x00][..]),
+            Item::ByteArray(vec![0xff, 0x00])
+        );
+        assert_eq!(
+            Item::from(vec![0xff, 0x00]),
+            Item::ByteArray(vec![0xff, 0x00])
+        );
+        assert_eq!(
+            Item::from(vec![Item::from(1), Item::from("a")]),
+            Item::List(vec![Item::Integer(1), Item::ByteArray(b"a".to_vec())])
+        );
+        assert_eq!(
+            Item::from(Dictionary::from([("cow".to_owned(), Item::from("moo"))])).encode(),
+            b"d3:cow3:mooe"
+        );
+    }
+
+    /// Builds `{"info": {"length": 20, "files": [{"length": 10}]}}`
+    fn nested() -> Item {
+        Item::Dictionary(Dictionary::from([(
+            "info".to_owned(),
+            Item::Dictionary(Dictionary::from([
+                ("length".to_owned(), Item::Integer(20)),
+                (
+                    "files".to_owned(),
+                    Item::List(vec![Item::Dictionary(Dictionary::from([(
+                        "length".to_owned(),
+                        Item::Integer(10),
+                    )]))]),
+                ),
+            ])),
+        )]))
+    }
+
+    #[test]
+    fn test_index() {
+        let item = nested();
+
+        assert_eq!(item["info"]["length"], Item::Integer(20));
+        assert_eq!(item["info"]["files"][0]["length"], Item::Integer(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "key \"missing\" not found in dictionary")]
+    fn test_index_missing_key() {
+        let _ = &nested()["info"]["missing"];
+    }
+
+    #[test]
+    #[should_panic(expected = "index 1 out of bounds for list of length 1")]
+    fn test_index_out_of_bounds() {
+        let _ = &nested()["info"]["files"][1];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into non-dictionary")]
+    fn test_index_wrong_type() {
+        let _ = &nested()["info"]["length"]["value"];
+    }
+
+    #[test]
+    fn test_get() {
+        let item = nested();
+
+        assert_eq!(
+            item.get("info").and_then(|info| info.get("length")),
+            Some(&Item::Integer(20))
+        );
+
+        let length = || item.get("info")?.get("files")?.get(0)?.get("length");
+        assert_eq!(length(), Some(&Item::Integer(10)));
+
+        assert_eq!(item.get("missing"), None);
+        assert_eq!(item.get(0), None);
+        assert_eq!(item["info"]["files"].get("length"), None);
+        assert_eq!(item["info"]["files"].get(1), None);
+        assert_eq!(item["info"]["length"].get(0), None);
+    }
+
+    #[test]
+    fn test_accessor_chain() {
+        let item = Item::Dictionary(Dictionary::from([(
+            "announce".to_owned(),
+            Item::ByteArray(b"http://tracker".to_vec()),
+        )]));
+
+        let announce = || item.as_dict()?.get("announce")?.as_bytes();
+        assert_eq!(announce(), Some(&b"http://tracker"[..]));
+    }
+
+    #[test]
+    fn test_ord_bytes() {
+        // "é" is 0xc3 0xa9 and "z" is 0x7a, so raw bytes put "z" first
+        assert!(Item::from("z") < Item::from("é"));
+        assert!(Item::from("B") < Item::from("a"));
+        assert!(Item::from(&[0x00, 0xff][..]) < Item::from(&[0x01][..]));
+        assert!(Item::from("spam") < Item::from("spams"));
+        assert!(Item::from("") < Item::from("a"));
+    }
+
+    #[test]
+    fn test_ord() {
+        assert!(Item::Integer(-10) < Item::Integer(2));
+        assert!(Item::from("zzz") < Item::Integer(i64::MIN));
+        assert!(Item::Integer(i64::MAX) < Item::Dictionary(Dictionary::new()));
+        assert!(Item::Dictionary(Dictionary::new()) < Item::List(vec![]));
+        assert!(Item::List(vec![Item::Integer(1)]) < Item::List(vec![Item::Integer(2)]));
+        assert!(Item::List(vec![Item::Integer(1)]) < Item::List(vec![1.into(), 0.into()]));
+
+        let a = Item::Dictionary(Dictionary::from([
+            ("b".to_owned(), Item::Integer(1)),
+            ("a".to_owned(), Item::Integer(2)),
+        ]));
+        let b = Item::Dictionary(Dictionary::from([
+            ("a".to_owned(), Item::Integer(2)),
+            ("b".to_owned(), Item::Integer(1)),
+        ]));
+        let c = Item::Dictionary(Dictionary::from([("a".to_owned(), Item::Integer(3))]));
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert!(a < c);
+
+        let mut items = vec![
+            Item::List(vec![]),
+            Item::Integer(3),
+            Item::from("b"),
+            Item::Integer(-3),
+            Item::from("a"),
+        ];
+        items.sort();
+        assert_eq!(
+            items,
+            [
+                Item::from("a"),
+                Item::from("b"),
+                Item::Integer(-3),
+                Item::Integer(3),
+                Item::List(vec![])
+            ]
+        );
+
+        let set: alloc::collections::BTreeSet<_> =
+            [Item::Integer(1), Item::Integer(1), Item::from("x")].into();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_eq_unordered() {
+        let forwards = Item::Dictionary(Dictionary::from_iter([
+            ("a".to_owned(), Item::Integer(1)),
+            ("b".to_owned(), Item::List(vec![Item::from("x")])),
+        ]));
+        let backwards = Item::Dictionary(Dictionary::from_iter([
+            ("b".to_owned(), Item::List(vec![Item::from("x")])),
+            ("a".to_owned(), Item::Integer(1)),
+        ]));
+        assert!(forwards.eq_unordered(&backwards));
+
+        // the same entries nested in lists in a different order
+        let nested = |dict: &Item| Item::List(vec![Item::Integer(0), dict.clone()]);
+        assert!(nested(&forwards).eq_unordered(&nested(&backwards)));
+
+        let raw = Item::RawDictionary(RawDictionary::from_iter([
+            (b"b".to_vec(), Item::List(vec![Item::from("x")])),
+            (b"a".to_vec(), Item::Integer(1)),
+        ]));
+        assert!(raw.eq_unordered(&forwards));
+        assert!(forwards.eq_unordered(&raw));
+        assert_ne!(raw, forwards);
+
+        let changed = Item::Dictionary(Dictionary::from_iter([
+            ("a".to_owned(), Item::Integer(2)),
+            ("b".to_owned(), Item::List(vec![Item::from("x")])),
+        ]));
+        assert!(!forwards.eq_unordered(&changed));
+        assert!(!forwards.eq_unordered(&Item::Dictionary(Dictionary::new())));
+        assert!(!nested(&forwards).eq_unordered(&Item::List(vec![Item::Integer(0)])));
+        assert!(!Item::Integer(1).eq_unordered(&Item::from("1")));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hash() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        for i in 0..10 {
+            // insert the same entries in a different order each time
+            let dict: Dictionary = (0..10)
+                .map(|j| (i + j) % 10)
+                .map(|j| (format!("key{j}"), Item::List(vec![Item::Integer(j)])))
+                .collect();
+            set.insert(Item::Dictionary(dict));
+        }
+        assert_eq!(set.len(), 1);
+
+        set.insert(Item::Dictionary(Dictionary::new()));
+        set.insert(Item::RawDictionary(RawDictionary::new()));
+        set.insert(Item::List(vec![]));
+        set.insert(Item::from(""));
+        assert_eq!(set.len(), 5);
+    }
+
+    #[test]
+    fn test_merge() {
+        let dict = |entries: &[(&str, Item)]| {
+            Item::Dictionary(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.clone()))
+                    .collect(),
+            )
+        };
+
+        let mut torrent = dict(&[
+            ("announce", Item::from("udp://a")),
+            ("announce-list", Item::List(vec![Item::from("udp://a")])),
+            (
+                "info",
+                dict(&[("name", Item::from("old")), ("length", Item::Integer(1))]),
+            ),
+        ]);
+        torrent.merge(&dict(&[
+            ("announce-list", Item::List(vec![Item::from("udp://b")])),
+            ("comment", Item::from("patched")),
+            ("info", dict(&[("name", Item::from("new"))])),
+        ]));
+
+        assert_eq!(
+            torrent,
+            dict(&[
+                ("announce", Item::from("udp://a")),
+                // lists are replaced rather than concatenated
+                ("announce-list", Item::List(vec![Item::from("udp://b")])),
+                ("comment", Item::from("patched")),
+                (
+                    "info",
+                    dict(&[("name", Item::from("new")), ("length", Item::Integer(1))])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_merge_replace() {
+        let mut item = Item::Dictionary(Dictionary::from_iter([(
+            "a".to_owned(),
+            Item::Dictionary(Dictionary::from_iter([("b".to_owned(), Item::Integer(1))])),
+        )]));
+
+        item.merge(&Item::Dictionary(Dictionary::from_iter([(
+            "a".to_owned(),
+            Item::Integer(2),
+        )])));
+        assert_eq!(item["a"], Item::Integer(2));
+
+        item.merge(&Item::List(vec![Item::Integer(3)]));
+        assert_eq!(item, Item::List(vec![Item::Integer(3)]));
+
+        let mut raw =
+            Item::RawDictionary(RawDictionary::from_iter([(vec![0xff], Item::Integer(1))]));
+        raw.merge(&Item::RawDictionary(RawDictionary::from_iter([(
+            vec![0xfe],
+            Item::Integer(2),
+        )])));
+        assert_eq!(raw.as_raw_dict().map(|dict| dict.len()), Some(2));
+    }
+}
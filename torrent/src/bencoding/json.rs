@@ -0,0 +1,74 @@
+use serde_json::{Map, Value};
+
+use super::Item;
+use crate::hex;
+
+/// Converts bytes to a string if valid UTF-8, and to lowercase hex otherwise
+fn bytes_to_string(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => string.to_owned(),
+        Err(_) => hex::encode(bytes),
+    }
+}
+
+impl Item {
+    /// Converts the item to a JSON value for debugging, with byte arrays that are not valid UTF-8 rendered as hex strings.
+    ///
+    /// This is lossy, as a hex string can't be distinguished from a string that happens to contain hex
+    pub fn to_json(&self) -> Value {
+        match self {
+            Item::ByteArray(bytes) => Value::String(bytes_to_string(bytes)),
+            Item::Integer(number) => Value::from(*number),
+            Item::List(items) => Value::Array(items.iter().map(Item::to_json).collect()),
+            Item::Dictionary(dict) => Value::Object(
+                dict.iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect::<Map<_, _>>(),
+            ),
+            Item::RawDictionary(dict) => Value::Object(
+                dict.iter()
+                    .map(|(key, value)| (bytes_to_string(key), value.to_json()))
+                    .collect::<Map<_, _>>(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::bencoding::{BEncoding, Dictionary, RawDictionary};
+
+    #[test]
+    fn test_to_json() {
+        let item = Item::Dictionary(Dictionary::from([
+            ("spam".to_owned(), Item::List(vec![Item::Integer(-1)])),
+            ("cow".to_owned(), Item::ByteArray(b"moo".to_vec())),
+        ]));
+        assert_eq!(item.to_json(), json!({"cow": "moo", "spam": [-1]}));
+
+        let raw = Item::RawDictionary(RawDictionary::from([(
+            vec![0xff, 0x01],
+            Item::ByteArray(vec![0x00, 0xfe]),
+        )]));
+        assert_eq!(raw.to_json(), json!({"ff01": "00fe"}));
+    }
+
+    #[test]
+    fn test_to_json_torrent() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        let (torrent, _) = BEncoding::decode_with_remainder(&data).unwrap();
+        let json = torrent.to_json();
+
+        assert_eq!(json["announce"], "udp://tracker.openbittorrent.com:80");
+        assert_eq!(json["info"]["length"], 20);
+
+        let pieces = torrent["info"]["pieces"].as_bytes().unwrap();
+        let hex: String = pieces.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(json["info"]["pieces"], hex);
+
+        assert!(serde_json::to_string_pretty(&json).is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+use alloc::{borrow::ToOwned, vec::Vec};
+
+use nom::{error::ErrorKind, Finish};
+
+use super::{
+    error::{DecodeError, DecodeErrorKind},
+    parse_bytearray, parse_integer, BEncoding, BencodeError, Dictionary, Item,
+};
+
+/// Parser which records errors and skips malformed entries rather than stopping at the first one
+struct Lenient<'a> {
+    /// Full input, used to find the offset of errors
+    input: &'a [u8],
+    errors: Vec<BencodeError>,
+}
+
+impl<'a> Lenient<'a> {
+    /// Records an error, ignoring repeated reports of the input ending at the same point
+    fn record(&mut self, err: DecodeError<'a>) {
+        let err = BencodeError::from_decode(err, self.input);
+
+        if let (
+            Some(BencodeError::UnexpectedEof { offset: previous }),
+            BencodeError::UnexpectedEof { offset },
+        ) = (self.errors.last(), &err)
+        {
+            if previous == offset {
+                return;
+            }
+        }
+
+        self.errors.push(err);
+    }
+
+    /// Records an error and returns the input to resume from, which is the end of the input if it was truncated
+    fn recover(&mut self, err: DecodeError<'a>, input: &'a [u8], skip: usize) -> &'a [u8] {
+        let truncated = err.input.is_empty() || err.kind == DecodeErrorKind::Nom(ErrorKind::Eof);
+        self.record(err);
+
+        if truncated {
+            &input[input.len()..]
+        } else {
+            &input[skip.min(input.len())..]
+        }
+    }
+
+    /// Parses one item, returning None if it was malformed and had to be skipped
+    fn item(&mut self, input: &'a [u8], depth: usize) -> (&'a [u8], Option<Item>) {
+        match input.first() {
+            None => {
+                self.record(DecodeError::new(
+                    input,
+                    DecodeErrorKind::Nom(ErrorKind::Eof),
+                ));
+                (input, None)
+            }
+            Some(b'i') => match parse_integer(input).finish() {
+                Ok((remaining, number)) => (remaining, Some(Item::Integer(number))),
+                // resume after the end code of the broken integer
+                Err(err) => {
+                    let skip = input
+                        .iter()
+                        .position(|&byte| byte == b'e')
+                        .map_or(input.len(), |end| end + 1);
+                    (self.recover(err, input, skip), None)
+                }
+            },
+            Some(b'l' | b'd') if depth == 0 => {
+                // the extent of the container is unknown without parsing it, so nothing after it can be recovered
+                self.record(DecodeError::new(
+                    &input[1..],
+                    DecodeErrorKind::DepthExceeded,
+                ));
+                (&input[input.len()..], None)
+            }
+            Some(b'l') => {
+                let (remaining, items) = self.list(&input[1..], depth - 1);
+                (remaining, Some(Item::List(items)))
+            }
+            Some(b'd') => {
+                let (remaining, dict) = self.dictionary(&input[1..], depth - 1);
+                (remaining, Some(Item::Dictionary(dict)))
+            }
+            Some(_) => match parse_bytearray(input).finish() {
+                Ok((remaining, bytes)) => (remaining, Some(Item::ByteArray(bytes.to_owned()))),
+                Err(err) => (self.recover(err, input, 1), None),
+            },
+        }
+    }
+
+    /// Parses the elements of a list after its start code, keeping the elements before an early end of input
+    fn list(&mut self, mut input: &'a [u8], depth: usize) -> (&'a [u8], Vec<Item>) {
+        let mut items = Vec::new();
+
+        loop {
+            match input.first() {
+                Some(b'e') => return (&input[1..], items),
+                None => {
+                    self.record(DecodeError::new(
+                        input,
+                        DecodeErrorKind::Nom(ErrorKind::Eof),
+                    ));
+                    return (input, items);
+                }
+                Some(_) => {
+                    let (remaining, item) = self.item(input, depth);
+                    input = remaining;
+                    items.extend(item);
+                }
+            }
+        }
+    }
+
+    /// Parses the entries of a dict after its start code, skipping entries with malformed keys or values
+    fn dictionary(&mut self, mut input: &'a [u8], depth: usize) -> (&'a [u8], Dictionary) {
+        let mut dict = Dictionary::new();
+
+        loop {
+            match input.first() {
+                Some(b'e') => return (&input[1..], dict),
+                None => {
+                    self.record(DecodeError::new(
+                        input,
+                        DecodeErrorKind::Nom(ErrorKind::Eof),
+                    ));
+                    return (input, dict);
+                }
+                Some(_) => {}
+            }
+
+            let (remaining, key) = match parse_bytearray(input).finish() {
+                Ok(parsed) => parsed,
+                Err(err) => {
+                    input = self.recover(err, input, 1);
+                    continue;
+                }
+            };
+
+            let (remaining, value) = self.item(remaining, depth);
+            match (core::str::from_utf8(key), value) {
+                (Ok(key), Some(value)) => {
+                    dict.insert(key.to_owned(), value);
+                }
+                (Err(_), _) => self.record(DecodeError::new(key, DecodeErrorKind::NonUtf8Key)),
+                (Ok(_), None) => {}
+            }
+
+            input = remaining;
+        }
+    }
+}
+
+impl BEncoding {
+    /// Decodes a byte array, skipping malformed items inside lists and dictionaries instead of failing.
+    ///
+    /// Returns every item that could be recovered along with the errors encountered. Broken integers are skipped up to
+    /// their end code, other unexpected bytes one at a time, and containers cut short by the end of the input keep the
+    /// entries before it
+    pub fn decode_lenient(bytes: &[u8]) -> (Self, Vec<BencodeError>) {
+        let mut parser = Lenient {
+            input: bytes,
+            errors: Vec::new(),
+        };
+
+        let mut items = Vec::new();
+        let mut input = bytes;
+        loop {
+            let (remaining, item) = parser.item(input, Self::DEFAULT_MAX_DEPTH);
+            items.extend(item);
+            input = remaining;
+
+            if input.is_empty() {
+                break;
+            }
+        }
+
+        (Self { items }, parser.errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_truncated_value() {
+        let (decoded, errors) = BEncoding::decode_lenient(b"d4:good4:spam3:badi12");
+
+        assert_eq!(decoded.items().len(), 1);
+        assert_eq!(
+            decoded.items()[0],
+            Item::Dictionary(Dictionary::from([("good".to_owned(), Item::from("spam"))]))
+        );
+        assert!(matches!(
+            errors[..],
+            [BencodeError::UnexpectedEof { offset: 21 }]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let (decoded, errors) = BEncoding::decode_lenient(b"d3:badi1x2e4:good4:spame");
+
+        assert_eq!(
+            decoded.items(),
+            [Item::Dictionary(Dictionary::from([(
+                "good".to_owned(),
+                Item::from("spam")
+            )]))]
+        );
+        assert!(matches!(
+            errors[..],
+            [BencodeError::InvalidInteger { offset: 8 }]
+        ));
+
+        let (decoded, errors) = BEncoding::decode_lenient(b"li1e?i2ei03ee");
+        assert_eq!(
+            decoded.items(),
+            [Item::List(vec![Item::Integer(1), Item::Integer(2)])]
+        );
+        assert!(matches!(
+            errors[..],
+            [
+                BencodeError::InvalidItem { offset: 4 },
+                BencodeError::InvalidInteger { offset: 9 }
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_non_utf8_key() {
+        let (decoded, errors) = BEncoding::decode_lenient(b"d1:\xffi1e1:ai2ee");
+
+        assert_eq!(
+            decoded.items(),
+            [Item::Dictionary(Dictionary::from([(
+                "a".to_owned(),
+                Item::Integer(2)
+            )]))]
+        );
+        assert!(matches!(
+            errors[..],
+            [BencodeError::NonUtf8Key { offset: 3 }]
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_valid_input() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        let (decoded, errors) = BEncoding::decode_lenient(&data);
+
+        assert!(errors.is_empty());
+        assert_eq!(decoded.items(), BEncoding::decode(&data).unwrap().items());
+    }
+
+    #[test]
+    fn test_depth() {
+        let nested = "l".repeat(BEncoding::DEFAULT_MAX_DEPTH + 1);
+        let (decoded, errors) = BEncoding::decode_lenient(nested.as_bytes());
+
+        assert_eq!(decoded.items().len(), 1);
+        assert!(matches!(
+            errors[..],
+            [
+                BencodeError::DepthExceeded { .. },
+                BencodeError::UnexpectedEof { .. }
+            ]
+        ));
+
+        let (decoded, errors) = BEncoding::decode_lenient(b"");
+        assert!(decoded.items().is_empty());
+        assert!(matches!(
+            errors[..],
+            [BencodeError::UnexpectedEof { offset: 0 }]
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use super::{BEncoding, BencodeError, ItemRef};
+
+impl BEncoding {
+    /// Decodes a BEncode file by mapping it into memory and parsing the mapped bytes directly,
+    /// so the file is never copied onto the heap as a whole
+    ///
+    /// # Safety
+    /// The file must not be modified, truncated or replaced by this or any other process while it is being decoded,
+    /// as the mapped bytes would change underneath the parser
+    pub unsafe fn decode_mmap(path: impl AsRef<Path>) -> Result<Self, BencodeError> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees the file is not modified while mapped
+        let map = unsafe { Mmap::map(&file)? };
+
+        let items = Self::decode_borrowed(&map)?
+            .iter()
+            .map(ItemRef::to_owned)
+            .collect();
+
+        Ok(Self { items })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_mmap() {
+        for path in [
+            "../sample.torrent",
+            "../archlinux-2022.10.01-x86_64.iso.torrent",
+        ] {
+            let mapped = unsafe { BEncoding::decode_mmap(path) }.unwrap();
+            let read = BEncoding::decode_path(path).unwrap();
+
+            assert_eq!(mapped.items(), read.items());
+        }
+    }
+
+    #[test]
+    fn test_decode_mmap_missing() {
+        assert!(matches!(
+            unsafe { BEncoding::decode_mmap("../missing.torrent") },
+            Err(BencodeError::Io(_))
+        ));
+    }
+}
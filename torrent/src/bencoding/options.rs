@@ -0,0 +1,157 @@
+use alloc::vec;
+
+use super::{parse_bytes, parse_single, BEncoding, BencodeError, Rules};
+
+/// Configures how strictly input is checked while decoding, which is useful for untrusted input.
+///
+/// The defaults match [`BEncoding::decode`], which accepts duplicate and unsorted keys and several top-level items
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DecodeOptions {
+    pub(super) rules: Rules,
+    forbid_trailing_data: bool,
+}
+
+impl DecodeOptions {
+    /// Creates options matching the lenient behaviour of [`BEncoding::decode`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how deeply lists and dictionaries may be nested, which defaults to [`BEncoding::DEFAULT_MAX_DEPTH`]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.rules.depth = max_depth;
+        self
+    }
+
+    /// Sets the longest byte string allowed, including dictionary keys, which is unlimited by default
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.rules.max_length = max_length;
+        self
+    }
+
+    /// Sets whether dictionary keys must be in strictly ascending order, as required for canonical BEncode
+    pub fn require_sorted_keys(mut self, require_sorted_keys: bool) -> Self {
+        self.rules.require_sorted_keys = require_sorted_keys;
+        self
+    }
+
+    /// Sets whether the input must contain exactly one item, rather than a sequence of items
+    pub fn forbid_trailing_data(mut self, forbid_trailing_data: bool) -> Self {
+        self.forbid_trailing_data = forbid_trailing_data;
+        self
+    }
+
+    /// Sets whether dictionaries may contain the same key more than once, keeping the last value if allowed
+    pub fn forbid_duplicate_keys(mut self, forbid_duplicate_keys: bool) -> Self {
+        self.rules.forbid_duplicate_keys = forbid_duplicate_keys;
+        self
+    }
+
+    /// Sets whether dictionaries with keys that are not valid UTF-8 are kept as an [`super::Item::RawDictionary`]
+    pub fn allow_raw_keys(mut self, allow_raw_keys: bool) -> Self {
+        self.rules.allow_raw_keys = allow_raw_keys;
+        self
+    }
+
+    /// Decodes a byte array using these options
+    pub fn decode(&self, bytes: &[u8]) -> Result<BEncoding, BencodeError> {
+        let items = if self.forbid_trailing_data {
+            vec![parse_single(bytes, self.rules)?]
+        } else {
+            parse_bytes(bytes, self.rules)?
+        };
+
+        Ok(BEncoding { items })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let decoded = DecodeOptions::new()
+            .decode(b"d1:bi1e1:ai2e1:ai3eei4e")
+            .unwrap();
+
+        assert_eq!(decoded.items().len(), 2);
+        assert_eq!(
+            decoded.items()[0].get("a"),
+            Some(&super::super::Item::Integer(3))
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let options = DecodeOptions::new().max_depth(2);
+
+        assert!(options.decode(b"llee").is_ok());
+        assert!(matches!(
+            options.decode(b"llleee"),
+            Err(BencodeError::DepthExceeded { offset: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_max_length() {
+        let options = DecodeOptions::new().max_length(4);
+
+        assert!(options.decode(b"4:spam").is_ok());
+        assert!(matches!(
+            options.decode(b"l5:spamse"),
+            Err(BencodeError::LengthExceeded { offset: 1 })
+        ));
+        assert!(matches!(
+            options.decode(b"d5:spamsi1ee"),
+            Err(BencodeError::LengthExceeded { offset: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_require_sorted_keys() {
+        let options = DecodeOptions::new().require_sorted_keys(true);
+
+        assert!(options.decode(b"d1:ai1e1:bi2ee").is_ok());
+        assert!(matches!(
+            options.decode(b"d1:bi1e1:ai2ee"),
+            Err(BencodeError::UnsortedKeys { offset: 9 })
+        ));
+    }
+
+    #[test]
+    fn test_forbid_trailing_data() {
+        let options = DecodeOptions::new().forbid_trailing_data(true);
+
+        assert!(options.decode(b"i1e").is_ok());
+        assert!(matches!(
+            options.decode(b"i1ei2e"),
+            Err(BencodeError::TrailingData { offset: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_forbid_duplicate_keys() {
+        let options = DecodeOptions::new().forbid_duplicate_keys(true);
+
+        assert!(options.decode(b"d1:ai1e1:bi2ee").is_ok());
+        assert!(matches!(
+            options.decode(b"d1:ai1e1:ai2ee"),
+            Err(BencodeError::DuplicateKey { offset: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn test_allow_raw_keys() {
+        let options = DecodeOptions::new().allow_raw_keys(true);
+
+        assert!(matches!(
+            options.decode(b"d1:\xffi1ee").unwrap().items()[0],
+            super::super::Item::RawDictionary(_)
+        ));
+        assert!(matches!(
+            DecodeOptions::new().decode(b"d1:\xffi1ee"),
+            Err(BencodeError::NonUtf8Key { .. })
+        ));
+    }
+}
@@ -0,0 +1,690 @@
+use serde::{
+    ser::{self, Impossible},
+    Serialize,
+};
+
+use super::{Dictionary, Item, RawDictionary, SerdeError};
+
+/// Serializes a value into canonical BEncode, with dictionary keys sorted even with the `ordered` feature
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, SerdeError> {
+    Ok(to_item(value)?.encode())
+}
+
+/// Serializes a value into an item, failing if the value has no bencode representation
+fn to_item<T: Serialize + ?Sized>(value: &T) -> Result<Item, SerdeError> {
+    value.serialize(ItemSerializer)?.ok_or_else(|| {
+        <SerdeError as ser::Error>::custom("cannot serialize None outside of a dictionary")
+    })
+}
+
+/// Builds an item from a serde data type.
+///
+/// Produces `None` for values that bencode can't represent, such as `Option::None`, so they can be omitted from dictionaries
+struct ItemSerializer;
+
+fn unsupported(kind: &str) -> SerdeError {
+    <SerdeError as ser::Error>::custom(format!("bencode cannot represent {kind}"))
+}
+
+fn integer<T: TryInto<i64> + std::fmt::Display + Copy>(
+    value: T,
+) -> Result<Option<Item>, SerdeError> {
+    value
+        .try_into()
+        .map(|number| Some(Item::Integer(number)))
+        .map_err(|_| {
+            <SerdeError as ser::Error>::custom(format!("integer {value} does not fit in an i64"))
+        })
+}
+
+impl ser::Serializer for ItemSerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = DictionarySerializer;
+    type SerializeStruct = DictionarySerializer;
+    type SerializeStructVariant = DictionarySerializer;
+
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok, SerdeError> {
+        integer(value as i64)
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_i128(self, value: i128) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_u128(self, value: u128) -> Result<Self::Ok, SerdeError> {
+        integer(value)
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<Self::Ok, SerdeError> {
+        Err(unsupported("floating point numbers"))
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<Self::Ok, SerdeError> {
+        Err(unsupported("floating point numbers"))
+    }
+
+    fn serialize_char(self, value: char) -> Result<Self::Ok, SerdeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok, SerdeError> {
+        self.serialize_bytes(value.as_bytes())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(Item::ByteArray(value.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, SerdeError> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, SerdeError> {
+        Err(unsupported("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, SerdeError> {
+        Err(unsupported("unit structs"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        let mut dict = Dictionary::new();
+        dict.insert(variant.to_string(), to_item(value)?);
+
+        Ok(Some(Item::Dictionary(dict)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, SerdeError> {
+        Ok(ListSerializer {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, SerdeError> {
+        Ok(ListSerializer {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<DictionarySerializer, SerdeError> {
+        Ok(DictionarySerializer {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<DictionarySerializer, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<DictionarySerializer, SerdeError> {
+        Ok(DictionarySerializer {
+            entries: Vec::with_capacity(len),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Wraps an item in a single-key dictionary if it is the contents of an enum variant
+fn wrap_variant(item: Item, variant: Option<&'static str>) -> Item {
+    match variant {
+        Some(variant) => {
+            let mut dict = Dictionary::new();
+            dict.insert(variant.to_string(), item);
+
+            Item::Dictionary(dict)
+        }
+        None => item,
+    }
+}
+
+/// Collects the elements of a sequence into a list
+struct ListSerializer {
+    items: Vec<Item>,
+    variant: Option<&'static str>,
+}
+
+impl ListSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.items
+            .push(value.serialize(ItemSerializer)?.ok_or_else(|| {
+                <SerdeError as ser::Error>::custom("cannot serialize None in a list")
+            })?);
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<Item>, SerdeError> {
+        Ok(Some(wrap_variant(Item::List(self.items), self.variant)))
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+/// Collects the entries of a map or struct into a dictionary, skipping entries with no value
+struct DictionarySerializer {
+    entries: Vec<(Vec<u8>, Item)>,
+    /// Key of the entry currently being serialized
+    key: Option<Vec<u8>>,
+    variant: Option<&'static str>,
+}
+
+impl DictionarySerializer {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Vec<u8>, value: &T) -> Result<(), SerdeError> {
+        if let Some(value) = value.serialize(ItemSerializer)? {
+            self.entries.push((key, value));
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Option<Item>, SerdeError> {
+        // sorting here keeps the output canonical even when dictionaries preserve insertion order
+        self.entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let item = if self
+            .entries
+            .iter()
+            .all(|(key, _)| std::str::from_utf8(key).is_ok())
+        {
+            Item::Dictionary(
+                self.entries
+                    .into_iter()
+                    .map(|(key, value)| (String::from_utf8(key).expect("key was checked"), value))
+                    .collect::<Dictionary>(),
+            )
+        } else {
+            Item::RawDictionary(self.entries.into_iter().collect::<RawDictionary>())
+        };
+
+        Ok(Some(wrap_variant(item, self.variant)))
+    }
+}
+
+impl ser::SerializeMap for DictionarySerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <SerdeError as ser::Error>::custom("value serialized before key"))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for DictionarySerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for DictionarySerializer {
+    type Ok = Option<Item>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        self.finish()
+    }
+}
+
+/// Serializes dictionary keys, which must be strings or bytes
+struct KeySerializer;
+
+fn invalid_key() -> SerdeError {
+    <SerdeError as ser::Error>::custom("dictionary keys must be strings or bytes")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Vec<u8>;
+    type Error = SerdeError;
+
+    type SerializeSeq = Impossible<Vec<u8>, SerdeError>;
+    type SerializeTuple = Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, SerdeError>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, SerdeError>;
+    type SerializeMap = Impossible<Vec<u8>, SerdeError>;
+    type SerializeStruct = Impossible<Vec<u8>, SerdeError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, SerdeError>;
+
+    fn serialize_str(self, value: &str) -> Result<Vec<u8>, SerdeError> {
+        Ok(value.as_bytes().to_vec())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Vec<u8>, SerdeError> {
+        Ok(value.to_vec())
+    }
+
+    fn serialize_char(self, value: char) -> Result<Vec<u8>, SerdeError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Vec<u8>, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _value: bool) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_i8(self, _value: i8) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_i16(self, _value: i16) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_i32(self, _value: i32) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_i64(self, _value: i64) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_u8(self, _value: u8) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_u16(self, _value: u16) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_u32(self, _value: u32) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_u64(self, _value: u64) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_f32(self, _value: f32) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_f64(self, _value: f64) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Vec<u8>, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, SerdeError> {
+        Err(invalid_key())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(invalid_key())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::bencoding::from_bytes;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Torrent {
+        announce: String,
+        info: Info,
+        comment: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Info {
+        name: String,
+        length: u64,
+        #[serde(rename = "piece length")]
+        piece_length: u32,
+        #[serde(with = "serde_bytes_vec")]
+        pieces: Vec<u8>,
+    }
+
+    /// Serializes a `Vec<u8>` as a byte string rather than a list of integers
+    mod serde_bytes_vec {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(bytes)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<u8>, D::Error> {
+            Vec::deserialize(deserializer)
+        }
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        assert_eq!(to_bytes(&42u8).unwrap(), b"i42e");
+        assert_eq!(to_bytes("spam").unwrap(), b"4:spam");
+        assert_eq!(to_bytes(&vec![1, -2]).unwrap(), b"li1ei-2ee");
+        assert_eq!(to_bytes(&vec![1u8, 2]).unwrap(), b"li1ei2ee");
+        assert_eq!(to_bytes(&(true, 'a')).unwrap(), b"li1e1:ae");
+
+        assert!(to_bytes(&u64::MAX).is_err());
+        assert!(to_bytes(&1.5).is_err());
+        assert!(to_bytes(&None::<u8>).is_err());
+        assert!(to_bytes(&vec![Some(1), None]).is_err());
+    }
+
+    #[test]
+    fn test_to_bytes_sorted() {
+        #[derive(Serialize)]
+        struct Unsorted {
+            spam: u8,
+            cow: u8,
+            #[serde(rename = "a b")]
+            space: u8,
+        }
+
+        assert_eq!(
+            to_bytes(&Unsorted {
+                spam: 1,
+                cow: 2,
+                space: 3
+            })
+            .unwrap(),
+            b"d3:a bi3e3:cowi2e4:spami1ee"
+        );
+    }
+
+    #[test]
+    fn test_to_bytes_enum() {
+        #[derive(Serialize)]
+        enum Event {
+            Started,
+            Completed { downloaded: u64 },
+            Seen(Vec<String>),
+        }
+
+        assert_eq!(to_bytes(&Event::Started).unwrap(), b"7:Started");
+        assert_eq!(
+            to_bytes(&Event::Completed { downloaded: 5 }).unwrap(),
+            b"d9:Completedd10:downloadedi5eee"
+        );
+        assert_eq!(
+            to_bytes(&Event::Seen(vec!["spam".to_string()])).unwrap(),
+            b"d4:Seenl4:spamee"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let torrent = Torrent {
+            announce: "udp://tracker.openbittorrent.com:80".to_string(),
+            info: Info {
+                name: "sample.txt".to_string(),
+                length: 20,
+                piece_length: 65536,
+                pieces: vec![0xff, 0x00, 0x10, 0x7f],
+            },
+            comment: None,
+        };
+
+        let bytes = to_bytes(&torrent).unwrap();
+        assert_eq!(
+            bytes,
+            b"d8:announce35:udp://tracker.openbittorrent.com:804:infod6:lengthi20e4:name10:sample.txt12:piece lengthi65536e6:pieces4:\xff\x00\x10\x7fee"
+        );
+        assert_eq!(from_bytes::<Torrent>(&bytes).unwrap(), torrent);
+    }
+}
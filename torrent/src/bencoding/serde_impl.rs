@@ -0,0 +1,201 @@
+use std::fmt;
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    ser::{SerializeMap, SerializeSeq},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use super::{Dictionary, Item};
+
+/// Serializes bytes as a string if they are valid UTF-8, and as raw bytes otherwise
+fn serialize_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => serializer.serialize_str(string),
+        Err(_) => serializer.serialize_bytes(bytes),
+    }
+}
+
+/// Wrapper to serialize raw dictionary keys the same way as byte arrays
+struct Bytes<'a>(&'a [u8]);
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_bytes(self.0, serializer)
+    }
+}
+
+impl Serialize for Item {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Item::ByteArray(bytes) => serialize_bytes(bytes, serializer),
+            Item::Integer(number) => serializer.serialize_i64(*number),
+            Item::List(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Item::Dictionary(dict) => {
+                let mut map = serializer.serialize_map(Some(dict.len()))?;
+                for (key, value) in dict {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Item::RawDictionary(dict) => {
+                let mut map = serializer.serialize_map(Some(dict.len()))?;
+                for (key, value) in dict {
+                    map.serialize_entry(&Bytes(key), value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Visitor building an item from any value bencode can represent
+struct ItemVisitor;
+
+impl<'de> Visitor<'de> for ItemVisitor {
+    type Value = Item;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer, byte array, list or dictionary")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Item, E> {
+        Ok(Item::Integer(value.into()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Item, E> {
+        Ok(Item::Integer(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Item, E> {
+        i64::try_from(value)
+            .map(Item::Integer)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Item, E> {
+        Ok(Item::ByteArray(value.as_bytes().to_vec()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Item, E> {
+        Ok(Item::ByteArray(value.into_bytes()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Item, E> {
+        Ok(Item::ByteArray(value.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Item, E> {
+        Ok(Item::ByteArray(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Item, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+
+        Ok(Item::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Item, A::Error> {
+        let mut dict = Dictionary::new();
+        while let Some((key, value)) = map.next_entry()? {
+            dict.insert(key, value);
+        }
+
+        Ok(Item::Dictionary(dict))
+    }
+}
+
+impl<'de> Deserialize<'de> for Item {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ItemVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bencoding::BEncoding;
+
+    #[test]
+    fn test_serialize() {
+        let item = BEncoding::decode_strict(b"d3:cowi-1e4:spaml1:a2:\xff\xfeee")
+            .unwrap()
+            .items
+            .remove(0);
+
+        assert_eq!(
+            serde_json::to_value(&item).unwrap(),
+            serde_json::json!({ "cow": -1, "spam": ["a", [255, 254]] })
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let item: Item =
+            serde_json::from_str(r#"{ "cow": "moo", "spam": [1, true, "eggs"] }"#).unwrap();
+
+        assert_eq!(
+            item,
+            Item::Dictionary(Dictionary::from([
+                ("cow".to_owned(), Item::ByteArray(b"moo".to_vec())),
+                (
+                    "spam".to_owned(),
+                    Item::List(vec![
+                        Item::Integer(1),
+                        Item::Integer(1),
+                        Item::ByteArray(b"eggs".to_vec())
+                    ])
+                )
+            ]))
+        );
+
+        assert!(serde_json::from_str::<Item>("1.5").is_err());
+        assert!(serde_json::from_str::<Item>("18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut item = BEncoding::decode_path("../sample.torrent")
+            .unwrap()
+            .items
+            .remove(0);
+
+        let json = serde_json::to_string(&item).unwrap();
+        let mut round_trip: Item = serde_json::from_str(&json).unwrap();
+
+        // binary data has no JSON representation, so comes back as a list of its bytes and is compared separately
+        let pieces = |item: &mut Item| match item {
+            Item::Dictionary(dict) => match dict.get_mut("info") {
+                Some(Item::Dictionary(info)) => info
+                    .get_mut("pieces")
+                    .map(|pieces| std::mem::replace(pieces, Item::Integer(0))),
+                _ => None,
+            },
+            _ => None,
+        };
+        let original_pieces = pieces(&mut item).unwrap();
+        let round_trip_pieces = pieces(&mut round_trip).unwrap();
+
+        assert_eq!(
+            round_trip_pieces,
+            Item::List(
+                original_pieces
+                    .as_bytes()
+                    .unwrap()
+                    .iter()
+                    .map(|&byte| Item::Integer(byte.into()))
+                    .collect()
+            )
+        );
+        assert_eq!(round_trip, item);
+    }
+}
@@ -0,0 +1,200 @@
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+use core::ops::Range;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{cut, map, map_res},
+    multi::{many0, many1},
+    sequence::{pair, terminated},
+    Finish,
+};
+
+use super::{
+    parse_bytearray, parse_container_start, parse_integer, BEncoding, BencodeError, DecodeOptions,
+    Key, ParseResult, Rules,
+};
+
+/// Position of a parsed item within the original input, along with the positions of its children
+#[derive(Debug, PartialEq, Clone)]
+pub struct Span {
+    /// Offset of the first byte of the item
+    pub start: usize,
+    /// Offset one past the last byte of the item
+    pub end: usize,
+    children: Children,
+}
+
+/// Spans of the items contained in a list or dictionary
+#[derive(Debug, PartialEq, Clone)]
+enum Children {
+    None,
+    List(Vec<Span>),
+    Dictionary(BTreeMap<String, Span>),
+}
+
+impl Span {
+    /// Returns the range of the input the item was parsed from
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Looks up the span of a dictionary value or list element, mirroring [`Item::get`](super::Item::get)
+    pub fn get<'a>(&self, key: impl Into<Key<'a>>) -> Option<&Span> {
+        match (&self.children, key.into()) {
+            (Children::Dictionary(dict), Key::Dictionary(key)) => dict.get(key),
+            (Children::List(spans), Key::List(index)) => spans.get(index),
+            _ => None,
+        }
+    }
+}
+
+impl BEncoding {
+    /// Decodes a byte array, also returning the span of each top-level item in the input.
+    ///
+    /// This allows recovering the exact original bytes of an item, such as the `info` dictionary of a torrent
+    pub fn decode_with_spans(bytes: &[u8]) -> Result<(Self, Vec<Span>), BencodeError> {
+        DecodeOptions::new().decode_with_spans(bytes)
+    }
+}
+
+impl DecodeOptions {
+    /// Decodes a byte array using these options, also returning the span of each top-level item in the input.
+    ///
+    /// Values under keys that are not valid UTF-8 have no span when raw keys are allowed
+    pub fn decode_with_spans(&self, bytes: &[u8]) -> Result<(BEncoding, Vec<Span>), BencodeError> {
+        let encoding = self.decode(bytes)?;
+
+        let spans = many1(|input| parse_span(input, bytes, self.rules))(bytes)
+            .finish()
+            .map(|(_remaining, spans)| spans)
+            .map_err(|err| BencodeError::from_decode(err, bytes))?;
+
+        Ok((encoding, spans))
+    }
+}
+
+/// Parse any BEncoded item, recording its position relative to `base`
+fn parse_span<'a>(input: &'a [u8], base: &'a [u8], rules: Rules) -> ParseResult<'a, Span> {
+    let offset = |remaining: &[u8]| remaining.as_ptr() as usize - base.as_ptr() as usize;
+
+    let (remaining, children) = alt((
+        map(parse_integer, |_| Children::None),
+        map(|input| parse_list_spans(input, base, rules), Children::List),
+        map(
+            |input| parse_dictionary_spans(input, base, rules),
+            Children::Dictionary,
+        ),
+        map(parse_bytearray, |_| Children::None),
+    ))(input)?;
+
+    Ok((
+        remaining,
+        Span {
+            start: offset(input),
+            end: offset(remaining),
+            children,
+        },
+    ))
+}
+
+/// Parse a BENcoded list, recording the positions of its elements
+fn parse_list_spans<'a>(
+    input: &'a [u8],
+    base: &'a [u8],
+    rules: Rules,
+) -> ParseResult<'a, Vec<Span>> {
+    let (input, rules) = parse_container_start(BEncoding::LIST_START, rules)(input)?;
+
+    cut(terminated(
+        many0(move |input| parse_span(input, base, rules)),
+        tag(BEncoding::END),
+    ))(input)
+}
+
+/// Parse a BENcoded dict, recording the positions of its values
+fn parse_dictionary_spans<'a>(
+    input: &'a [u8],
+    base: &'a [u8],
+    rules: Rules,
+) -> ParseResult<'a, BTreeMap<String, Span>> {
+    let (input, rules) = parse_container_start(BEncoding::DICT_START, rules)(input)?;
+
+    let key = move |input| {
+        if rules.allow_raw_keys {
+            map(parse_bytearray, |key| {
+                core::str::from_utf8(key).ok().map(str::to_owned)
+            })(input)
+        } else {
+            map_res(parse_bytearray, |key| {
+                core::str::from_utf8(key).map(|key| Some(key.to_owned()))
+            })(input)
+        }
+    };
+
+    cut(terminated(
+        many0(pair(key, move |input| parse_span(input, base, rules))),
+        tag(BEncoding::END),
+    ))(input)
+    .map(|(remaining, pairs)| {
+        let spans = pairs
+            .into_iter()
+            .filter_map(|(key, span)| Some((key?, span)))
+            .collect();
+
+        (remaining, spans)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spans() {
+        let (_, spans) = BEncoding::decode_with_spans(b"i1eld3:cow3:mooei-5ee").unwrap();
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].range(), 0..3);
+        assert_eq!(spans[1].range(), 3..21);
+        assert_eq!(spans[1].get(0).unwrap().range(), 4..16);
+        assert_eq!(spans[1].get(0).unwrap().get("cow").unwrap().range(), 10..15);
+        assert_eq!(spans[1].get(1).unwrap().range(), 16..20);
+
+        assert_eq!(spans[0].get(0), None);
+        assert_eq!(spans[1].get("cow"), None);
+        assert_eq!(spans[1].get(2), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_info_span() {
+        let data = std::fs::read("../sample.torrent").unwrap();
+        let (encoding, spans) = BEncoding::decode_with_spans(&data).unwrap();
+
+        let info = &data[spans[0].get("info").unwrap().range()];
+        assert_eq!(info, encoding.items[0]["info"].encode());
+        assert_eq!(
+            BEncoding::decode_strict(info).unwrap().items[0],
+            encoding.items[0]["info"]
+        );
+    }
+
+    #[test]
+    fn test_span_errors() {
+        assert!(BEncoding::decode_with_spans(b"l4:spam").is_err());
+    }
+
+    #[test]
+    fn test_raw_key_spans() {
+        let input = b"d1:ai1e1:\xffi2ee";
+        assert!(BEncoding::decode_with_spans(input).is_err());
+
+        let (decoded, spans) = DecodeOptions::new()
+            .allow_raw_keys(true)
+            .decode_with_spans(input)
+            .unwrap();
+        assert!(decoded.items()[0].as_raw_dict().is_some());
+        assert_eq!(spans[0].get("a").unwrap().range(), 4..7);
+    }
+}
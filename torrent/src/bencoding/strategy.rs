@@ -0,0 +1,81 @@
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::{hash_map, vec},
+    prop_oneof,
+    strategy::{BoxedStrategy, Just, Strategy},
+};
+
+use super::{Dictionary, Item};
+
+/// Most children generated for a single list or dictionary
+const MAX_CHILDREN: usize = 8;
+/// Longest byte array generated
+const MAX_BYTES: usize = 32;
+/// Deepest nesting generated by [`any::<Item>()`](any), which passes a depth of zero
+const DEFAULT_MAX_DEPTH: u32 = 4;
+
+/// Generates well-formed items with lists and dictionaries nested at most `max_depth` levels deep.
+///
+/// Byte arrays may hold any bytes, including nulls, and integers cover the full `i64` range. Dictionaries only have
+/// UTF-8 keys, so every item survives `decode(item.encode())` unchanged
+pub fn arb_item(max_depth: u32) -> impl Strategy<Value = Item> {
+    let leaf = prop_oneof![
+        vec(any::<u8>(), 0..=MAX_BYTES).prop_map(Item::ByteArray),
+        // the extremes are unlikely to be picked at random, but are the most likely to be mishandled
+        prop_oneof![any::<i64>(), Just(i64::MIN), Just(i64::MAX), Just(0)].prop_map(Item::Integer),
+    ];
+
+    leaf.prop_recursive(max_depth, 64, MAX_CHILDREN as u32, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..=MAX_CHILDREN).prop_map(Item::List),
+            hash_map(".{0,8}", inner, 0..=MAX_CHILDREN)
+                .prop_map(|dict| Item::Dictionary(dict.into_iter().collect::<Dictionary>())),
+        ]
+    })
+}
+
+impl Arbitrary for Item {
+    /// Deepest nesting of lists and dictionaries
+    type Parameters = u32;
+    type Strategy = BoxedStrategy<Item>;
+
+    fn arbitrary_with(max_depth: u32) -> Self::Strategy {
+        match max_depth {
+            0 => arb_item(DEFAULT_MAX_DEPTH),
+            max_depth => arb_item(max_depth),
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::proptest;
+
+    use super::*;
+    use crate::bencoding::BEncoding;
+
+    /// Returns the deepest nesting of lists and dictionaries within an item
+    fn depth(item: &Item) -> u32 {
+        match item {
+            Item::ByteArray(_) | Item::Integer(_) => 0,
+            Item::List(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+            Item::Dictionary(dict) => 1 + dict.values().map(depth).max().unwrap_or(0),
+            Item::RawDictionary(dict) => 1 + dict.values().map(depth).max().unwrap_or(0),
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(item in any::<Item>()) {
+            let decoded = BEncoding::decode_strict(&item.encode()).unwrap();
+
+            assert_eq!(decoded.root(), Some(&item));
+        }
+
+        #[test]
+        fn test_max_depth(item in arb_item(3)) {
+            assert!(depth(&item) <= 3);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::io::{ErrorKind, Read};
+
+use super::{BEncoding, BencodeError, Item};
+
+/// Number of bytes requested from the reader at a time
+pub(super) const CHUNK_SIZE: usize = 8192;
+
+/// Longest item decoded from a stream unless another limit is set
+pub const DEFAULT_MAX_ITEM_LEN: usize = 64 * 1024 * 1024;
+
+/// Bytes read from a stream that have not yet been decoded
+#[derive(Debug)]
+pub(super) struct ItemBuffer {
+    bytes: Vec<u8>,
+    /// Length the buffer must reach before decoding is attempted again.
+    ///
+    /// This doubles after each incomplete attempt, so a large item arriving in full chunks isn't decoded from the
+    /// start after every chunk
+    retry_len: usize,
+    max_len: usize,
+}
+
+impl ItemBuffer {
+    pub(super) fn new(max_len: usize) -> Self {
+        Self {
+            bytes: Vec::new(),
+            retry_len: 0,
+            max_len,
+        }
+    }
+
+    /// Appends bytes read from the stream
+    pub(super) fn extend(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+
+        // a short read means the reader has nothing more ready, so waiting for more before decoding could stall
+        if bytes.len() < CHUNK_SIZE {
+            self.retry_len = 0;
+        }
+    }
+
+    /// Decodes an item from the start of the buffer and removes its bytes, or returns None if more bytes are needed.
+    ///
+    /// Once the input has ended, `eof` makes an incomplete item an error. Fails if the item is longer than the
+    /// maximum length, which bounds the buffer to the maximum length plus one chunk
+    pub(super) fn take_item(&mut self, eof: bool) -> Result<Option<Item>, BencodeError> {
+        let too_long = BencodeError::ItemTooLong {
+            max_len: self.max_len,
+        };
+
+        let waiting = !eof && self.bytes.len() < self.retry_len && self.bytes.len() <= self.max_len;
+        if self.bytes.is_empty() || waiting {
+            return Ok(None);
+        }
+
+        match BEncoding::decode_with_remainder(&self.bytes) {
+            Ok((item, remaining)) => {
+                let consumed = self.bytes.len() - remaining.len();
+                if consumed > self.max_len {
+                    return Err(too_long);
+                }
+                self.bytes.drain(..consumed);
+                self.retry_len = 0;
+
+                Ok(Some(item))
+            }
+            // item may be completed by more data from the reader
+            Err(BencodeError::UnexpectedEof { .. }) if !eof => {
+                if self.bytes.len() > self.max_len {
+                    return Err(too_long);
+                }
+
+                self.retry_len = self.bytes.len().saturating_mul(2);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Decodes a stream of BEncoded items one at a time, only buffering the bytes of the item being decoded
+#[derive(Debug)]
+pub struct StreamDecoder<R> {
+    reader: R,
+    buffer: ItemBuffer,
+    /// Whether the reader has been exhausted
+    eof: bool,
+}
+
+impl<R: Read> StreamDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: ItemBuffer::new(DEFAULT_MAX_ITEM_LEN),
+            eof: false,
+        }
+    }
+
+    /// Sets the most bytes a single item may take up, which defaults to [`DEFAULT_MAX_ITEM_LEN`].
+    ///
+    /// Longer items fail with [`BencodeError::ItemTooLong`] rather than being buffered
+    pub fn max_item_len(mut self, max_len: usize) -> Self {
+        self.buffer.max_len = max_len;
+        self
+    }
+
+    /// Decodes the next item from the stream, returning None once the stream ends cleanly between items.
+    ///
+    /// Offsets in any returned error are relative to the start of the item being decoded
+    pub fn next_item(&mut self) -> Result<Option<Item>, BencodeError> {
+        loop {
+            if let Some(item) = self.buffer.take_item(self.eof)? {
+                return Ok(Some(item));
+            }
+            // an empty buffer at the end of the stream is the only way to get here once it's exhausted
+            if self.eof {
+                return Ok(None);
+            }
+
+            self.fill_buffer()?;
+        }
+    }
+
+    /// Reads the next chunk from the reader into the buffer, marking the stream as finished if nothing is read
+    fn fill_buffer(&mut self) -> std::io::Result<()> {
+        let mut chunk = [0; CHUNK_SIZE];
+
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        if read == 0 {
+            self.eof = true;
+        } else {
+            self.buffer.extend(&chunk[..read]);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reader which only returns a few bytes per read, to exercise buffering
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = self.chunk_size.min(self.data.len()).min(buf.len());
+            buf[..length].copy_from_slice(&self.data[..length]);
+            self.data = &self.data[length..];
+
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut decoder = StreamDecoder::new(ChunkedReader {
+            data: b"i42ed3:cow3:mooe4:spaml10:abcdefghije",
+            chunk_size: 3,
+        });
+
+        assert_eq!(decoder.next_item().unwrap(), Some(Item::Integer(42)));
+        assert_eq!(
+            decoder.next_item().unwrap().unwrap()["cow"],
+            Item::ByteArray(b"moo".to_vec())
+        );
+        assert_eq!(
+            decoder.next_item().unwrap(),
+            Some(Item::ByteArray(b"spam".to_vec()))
+        );
+        assert_eq!(
+            decoder.next_item().unwrap(),
+            Some(Item::List(vec![Item::ByteArray(b"abcdefghij".to_vec())]))
+        );
+        assert_eq!(decoder.next_item().unwrap(), None);
+    }
+
+    #[test]
+    fn test_stream_file() {
+        let data = std::fs::read("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        let mut decoder = StreamDecoder::new(ChunkedReader {
+            data: &data,
+            chunk_size: 1000,
+        });
+
+        assert_eq!(
+            decoder.next_item().unwrap().as_ref(),
+            BEncoding::decode(&data).unwrap().items.first()
+        );
+        assert_eq!(decoder.next_item().unwrap(), None);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let mut decoder = StreamDecoder::new(&b"i1el4:spam"[..]);
+        assert_eq!(decoder.next_item().unwrap(), Some(Item::Integer(1)));
+        assert!(matches!(
+            decoder.next_item(),
+            Err(BencodeError::UnexpectedEof { .. })
+        ));
+
+        let mut decoder = StreamDecoder::new(&b"x"[..]);
+        assert!(matches!(
+            decoder.next_item(),
+            Err(BencodeError::InvalidItem { .. })
+        ));
+    }
+
+    #[test]
+    fn test_max_item_len() {
+        let mut decoder = StreamDecoder::new(&b"4:spam7:eggsham"[..]).max_item_len(8);
+        assert_eq!(
+            decoder.next_item().unwrap(),
+            Some(Item::ByteArray(b"spam".to_vec()))
+        );
+
+        let data = [&b"100000:"[..], &[0; 100_000]].concat();
+        let mut decoder = StreamDecoder::new(&data[..]).max_item_len(50_000);
+        assert!(matches!(
+            decoder.next_item(),
+            Err(BencodeError::ItemTooLong { max_len: 50_000 })
+        ));
+    }
+
+    #[test]
+    fn test_retry_len() {
+        let data = [&b"l100000:"[..], &[0; 100_000], b"ei1e"].concat();
+        let mut buffer = ItemBuffer::new(DEFAULT_MAX_ITEM_LEN);
+
+        // full chunks only retry decoding once the buffer has doubled in size since the last attempt
+        let mut attempts = 0;
+        let mut chunks = data.chunks(CHUNK_SIZE);
+        let item = loop {
+            let chunk = chunks.next().unwrap();
+            buffer.extend(chunk);
+            if buffer.bytes.len() >= buffer.retry_len {
+                attempts += 1;
+            }
+            if let Some(item) = buffer.take_item(false).unwrap() {
+                break item;
+            }
+        };
+
+        assert_eq!(item.encoded_len(), 100_009);
+        assert!(attempts <= 6);
+        assert_eq!(buffer.take_item(false).unwrap(), Some(Item::Integer(1)));
+    }
+}
@@ -0,0 +1,13 @@
+mod distance;
+mod error;
+mod krpc;
+mod node;
+mod routing;
+mod token;
+
+pub use distance::{distance_cmp, xor_distance};
+pub use error::KrpcError;
+pub use krpc::{KrpcMessage, Query, Response};
+pub use node::{parse_compact_nodes, parse_compact_nodes6, NodeInfo, NodeInfo6};
+pub use routing::RoutingTable;
+pub use token::TokenManager;
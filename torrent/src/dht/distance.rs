@@ -0,0 +1,67 @@
+use std::cmp::Ordering;
+
+/// Returns the XOR of two node ids, which is the distance between them in Kademlia
+pub fn xor_distance(a: &[u8; 20], b: &[u8; 20]) -> [u8; 20] {
+    let mut distance = [0; 20];
+    for (byte, (a, b)) in distance.iter_mut().zip(a.iter().zip(b)) {
+        *byte = a ^ b;
+    }
+
+    distance
+}
+
+/// Compares how far `a` and `b` are from `target`, with the closer id ordered first.
+///
+/// The distances are ordered by the most significant bit in which they differ, which is the same as comparing them
+/// as big endian integers
+pub fn distance_cmp(target: &[u8; 20], a: &[u8; 20], b: &[u8; 20]) -> Ordering {
+    xor_distance(target, a).cmp(&xor_distance(target, b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Returns an id that is zero except for its last byte
+    fn id(last: u8) -> [u8; 20] {
+        let mut id = [0; 20];
+        id[19] = last;
+        id
+    }
+
+    #[test]
+    fn test_xor_distance() {
+        let a = [0xaa; 20];
+        assert_eq!(xor_distance(&a, &a), [0; 20]);
+        assert_eq!(xor_distance(&a, &[0x55; 20]), [0xff; 20]);
+        assert_eq!(xor_distance(&id(0b0110), &id(0b0011)), id(0b0101));
+    }
+
+    #[test]
+    fn test_distance_cmp() {
+        let target = id(0b1000);
+
+        assert_eq!(distance_cmp(&target, &target, &id(0b1001)), Ordering::Less);
+        assert_eq!(
+            distance_cmp(&target, &id(0b1111), &id(0b0000)),
+            Ordering::Less
+        );
+        assert_eq!(
+            distance_cmp(&target, &id(0b0111), &id(0b0000)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            distance_cmp(&target, &id(0b0001), &id(0b0001)),
+            Ordering::Equal
+        );
+
+        // a difference in an earlier byte outweighs any later bits
+        let mut far = target;
+        far[0] = 0x01;
+        assert_eq!(distance_cmp(&target, &id(0xff), &far), Ordering::Less);
+
+        let mut ids = vec![id(0b0000), id(0b1111), far, id(0b1010), target];
+        ids.sort_by(|a, b| distance_cmp(&target, a, b));
+        assert_eq!(ids, [target, id(0b1010), id(0b1111), id(0b0000), far]);
+    }
+}
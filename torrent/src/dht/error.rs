@@ -0,0 +1,92 @@
+use std::fmt::Display;
+
+use crate::bencoding::BencodeError;
+
+/// Represents an error encountered while decoding a KRPC message
+#[derive(Debug)]
+pub enum KrpcError {
+    /// Message was not valid BEncode
+    Decode(BencodeError),
+    /// Required key was absent
+    MissingField { field: &'static str },
+    /// Key was present but had the wrong type or an invalid value
+    InvalidField { field: &'static str },
+    /// Compact node info had a length that wasn't a multiple of the entry size
+    InvalidNodes { length: usize },
+    /// Query used a method this implementation doesn't know
+    UnknownMethod { method: String },
+}
+
+impl Display for KrpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode KRPC message: {err}"),
+            Self::MissingField { field } => write!(f, "missing field {field:?}"),
+            Self::InvalidField { field } => write!(f, "field {field:?} has an invalid value"),
+            Self::InvalidNodes { length } => {
+                write!(f, "invalid compact node info of length {length}")
+            }
+            Self::UnknownMethod { method } => write!(f, "unknown query method {method:?}"),
+        }
+    }
+}
+
+impl std::error::Error for KrpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BencodeError> for KrpcError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (
+                KrpcError::Decode(BencodeError::TrailingData { offset: 10 }),
+                "failed to decode KRPC message: trailing data after item at byte 10",
+            ),
+            (
+                KrpcError::MissingField { field: "t" },
+                "missing field \"t\"",
+            ),
+            (
+                KrpcError::InvalidField { field: "a.id" },
+                "field \"a.id\" has an invalid value",
+            ),
+            (
+                KrpcError::InvalidNodes { length: 27 },
+                "invalid compact node info of length 27",
+            ),
+            (
+                KrpcError::UnknownMethod {
+                    method: "vote".to_owned(),
+                },
+                "unknown query method \"vote\"",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_source() {
+        let err = KrpcError::from(BencodeError::InvalidItem { offset: 0 });
+        assert!(err.source().unwrap().is::<BencodeError>());
+        assert!(KrpcError::InvalidNodes { length: 27 }.source().is_none());
+    }
+}
@@ -0,0 +1,507 @@
+use std::net::{IpAddr, SocketAddr};
+
+use super::{parse_compact_nodes, parse_compact_nodes6, KrpcError, NodeInfo, NodeInfo6};
+use crate::{
+    bencoding::{BEncoding, Dictionary, Item},
+    tracker::{parse_compact_v4, parse_compact_v6, Peer},
+};
+
+/// Represents a query sent to a DHT node, where `id` is the sender's node id
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Query {
+    /// Checks the node is reachable
+    Ping { id: [u8; 20] },
+    /// Asks for the nodes closest to `target`
+    FindNode { id: [u8; 20], target: [u8; 20] },
+    /// Asks for peers of a torrent, or the closest nodes if there are none
+    GetPeers { id: [u8; 20], info_hash: [u8; 20] },
+    /// Announces that the sender is downloading a torrent, using the token from an earlier `get_peers` response.
+    ///
+    /// If `implied_port` is set, the receiver should use the source port of the packet instead of `port`
+    AnnouncePeer {
+        id: [u8; 20],
+        info_hash: [u8; 20],
+        port: u16,
+        implied_port: bool,
+        token: Vec<u8>,
+    },
+}
+
+/// Represents a response from a DHT node to any query, where `id` is the responder's node id.
+///
+/// Responses don't say which query they answer, so the remaining keys are only present for some queries
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Response {
+    pub id: [u8; 20],
+    /// Closest IPv4 nodes, from `find_node` and `get_peers`
+    pub nodes: Option<Vec<NodeInfo>>,
+    /// Closest IPv6 nodes, from `find_node` and `get_peers`
+    pub nodes6: Option<Vec<NodeInfo6>>,
+    /// Token to use in a later `announce_peer`, from `get_peers`
+    pub token: Option<Vec<u8>>,
+    /// Peers of the torrent, from `get_peers`
+    pub values: Option<Vec<SocketAddr>>,
+}
+
+impl Response {
+    /// Creates a response holding only the responder's id, as for `ping` and `announce_peer`
+    pub fn new(id: [u8; 20]) -> Self {
+        Self {
+            id,
+            nodes: None,
+            nodes6: None,
+            token: None,
+            values: None,
+        }
+    }
+}
+
+/// Represents a message of the KRPC protocol used by the DHT, from BEP 5
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum KrpcMessage {
+    Query {
+        transaction_id: Vec<u8>,
+        query: Query,
+    },
+    Response {
+        transaction_id: Vec<u8>,
+        response: Response,
+    },
+    Error {
+        transaction_id: Vec<u8>,
+        code: i64,
+        message: String,
+    },
+}
+
+impl KrpcMessage {
+    /// Error code for a generic error
+    pub const GENERIC_ERROR: i64 = 201;
+    /// Error code for an error on the receiving node
+    pub const SERVER_ERROR: i64 = 202;
+    /// Error code for a malformed message or invalid token
+    pub const PROTOCOL_ERROR: i64 = 203;
+    /// Error code for a query with an unknown method
+    pub const METHOD_UNKNOWN: i64 = 204;
+
+    /// Returns the transaction id used to match responses to queries
+    pub fn transaction_id(&self) -> &[u8] {
+        match self {
+            Self::Query { transaction_id, .. }
+            | Self::Response { transaction_id, .. }
+            | Self::Error { transaction_id, .. } => transaction_id,
+        }
+    }
+
+    /// Decodes a message, ignoring keys it doesn't use
+    pub fn decode(bytes: &[u8]) -> Result<Self, KrpcError> {
+        let decoded = BEncoding::decode_strict(bytes)?;
+        let root = decoded.root().expect("strict decoding produces one item");
+        if root.as_dict().is_none() {
+            return Err(KrpcError::InvalidField { field: "root" });
+        }
+
+        let transaction_id = bytes_field(root, "t", "t")?.to_vec();
+
+        match bytes_field(root, "y", "y")? {
+            b"q" => Ok(Self::Query {
+                transaction_id,
+                query: decode_query(root)?,
+            }),
+            b"r" => Ok(Self::Response {
+                transaction_id,
+                response: decode_response(root)?,
+            }),
+            b"e" => {
+                let invalid = || KrpcError::InvalidField { field: "e" };
+                let error = root
+                    .get("e")
+                    .ok_or(KrpcError::MissingField { field: "e" })?;
+
+                match error.as_list() {
+                    Some([code, message]) => Ok(Self::Error {
+                        transaction_id,
+                        code: code.as_integer().ok_or_else(invalid)?,
+                        message: String::from_utf8_lossy(message.as_bytes().ok_or_else(invalid)?)
+                            .into_owned(),
+                    }),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(KrpcError::InvalidField { field: "y" }),
+        }
+    }
+
+    /// Encodes the message with keys in sorted order
+    pub fn encode(&self) -> Vec<u8> {
+        let mut dict = Dictionary::new();
+
+        match self {
+            Self::Query { query, .. } => {
+                let (method, args) = encode_query(query);
+                dict.insert("a".to_owned(), Item::Dictionary(args));
+                dict.insert("q".to_owned(), Item::from(method));
+            }
+            Self::Response { response, .. } => {
+                dict.insert("r".to_owned(), Item::Dictionary(encode_response(response)));
+            }
+            Self::Error { code, message, .. } => {
+                dict.insert(
+                    "e".to_owned(),
+                    Item::List(vec![Item::Integer(*code), Item::from(message.as_str())]),
+                );
+            }
+        }
+
+        let kind = match self {
+            Self::Query { .. } => "q",
+            Self::Response { .. } => "r",
+            Self::Error { .. } => "e",
+        };
+        dict.insert("t".to_owned(), Item::from(self.transaction_id()));
+        dict.insert("y".to_owned(), Item::from(kind));
+
+        Item::Dictionary(dict).encode()
+    }
+}
+
+/// Extracts a required byte string, where `path` is the name reported in errors
+fn bytes_field<'a>(dict: &'a Item, key: &str, path: &'static str) -> Result<&'a [u8], KrpcError> {
+    dict.get(key)
+        .ok_or(KrpcError::MissingField { field: path })?
+        .as_bytes()
+        .ok_or(KrpcError::InvalidField { field: path })
+}
+
+/// Extracts a required 20 byte id or info hash
+fn id_field(dict: &Item, key: &str, path: &'static str) -> Result<[u8; 20], KrpcError> {
+    bytes_field(dict, key, path)?
+        .try_into()
+        .map_err(|_| KrpcError::InvalidField { field: path })
+}
+
+fn decode_query(root: &Item) -> Result<Query, KrpcError> {
+    let method = bytes_field(root, "q", "q")?;
+    let args = root
+        .get("a")
+        .filter(|args| args.as_dict().is_some())
+        .ok_or(KrpcError::MissingField { field: "a" })?;
+    let id = id_field(args, "id", "a.id")?;
+
+    match method {
+        b"ping" => Ok(Query::Ping { id }),
+        b"find_node" => Ok(Query::FindNode {
+            id,
+            target: id_field(args, "target", "a.target")?,
+        }),
+        b"get_peers" => Ok(Query::GetPeers {
+            id,
+            info_hash: id_field(args, "info_hash", "a.info_hash")?,
+        }),
+        b"announce_peer" => Ok(Query::AnnouncePeer {
+            id,
+            info_hash: id_field(args, "info_hash", "a.info_hash")?,
+            port: args
+                .get("port")
+                .ok_or(KrpcError::MissingField { field: "a.port" })?
+                .as_integer()
+                .and_then(|port| u16::try_from(port).ok())
+                .ok_or(KrpcError::InvalidField { field: "a.port" })?,
+            implied_port: args
+                .get("implied_port")
+                .map(|implied| match implied.as_integer() {
+                    Some(0) => Ok(false),
+                    Some(1) => Ok(true),
+                    _ => Err(KrpcError::InvalidField {
+                        field: "a.implied_port",
+                    }),
+                })
+                .transpose()?
+                .unwrap_or(false),
+            token: bytes_field(args, "token", "a.token")?.to_vec(),
+        }),
+        _ => Err(KrpcError::UnknownMethod {
+            method: String::from_utf8_lossy(method).into_owned(),
+        }),
+    }
+}
+
+fn decode_response(root: &Item) -> Result<Response, KrpcError> {
+    let values = root
+        .get("r")
+        .filter(|values| values.as_dict().is_some())
+        .ok_or(KrpcError::MissingField { field: "r" })?;
+
+    let optional_bytes = |key: &str, path: &'static str| {
+        values
+            .get(key)
+            .map(|value| {
+                value
+                    .as_bytes()
+                    .map(<[u8]>::to_vec)
+                    .ok_or(KrpcError::InvalidField { field: path })
+            })
+            .transpose()
+    };
+
+    let invalid_values = || KrpcError::InvalidField { field: "r.values" };
+    let peers = values
+        .get("values")
+        .map(|peers| {
+            peers
+                .as_list()
+                .ok_or_else(invalid_values)?
+                .iter()
+                .map(|peer| {
+                    let peer = peer.as_bytes().ok_or_else(invalid_values)?;
+                    let parsed = match peer.len() {
+                        Peer::COMPACT_V4_LEN => parse_compact_v4(peer),
+                        Peer::COMPACT_V6_LEN => parse_compact_v6(peer),
+                        _ => None,
+                    };
+
+                    Ok(parsed.ok_or_else(invalid_values)?[0].addr())
+                })
+                .collect::<Result<Vec<_>, KrpcError>>()
+        })
+        .transpose()?;
+
+    Ok(Response {
+        id: id_field(values, "id", "r.id")?,
+        nodes: optional_bytes("nodes", "r.nodes")?
+            .map(|nodes| {
+                parse_compact_nodes(&nodes)
+                    .map_err(|_| KrpcError::InvalidField { field: "r.nodes" })
+            })
+            .transpose()?,
+        nodes6: optional_bytes("nodes6", "r.nodes6")?
+            .map(|nodes| {
+                parse_compact_nodes6(&nodes)
+                    .map_err(|_| KrpcError::InvalidField { field: "r.nodes6" })
+            })
+            .transpose()?,
+        token: optional_bytes("token", "r.token")?,
+        values: peers,
+    })
+}
+
+fn encode_query(query: &Query) -> (&'static str, Dictionary) {
+    let mut args = Dictionary::new();
+
+    let method = match query {
+        Query::Ping { id } => {
+            args.insert("id".to_owned(), Item::from(&id[..]));
+            "ping"
+        }
+        Query::FindNode { id, target } => {
+            args.insert("id".to_owned(), Item::from(&id[..]));
+            args.insert("target".to_owned(), Item::from(&target[..]));
+            "find_node"
+        }
+        Query::GetPeers { id, info_hash } => {
+            args.insert("id".to_owned(), Item::from(&id[..]));
+            args.insert("info_hash".to_owned(), Item::from(&info_hash[..]));
+            "get_peers"
+        }
+        Query::AnnouncePeer {
+            id,
+            info_hash,
+            port,
+            implied_port,
+            token,
+        } => {
+            args.insert("id".to_owned(), Item::from(&id[..]));
+            args.insert(
+                "implied_port".to_owned(),
+                Item::Integer(i64::from(*implied_port)),
+            );
+            args.insert("info_hash".to_owned(), Item::from(&info_hash[..]));
+            args.insert("port".to_owned(), Item::Integer(i64::from(*port)));
+            args.insert("token".to_owned(), Item::from(&token[..]));
+            "announce_peer"
+        }
+    };
+
+    (method, args)
+}
+
+fn encode_response(response: &Response) -> Dictionary {
+    let mut values = Dictionary::new();
+    values.insert("id".to_owned(), Item::from(&response.id[..]));
+
+    if let Some(nodes) = &response.nodes {
+        let nodes: Vec<u8> = nodes.iter().flat_map(NodeInfo::to_compact).collect();
+        values.insert("nodes".to_owned(), Item::ByteArray(nodes));
+    }
+    if let Some(nodes) = &response.nodes6 {
+        let nodes: Vec<u8> = nodes.iter().flat_map(NodeInfo6::to_compact).collect();
+        values.insert("nodes6".to_owned(), Item::ByteArray(nodes));
+    }
+    if let Some(token) = &response.token {
+        values.insert("token".to_owned(), Item::from(&token[..]));
+    }
+    if let Some(peers) = &response.values {
+        values.insert(
+            "values".to_owned(),
+            Item::List(
+                peers
+                    .iter()
+                    .map(|peer| {
+                        let mut bytes = match peer.ip() {
+                            IpAddr::V4(ip) => ip.octets().to_vec(),
+                            IpAddr::V6(ip) => ip.octets().to_vec(),
+                        };
+                        bytes.extend_from_slice(&peer.port().to_be_bytes());
+                        Item::ByteArray(bytes)
+                    })
+                    .collect(),
+            ),
+        );
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_node_query() {
+        // example from BEP 5
+        let bytes =
+            b"d1:ad2:id20:abcdefghij01234567896:target20:mnopqrstuvwxyz123456e1:q9:find_node1:t2:aa1:y1:qe";
+        let message = KrpcMessage::decode(bytes).unwrap();
+
+        assert_eq!(
+            message,
+            KrpcMessage::Query {
+                transaction_id: b"aa".to_vec(),
+                query: Query::FindNode {
+                    id: *b"abcdefghij0123456789",
+                    target: *b"mnopqrstuvwxyz123456",
+                },
+            }
+        );
+        assert_eq!(message.encode(), bytes);
+    }
+
+    #[test]
+    fn test_find_node_response() {
+        let bytes = b"d1:rd2:id20:0123456789abcdefghij5:nodes26:\
+            mnopqrstuvwxyz123456\x7f\x00\x00\x01\x1a\xe1e1:t2:aa1:y1:re";
+        let message = KrpcMessage::decode(bytes).unwrap();
+
+        assert_eq!(
+            message,
+            KrpcMessage::Response {
+                transaction_id: b"aa".to_vec(),
+                response: Response {
+                    nodes: Some(vec![NodeInfo {
+                        id: *b"mnopqrstuvwxyz123456",
+                        addr: "127.0.0.1:6881".parse().unwrap(),
+                    }]),
+                    ..Response::new(*b"0123456789abcdefghij")
+                },
+            }
+        );
+        assert_eq!(message.encode(), bytes);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let messages = [
+            KrpcMessage::Query {
+                transaction_id: b"aa".to_vec(),
+                query: Query::Ping { id: [1; 20] },
+            },
+            KrpcMessage::Query {
+                transaction_id: b"ab".to_vec(),
+                query: Query::GetPeers {
+                    id: [1; 20],
+                    info_hash: [2; 20],
+                },
+            },
+            KrpcMessage::Query {
+                transaction_id: b"ac".to_vec(),
+                query: Query::AnnouncePeer {
+                    id: [1; 20],
+                    info_hash: [2; 20],
+                    port: 6881,
+                    implied_port: true,
+                    token: b"aoeusnth".to_vec(),
+                },
+            },
+            KrpcMessage::Response {
+                transaction_id: b"ab".to_vec(),
+                response: Response {
+                    token: Some(b"aoeusnth".to_vec()),
+                    values: Some(vec![
+                        "127.0.0.1:6881".parse().unwrap(),
+                        "[::1]:6882".parse().unwrap(),
+                    ]),
+                    ..Response::new([3; 20])
+                },
+            },
+            KrpcMessage::Response {
+                transaction_id: b"ad".to_vec(),
+                response: Response {
+                    nodes6: Some(vec![NodeInfo6 {
+                        id: [4; 20],
+                        addr: "[::1]:6881".parse().unwrap(),
+                    }]),
+                    ..Response::new([3; 20])
+                },
+            },
+            KrpcMessage::Error {
+                transaction_id: b"ac".to_vec(),
+                code: KrpcMessage::PROTOCOL_ERROR,
+                message: "Bad token".to_owned(),
+            },
+        ];
+
+        for message in messages {
+            assert_eq!(KrpcMessage::decode(&message.encode()).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(
+            KrpcMessage::decode(b"d1:eli201e23:A Generic Error Ocurrede1:t2:aa1:y1:ee").unwrap(),
+            KrpcMessage::Error {
+                transaction_id: b"aa".to_vec(),
+                code: KrpcMessage::GENERIC_ERROR,
+                message: "A Generic Error Ocurred".to_owned(),
+            }
+        );
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:eli201ee1:t2:aa1:y1:ee"),
+            Err(KrpcError::InvalidField { field: "e" })
+        ));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:y1:qe"),
+            Err(KrpcError::MissingField { field: "t" })
+        ));
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:t2:aa1:y1:xe"),
+            Err(KrpcError::InvalidField { field: "y" })
+        ));
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:ad2:id3:abce1:q4:ping1:t2:aa1:y1:qe"),
+            Err(KrpcError::InvalidField { field: "a.id" })
+        ));
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:rd2:id20:0123456789abcdefghij5:nodes3:abce1:t2:aa1:y1:re"),
+            Err(KrpcError::InvalidField { field: "r.nodes" })
+        ));
+        assert!(matches!(
+            KrpcMessage::decode(b"d1:ad2:id20:abcdefghij0123456789e1:q4:vote1:t2:aa1:y1:qe"),
+            Err(KrpcError::UnknownMethod { method }) if method == "vote"
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+use std::net::{Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+use super::KrpcError;
+
+/// Represents a DHT node with an IPv4 address, as listed in `nodes`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NodeInfo {
+    pub id: [u8; 20],
+    pub addr: SocketAddrV4,
+}
+
+/// Represents a DHT node with an IPv6 address, as listed in `nodes6`
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NodeInfo6 {
+    pub id: [u8; 20],
+    pub addr: SocketAddrV6,
+}
+
+impl NodeInfo {
+    /// Length of a node in compact form
+    pub const COMPACT_LEN: usize = 26;
+
+    /// Encodes the node as its id followed by its compact address
+    pub fn to_compact(&self) -> [u8; Self::COMPACT_LEN] {
+        let mut bytes = [0; Self::COMPACT_LEN];
+        bytes[..20].copy_from_slice(&self.id);
+        bytes[20..24].copy_from_slice(&self.addr.ip().octets());
+        bytes[24..].copy_from_slice(&self.addr.port().to_be_bytes());
+
+        bytes
+    }
+}
+
+impl NodeInfo6 {
+    /// Length of a node in compact form
+    pub const COMPACT_LEN: usize = 38;
+
+    /// Encodes the node as its id followed by its compact address
+    pub fn to_compact(&self) -> [u8; Self::COMPACT_LEN] {
+        let mut bytes = [0; Self::COMPACT_LEN];
+        bytes[..20].copy_from_slice(&self.id);
+        bytes[20..36].copy_from_slice(&self.addr.ip().octets());
+        bytes[36..].copy_from_slice(&self.addr.port().to_be_bytes());
+
+        bytes
+    }
+}
+
+/// Parses the compact IPv4 node info from `nodes`, failing if the length isn't a multiple of 26
+pub fn parse_compact_nodes(bytes: &[u8]) -> Result<Vec<NodeInfo>, KrpcError> {
+    if !bytes.len().is_multiple_of(NodeInfo::COMPACT_LEN) {
+        return Err(KrpcError::InvalidNodes {
+            length: bytes.len(),
+        });
+    }
+
+    Ok(bytes
+        .chunks_exact(NodeInfo::COMPACT_LEN)
+        .map(|chunk| NodeInfo {
+            id: chunk[..20].try_into().expect("chunk is 26 bytes"),
+            addr: SocketAddrV4::new(
+                [chunk[20], chunk[21], chunk[22], chunk[23]].into(),
+                u16::from_be_bytes([chunk[24], chunk[25]]),
+            ),
+        })
+        .collect())
+}
+
+/// Parses the compact IPv6 node info from `nodes6`, failing if the length isn't a multiple of 38
+pub fn parse_compact_nodes6(bytes: &[u8]) -> Result<Vec<NodeInfo6>, KrpcError> {
+    if !bytes.len().is_multiple_of(NodeInfo6::COMPACT_LEN) {
+        return Err(KrpcError::InvalidNodes {
+            length: bytes.len(),
+        });
+    }
+
+    Ok(bytes
+        .chunks_exact(NodeInfo6::COMPACT_LEN)
+        .map(|chunk| NodeInfo6 {
+            id: chunk[..20].try_into().expect("chunk is 38 bytes"),
+            addr: SocketAddrV6::new(
+                Ipv6Addr::from(<[u8; 16]>::try_from(&chunk[20..36]).expect("chunk is 38 bytes")),
+                u16::from_be_bytes([chunk[36], chunk[37]]),
+                0,
+                0,
+            ),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_v4() {
+        let bytes = b"abcdefghij0123456789\x7f\x00\x00\x01\x1a\xe1mnopqrstuvwxyz123456\x0a\x00\x00\x02\x00\x50";
+        let nodes = parse_compact_nodes(bytes).unwrap();
+
+        assert_eq!(
+            nodes,
+            [
+                NodeInfo {
+                    id: *b"abcdefghij0123456789",
+                    addr: "127.0.0.1:6881".parse().unwrap(),
+                },
+                NodeInfo {
+                    id: *b"mnopqrstuvwxyz123456",
+                    addr: "10.0.0.2:80".parse().unwrap(),
+                },
+            ]
+        );
+        assert_eq!(
+            nodes
+                .iter()
+                .flat_map(NodeInfo::to_compact)
+                .collect::<Vec<_>>(),
+            bytes
+        );
+
+        assert_eq!(parse_compact_nodes(b"").unwrap(), []);
+        assert!(matches!(
+            parse_compact_nodes(&bytes[..30]),
+            Err(KrpcError::InvalidNodes { length: 30 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_v6() {
+        let mut bytes = b"abcdefghij0123456789".to_vec();
+        bytes.extend_from_slice(&[0; 15]);
+        bytes.extend_from_slice(b"\x01\x1a\xe1");
+        let nodes = parse_compact_nodes6(&bytes).unwrap();
+
+        assert_eq!(
+            nodes,
+            [NodeInfo6 {
+                id: *b"abcdefghij0123456789",
+                addr: "[::1]:6881".parse().unwrap(),
+            }]
+        );
+        assert_eq!(nodes[0].to_compact(), &bytes[..]);
+
+        assert!(matches!(
+            parse_compact_nodes6(&bytes[..26]),
+            Err(KrpcError::InvalidNodes { length: 26 })
+        ));
+    }
+}
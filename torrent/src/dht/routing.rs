@@ -0,0 +1,266 @@
+use std::time::{Duration, Instant};
+
+use super::{distance_cmp, xor_distance, NodeInfo};
+
+/// Node in a bucket, with the last time it was heard from
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    node: NodeInfo,
+    last_seen: Instant,
+}
+
+/// Kademlia routing table of k-buckets, from BEP 5.
+///
+/// The table starts as a single bucket covering every id. Only the last bucket, which contains the local id, is ever
+/// split when full, so the table holds many nodes close to the local id and few far from it
+#[derive(Debug, Clone)]
+pub struct RoutingTable {
+    local_id: [u8; 20],
+    /// Bucket `i` holds nodes sharing exactly `i` leading bits with the local id, except the last which holds the rest
+    buckets: Vec<Vec<Entry>>,
+}
+
+impl RoutingTable {
+    /// Number of nodes each bucket holds
+    pub const K: usize = 8;
+    /// Most buckets the table is split into, one for each bit of an id
+    pub const MAX_BUCKETS: usize = 160;
+    /// Time after which a node that hasn't been heard from may be evicted
+    pub const STALE_AFTER: Duration = Duration::from_secs(15 * 60);
+
+    /// Creates an empty table for a node with id `local_id`
+    pub fn new(local_id: [u8; 20]) -> Self {
+        Self {
+            local_id,
+            buckets: vec![Vec::new()],
+        }
+    }
+
+    /// Returns the id of the node owning the table
+    pub fn local_id(&self) -> &[u8; 20] {
+        &self.local_id
+    }
+
+    /// Returns the number of nodes in the table
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(Vec::len).sum()
+    }
+
+    /// Returns true if the table has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(Vec::is_empty)
+    }
+
+    /// Returns the number of buckets the table has been split into
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Records that `node` was heard from, adding it if there is room.
+    ///
+    /// A known node is refreshed. When its bucket is full, the bucket is split if it contains the local id, and
+    /// otherwise the least recently seen node is evicted if it is stale. Returns false if the node was not added
+    pub fn insert(&mut self, node: NodeInfo) -> bool {
+        self.insert_at(node, Instant::now())
+    }
+
+    /// Removes the node with `id`, such as after it stops responding, returning it if it was present
+    pub fn remove(&mut self, id: &[u8; 20]) -> Option<NodeInfo> {
+        let bucket = self.bucket_index(id);
+        let bucket = &mut self.buckets[bucket];
+        let index = bucket.iter().position(|entry| &entry.node.id == id)?;
+
+        Some(bucket.remove(index).node)
+    }
+
+    /// Returns up to `n` nodes closest to `target`, closest first, to answer `find_node` and `get_peers` queries
+    pub fn closest_nodes(&self, target: &[u8; 20], n: usize) -> Vec<NodeInfo> {
+        let mut nodes: Vec<NodeInfo> = self
+            .buckets
+            .iter()
+            .flatten()
+            .map(|entry| entry.node)
+            .collect();
+        nodes.sort_by(|a, b| distance_cmp(target, &a.id, &b.id));
+        nodes.truncate(n);
+
+        nodes
+    }
+
+    /// Inserts a node heard from at `now`
+    fn insert_at(&mut self, node: NodeInfo, now: Instant) -> bool {
+        if node.id == self.local_id {
+            return false;
+        }
+
+        loop {
+            let index = self.bucket_index(&node.id);
+            let bucket = &mut self.buckets[index];
+
+            // known nodes move to the back, keeping each bucket ordered from least to most recently seen
+            if let Some(position) = bucket.iter().position(|entry| entry.node.id == node.id) {
+                bucket.remove(position);
+                bucket.push(Entry {
+                    node,
+                    last_seen: now,
+                });
+                return true;
+            }
+
+            if bucket.len() < Self::K {
+                bucket.push(Entry {
+                    node,
+                    last_seen: now,
+                });
+                return true;
+            }
+
+            if index == self.buckets.len() - 1 && self.buckets.len() < Self::MAX_BUCKETS {
+                self.split_last();
+                continue;
+            }
+
+            let bucket = &mut self.buckets[index];
+            if now.saturating_duration_since(bucket[0].last_seen) < Self::STALE_AFTER {
+                return false;
+            }
+
+            bucket.remove(0);
+            bucket.push(Entry {
+                node,
+                last_seen: now,
+            });
+            return true;
+        }
+    }
+
+    /// Splits the last bucket, moving the nodes sharing more leading bits with the local id into a new last bucket
+    fn split_last(&mut self) {
+        let index = self.buckets.len() - 1;
+        let (stay, moved) = self.buckets[index]
+            .drain(..)
+            .partition(|entry| shared_prefix(&self.local_id, &entry.node.id) == index);
+
+        self.buckets[index] = stay;
+        self.buckets.push(moved);
+    }
+
+    /// Returns the bucket that covers `id`
+    fn bucket_index(&self, id: &[u8; 20]) -> usize {
+        shared_prefix(&self.local_id, id).min(self.buckets.len() - 1)
+    }
+}
+
+/// Returns the number of leading bits two ids have in common, which is 160 if they are equal
+fn shared_prefix(a: &[u8; 20], b: &[u8; 20]) -> usize {
+    let distance = xor_distance(a, b);
+    match distance.iter().position(|&byte| byte != 0) {
+        Some(index) => index * 8 + distance[index].leading_zeros() as usize,
+        None => 160,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddrV4;
+
+    use super::*;
+
+    /// Creates a node whose id is zero except for its first two bytes
+    fn node(first: u8, second: u8) -> NodeInfo {
+        let mut id = [0; 20];
+        id[0] = first;
+        id[1] = second;
+
+        NodeInfo {
+            id,
+            addr: SocketAddrV4::new([10, 0, first, second].into(), 6881),
+        }
+    }
+
+    #[test]
+    fn test_closest_nodes() {
+        let mut table = RoutingTable::new([0; 20]);
+        for first in [0x80, 0x40, 0x20, 0x10, 0x08, 0x04, 0x02, 0x01, 0x03, 0x81] {
+            assert!(table.insert(node(first, 0)));
+        }
+        assert_eq!(table.len(), 10);
+
+        let ids = |nodes: Vec<NodeInfo>| nodes.iter().map(|node| node.id[0]).collect::<Vec<_>>();
+        let mut target = [0; 20];
+        target[0] = 0x03;
+
+        assert_eq!(ids(table.closest_nodes(&target, 3)), [0x03, 0x02, 0x01]);
+        assert_eq!(
+            ids(table.closest_nodes(&[0; 20], 4)),
+            [0x01, 0x02, 0x03, 0x04]
+        );
+        assert_eq!(ids(table.closest_nodes(&[0xff; 20], 2)), [0x81, 0x80]);
+        assert_eq!(table.closest_nodes(&target, 100).len(), 10);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut table = RoutingTable::new([0; 20]);
+        assert!(!table.insert(NodeInfo {
+            id: [0; 20],
+            addr: SocketAddrV4::new([127, 0, 0, 1].into(), 6881),
+        }));
+
+        // the first bucket fills with far nodes, then splits so nodes sharing the first bit with the local id move out
+        for second in 0..8 {
+            assert!(table.insert(node(0x80, second)));
+        }
+        assert_eq!(table.bucket_count(), 1);
+        assert!(table.insert(node(0x40, 0)));
+        assert_eq!(table.bucket_count(), 2);
+
+        // the far bucket no longer contains the local id, so it is full and never splits again
+        assert!(!table.insert(node(0x80, 8)));
+        assert_eq!(table.bucket_count(), 2);
+        assert_eq!(table.len(), 9);
+
+        // refreshing a known node doesn't add another copy
+        assert!(table.insert(node(0x80, 0)));
+        assert_eq!(table.len(), 9);
+
+        // close nodes keep splitting the last bucket
+        for second in 1..=8 {
+            assert!(table.insert(node(0, second)));
+        }
+        assert!(table.bucket_count() > 2);
+        assert_eq!(table.len(), 17);
+
+        assert_eq!(table.remove(&node(0x40, 0).id), Some(node(0x40, 0)));
+        assert_eq!(table.remove(&node(0x40, 0).id), None);
+        assert_eq!(table.len(), 16);
+    }
+
+    #[test]
+    fn test_evict_stale() {
+        let mut table = RoutingTable::new([0; 20]);
+        let start = Instant::now();
+
+        // fill the far bucket after a split, so it can't grow
+        assert!(table.insert_at(node(0, 1), start));
+        for second in 0..8 {
+            assert!(table.insert_at(
+                node(0x80, second),
+                start + Duration::from_secs(second.into())
+            ));
+        }
+        assert_eq!(table.bucket_count(), 2);
+
+        // none of the nodes in the full bucket are stale yet
+        assert!(!table.insert_at(node(0x80, 9), start + Duration::from_secs(60)));
+
+        // once stale, the least recently seen node is replaced
+        let later = start + RoutingTable::STALE_AFTER + Duration::from_secs(1);
+        assert!(table.insert_at(node(0x80, 9), later));
+        assert_eq!(table.len(), 9);
+        assert!(table
+            .closest_nodes(&node(0x80, 0).id, 20)
+            .iter()
+            .all(|found| found.id != node(0x80, 0).id));
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use sha1::{Digest, Sha1};
+
+/// Length of the block used by SHA-1, which HMAC pads its key to
+const BLOCK_LEN: usize = 64;
+
+/// Issues and checks the tokens handed out in `get_peers` responses, from BEP 5.
+///
+/// A token is an HMAC of the requester's IP address keyed by a secret that changes every
+/// [`ROTATE_INTERVAL`](Self::ROTATE_INTERVAL). Tokens made with the current or previous secret are accepted, so each
+/// one is valid for at least one interval and at most two
+#[derive(Debug, Clone)]
+pub struct TokenManager {
+    current: [u8; 20],
+    previous: [u8; 20],
+    rotated: Instant,
+}
+
+impl Default for TokenManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenManager {
+    /// Time each secret is used to issue tokens for
+    pub const ROTATE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+    /// Creates a manager with random secrets
+    pub fn new() -> Self {
+        Self {
+            current: rand::random(),
+            previous: rand::random(),
+            rotated: Instant::now(),
+        }
+    }
+
+    /// Returns the token to give a node at `ip` in a `get_peers` response
+    pub fn issue(&mut self, ip: IpAddr) -> Vec<u8> {
+        self.issue_at(ip, Instant::now())
+    }
+
+    /// Returns true if `token` was issued to a node at `ip` recently enough to accept its `announce_peer`
+    pub fn validate(&mut self, ip: IpAddr, token: &[u8]) -> bool {
+        self.validate_at(ip, token, Instant::now())
+    }
+
+    /// Issues a token at `now`
+    fn issue_at(&mut self, ip: IpAddr, now: Instant) -> Vec<u8> {
+        self.rotate(now);
+
+        hmac(&self.current, &ip_bytes(ip)).to_vec()
+    }
+
+    /// Validates a token at `now`
+    fn validate_at(&mut self, ip: IpAddr, token: &[u8], now: Instant) -> bool {
+        self.rotate(now);
+
+        let ip = ip_bytes(ip);
+        [self.current, self.previous]
+            .iter()
+            .any(|secret| constant_time_eq(&hmac(secret, &ip), token))
+    }
+
+    /// Replaces the secrets that are due to change by `now`
+    fn rotate(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.rotated);
+        if elapsed < Self::ROTATE_INTERVAL {
+            return;
+        }
+
+        // after two intervals even the current secret has expired
+        self.previous = if elapsed < Self::ROTATE_INTERVAL * 2 {
+            self.current
+        } else {
+            rand::random()
+        };
+        self.current = rand::random();
+        self.rotated = now;
+    }
+}
+
+/// Returns the bytes of an IP address, with IPv4 addresses mapped to IPv6 so both forms of an address match
+fn ip_bytes(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped().octets(),
+        IpAddr::V6(ip) => ip.octets(),
+    }
+}
+
+/// Computes HMAC-SHA1 of `message`, from RFC 2104
+fn hmac(key: &[u8; 20], message: &[u8]) -> [u8; 20] {
+    let mut inner_pad = [0x36; BLOCK_LEN];
+    let mut outer_pad = [0x5c; BLOCK_LEN];
+    for (index, byte) in key.iter().enumerate() {
+        inner_pad[index] ^= byte;
+        outer_pad[index] ^= byte;
+    }
+
+    let inner = Sha1::new()
+        .chain_update(inner_pad)
+        .chain_update(message)
+        .finalize();
+
+    Sha1::new()
+        .chain_update(outer_pad)
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// Compares two byte strings without returning early, so the time taken doesn't reveal how much of a token was correct
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::bencoding::Item;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2));
+
+    #[test]
+    fn test_hmac() {
+        // test case 2 from RFC 2202, with the key zero padded to 20 bytes
+        let mut key = [0; 20];
+        key[..4].copy_from_slice(b"Jefe");
+
+        assert_eq!(
+            hmac(&key, b"what do ya want for nothing?"),
+            *b"\xef\xfc\xdf\x6a\xe5\xeb\x2f\xa2\xd2\x74\x16\xd5\xf1\x84\xdf\x9c\x25\x9a\x7c\x79"
+        );
+    }
+
+    #[test]
+    fn test_valid_token() {
+        let mut manager = TokenManager::new();
+        let start = manager.rotated;
+
+        let token = manager.issue_at(IP, start);
+        assert!(manager.validate_at(IP, &token, start));
+        assert!(manager.validate_at(IP, &token, start + TokenManager::ROTATE_INTERVAL));
+
+        // tokens travel in KRPC messages as byte strings
+        let Item::ByteArray(bytes) = Item::from(token) else {
+            panic!("token should be a byte string");
+        };
+        assert!(manager.validate_at(IP, &bytes, start + TokenManager::ROTATE_INTERVAL));
+
+        let mapped = IpAddr::V6(Ipv4Addr::new(192, 168, 1, 2).to_ipv6_mapped());
+        assert_eq!(manager.issue_at(mapped, start), manager.issue_at(IP, start));
+        assert!(!manager.validate_at(IP, b"", start));
+    }
+
+    #[test]
+    fn test_expired_token() {
+        let mut manager = TokenManager::new();
+        let start = manager.rotated;
+
+        let token = manager.issue_at(IP, start);
+        let first = start + TokenManager::ROTATE_INTERVAL;
+        assert!(manager.validate_at(IP, &token, first));
+        assert!(!manager.validate_at(IP, &token, first + TokenManager::ROTATE_INTERVAL));
+
+        // a long gap between requests replaces both secrets at once
+        let mut manager = TokenManager::new();
+        let token = manager.issue_at(IP, start);
+        assert!(!manager.validate_at(IP, &token, start + TokenManager::ROTATE_INTERVAL * 3));
+    }
+
+    #[test]
+    fn test_different_ip() {
+        let mut manager = TokenManager::new();
+        let start = manager.rotated;
+
+        let token = manager.issue_at(IP, start);
+        assert!(!manager.validate_at(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 3)), &token, start));
+        assert!(!manager.validate_at(IpAddr::V6(Ipv6Addr::LOCALHOST), &token, start));
+
+        assert_ne!(TokenManager::new().issue_at(IP, start), token);
+    }
+}
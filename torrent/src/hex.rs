@@ -0,0 +1,41 @@
+//! Hexadecimal encoding of hashes
+
+/// Encodes bytes as lowercase hex digits
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes exactly `2 * N` hex digits of either case into `N` bytes
+pub(crate) fn decode<const N: usize>(encoded: &str) -> Option<[u8; N]> {
+    if encoded.len() != 2 * N || !encoded.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+    for (byte, digits) in bytes.iter_mut().zip(encoded.as_bytes().chunks_exact(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(&[0x00, 0xff, 0x7f]), "00ff7f");
+        assert_eq!(decode::<3>(&encode(&[1, 2, 3])), Some([1, 2, 3]));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("00ff7F"), Some([0x00, 0xff, 0x7f]));
+        assert_eq!(decode::<2>(""), None);
+        assert_eq!(decode::<2>("00f"), None);
+        assert_eq!(decode::<2>("00ff00"), None);
+        assert_eq!(decode::<1>("+1"), None);
+        assert_eq!(decode::<1>("zz"), None);
+    }
+}
@@ -1 +1,27 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// the test harness and helpers always need std, even when the crate itself doesn't
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
 pub mod bencoding;
+#[cfg(feature = "std")]
+pub mod dht;
+#[cfg(feature = "std")]
+mod hex;
+#[cfg(feature = "std")]
+pub mod magnet;
+#[cfg(feature = "std")]
+pub mod metainfo;
+#[cfg(feature = "std")]
+pub mod peer;
+#[cfg(feature = "std")]
+mod percent;
+#[cfg(feature = "std")]
+pub mod resume;
+#[cfg(feature = "std")]
+pub mod tracker;
+
+#[cfg(feature = "serde")]
+pub use bencoding::{from_bytes, to_bytes, SerdeError};
@@ -0,0 +1,207 @@
+mod error;
+
+use crate::{hex, percent};
+
+pub use error::MagnetError;
+
+/// Represents a magnet URI identifying a BitTorrent torrent, from BEP 9
+#[derive(Debug, PartialEq, Clone)]
+pub struct Magnet {
+    /// SHA-1 hash of the info dictionary of the torrent
+    pub info_hash: [u8; 20],
+    /// Suggested name to display while the metadata is downloaded
+    pub display_name: Option<String>,
+    pub trackers: Vec<String>,
+    /// Total size of the torrent in bytes
+    pub exact_length: Option<u64>,
+    /// URLs of web seeds serving the torrent, from BEP 19
+    pub web_seeds: Vec<String>,
+}
+
+/// Prefix of the exact topic identifying a BitTorrent info hash
+const BTIH_PREFIX: &str = "urn:btih:";
+
+/// Decodes an info hash encoded as 32 base32 characters, from RFC 4648
+fn decode_base32(encoded: &str) -> Option<[u8; 20]> {
+    if encoded.len() != 32 {
+        return None;
+    }
+
+    let mut hash = [0; 20];
+    // every 8 characters hold 40 bits, which is 5 bytes
+    for (bytes, chars) in hash
+        .chunks_exact_mut(5)
+        .zip(encoded.as_bytes().chunks_exact(8))
+    {
+        let mut bits = 0u64;
+        for &char in chars {
+            let value = match char.to_ascii_uppercase() {
+                letter @ b'A'..=b'Z' => letter - b'A',
+                digit @ b'2'..=b'7' => digit - b'2' + 26,
+                _ => return None,
+            };
+            bits = (bits << 5) | value as u64;
+        }
+
+        bytes.copy_from_slice(&bits.to_be_bytes()[3..]);
+    }
+
+    Some(hash)
+}
+
+impl Magnet {
+    /// Parses a magnet URI, ignoring parameters that are not used by BitTorrent
+    pub fn parse(uri: &str) -> Result<Self, MagnetError> {
+        let query = uri
+            .strip_prefix("magnet:?")
+            .ok_or(MagnetError::InvalidScheme)?;
+
+        let mut info_hash = None;
+        let mut display_name = None;
+        let mut trackers = Vec::new();
+        let mut exact_length = None;
+        let mut web_seeds = Vec::new();
+
+        for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+            let value = percent::decode(value)
+                .and_then(|value| String::from_utf8(value).ok())
+                .ok_or_else(|| MagnetError::InvalidEncoding {
+                    parameter: key.to_owned(),
+                })?;
+
+            match key {
+                "xt" => {
+                    // other exact topics, such as BitTorrent v2 hashes, may also be present
+                    let Some(hash) = value.strip_prefix(BTIH_PREFIX) else {
+                        continue;
+                    };
+
+                    if info_hash.is_none() {
+                        info_hash = Some(
+                            hex::decode(hash)
+                                .or_else(|| decode_base32(hash))
+                                .ok_or(MagnetError::InvalidInfoHash)?,
+                        );
+                    }
+                }
+                "dn" => display_name = Some(value),
+                "tr" => trackers.push(value),
+                "xl" => exact_length = Some(value.parse().map_err(|_| MagnetError::InvalidLength)?),
+                "ws" => web_seeds.push(value),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            info_hash: info_hash.ok_or(MagnetError::MissingInfoHash)?,
+            display_name,
+            trackers,
+            exact_length,
+            web_seeds,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metainfo::MetaInfo;
+
+    const SAMPLE_HASH: [u8; 20] = [
+        0xd0, 0xd1, 0x4c, 0x92, 0x6e, 0x6e, 0x99, 0x76, 0x1a, 0x2f, 0xdc, 0xff, 0x27, 0xb4, 0x03,
+        0xd9, 0x63, 0x76, 0xef, 0xf6,
+    ];
+
+    #[test]
+    fn test_parse_hex() {
+        let magnet =
+            Magnet::parse("magnet:?xt=urn:btih:D0D14C926E6E99761A2FDCFF27B403D96376EFF6").unwrap();
+
+        assert_eq!(
+            magnet,
+            Magnet {
+                info_hash: SAMPLE_HASH,
+                display_name: None,
+                trackers: vec![],
+                exact_length: None,
+                web_seeds: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_base32() {
+        let magnet = Magnet::parse("magnet:?xt=urn:btih:2DIUZETON2MXMGRP3T7SPNAD3FRXN37W").unwrap();
+        assert_eq!(magnet.info_hash, SAMPLE_HASH);
+
+        let lowercase =
+            Magnet::parse("magnet:?xt=urn:btih:2diuzeton2mxmgrp3t7spnad3frxn37w").unwrap();
+        assert_eq!(lowercase.info_hash, SAMPLE_HASH);
+    }
+
+    #[test]
+    fn test_parse_all_fields() {
+        let magnet = Magnet::parse(
+            "magnet:?xt=urn:btih:d0d14c926e6e99761a2fdcff27b403d96376eff6&dn=sample+file%2Etxt&xl=20\
+            &tr=udp%3A%2F%2Fone%3A80&tr=http%3A%2F%2Ftwo%2Fannounce&ws=http%3A%2F%2Fseed&x.pe=1.2.3.4%3A5",
+        )
+        .unwrap();
+
+        assert_eq!(
+            magnet,
+            Magnet {
+                info_hash: SAMPLE_HASH,
+                display_name: Some("sample file.txt".to_owned()),
+                trackers: vec!["udp://one:80".to_owned(), "http://two/announce".to_owned()],
+                exact_length: Some(20),
+                web_seeds: vec!["http://seed".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        let magnet = Magnet::parse(&meta.magnet_link()).unwrap();
+
+        assert_eq!(magnet.info_hash, meta.info_hash());
+        assert_eq!(magnet.display_name.as_deref(), Some("sample.txt"));
+        assert_eq!(magnet.trackers, meta.trackers());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Magnet::parse(
+                "http://example.com/?xt=urn:btih:d0d14c926e6e99761a2fdcff27b403d96376eff6"
+            ),
+            Err(MagnetError::InvalidScheme)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?dn=name"),
+            Err(MagnetError::MissingInfoHash)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:sha1:d0d14c926e6e99761a2fdcff27b403d96376eff6"),
+            Err(MagnetError::MissingInfoHash)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:btih:d0d14c"),
+            Err(MagnetError::InvalidInfoHash)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:btih:z0d14c926e6e99761a2fdcff27b403d96376eff6"),
+            Err(MagnetError::InvalidInfoHash)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:btih:d0d14c926e6e99761a2fdcff27b403d96376eff6&xl=-1"),
+            Err(MagnetError::InvalidLength)
+        );
+        assert_eq!(
+            Magnet::parse("magnet:?xt=urn:btih:d0d14c926e6e99761a2fdcff27b403d96376eff6&dn=%ff"),
+            Err(MagnetError::InvalidEncoding {
+                parameter: "dn".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+/// Represents an error encountered while parsing a magnet URI
+#[derive(Debug, PartialEq, Clone)]
+pub enum MagnetError {
+    /// URI did not start with `magnet:?`
+    InvalidScheme,
+    /// No `xt` parameter contained a BitTorrent info hash
+    MissingInfoHash,
+    /// Info hash was neither 40 hex characters nor 32 base32 characters
+    InvalidInfoHash,
+    /// Exact length was not a non-negative integer
+    InvalidLength,
+    /// Parameter value was not correctly percent-encoded UTF-8
+    InvalidEncoding { parameter: String },
+}
+
+impl Display for MagnetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidScheme => write!(f, "URI is not a magnet link"),
+            Self::MissingInfoHash => write!(f, "magnet link has no BitTorrent info hash"),
+            Self::InvalidInfoHash => write!(f, "magnet link has an invalid info hash"),
+            Self::InvalidLength => write!(f, "magnet link has an invalid exact length"),
+            Self::InvalidEncoding { parameter } => {
+                write!(
+                    f,
+                    "magnet link parameter {parameter:?} is not correctly encoded"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MagnetError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (MagnetError::InvalidScheme, "URI is not a magnet link"),
+            (
+                MagnetError::MissingInfoHash,
+                "magnet link has no BitTorrent info hash",
+            ),
+            (
+                MagnetError::InvalidInfoHash,
+                "magnet link has an invalid info hash",
+            ),
+            (
+                MagnetError::InvalidLength,
+                "magnet link has an invalid exact length",
+            ),
+            (
+                MagnetError::InvalidEncoding {
+                    parameter: "dn".to_owned(),
+                },
+                "magnet link parameter \"dn\" is not correctly encoded",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+}
@@ -0,0 +1,1792 @@
+mod builder;
+mod encode;
+mod error;
+mod file_tree;
+mod validate;
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use rand::{seq::SliceRandom, Rng};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::{
+    bencoding::{BencodeError, DecodeOptions, Item},
+    hex, percent,
+    tracker::{self, AnnounceParams},
+};
+
+pub use builder::TorrentBuilder;
+pub use error::{BuildError, MetaInfoError, ValidationError};
+
+/// Represents the contents of a .torrent file
+#[derive(Debug, PartialEq, Clone)]
+pub struct MetaInfo {
+    /// URL of the tracker, absent for torrents that only use other peer sources
+    pub announce: Option<String>,
+    pub info: Info,
+    pub comment: Option<String>,
+    pub created_by: Option<String>,
+    /// Seconds since the Unix epoch
+    creation_date: Option<i64>,
+    /// Tiers of tracker URLs, from BEP 12
+    announce_list: Option<Vec<Vec<String>>>,
+    /// DHT nodes to bootstrap from as host and port pairs, from BEP 5
+    nodes: Option<Vec<(String, u16)>>,
+    /// HTTP and FTP servers hosting the files, from BEP 19 and BEP 17
+    web_seeds: Vec<String>,
+    /// Hash identifying the torrent on the wire, which is the truncated SHA-256 hash for v2 torrents
+    info_hash: [u8; 20],
+    /// SHA-1 hash of the info dictionary, for v1 and hybrid torrents
+    info_hash_v1: Option<[u8; 20]>,
+    /// SHA-256 hash of the info dictionary, for v2 and hybrid torrents
+    info_hash_v2: Option<[u8; 32]>,
+    /// Hashes of the pieces of each file larger than one piece, keyed by the file's pieces root, from BEP 52
+    piece_layers: HashMap<[u8; 32], Vec<[u8; 32]>>,
+    /// Charset of byte strings that aren't UTF-8, set by some older clients
+    encoding: Option<String>,
+}
+
+/// Version of the BitTorrent protocol a torrent was created for
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TorrentVersion {
+    /// Original format, with SHA-1 piece hashes in `pieces`
+    V1,
+    /// Format from BEP 52, with a `file tree` of per-file SHA-256 merkle roots
+    V2,
+    /// Torrent with both v1 and v2 metadata describing the same files, usable by clients supporting either
+    Hybrid,
+}
+
+/// Represents the info dictionary of a torrent, which describes the files being shared
+#[derive(Debug, PartialEq, Clone)]
+pub struct Info {
+    /// Suggested name of the file, or of the directory for multi-file torrents, converted lossily if it isn't valid UTF-8
+    pub name: String,
+    /// Original bytes of the name when it isn't valid UTF-8, so re-encoding preserves the info hash
+    raw_name: Option<Vec<u8>>,
+    /// Number of bytes in each piece, except possibly the last
+    pub piece_length: u64,
+    /// Concatenated SHA-1 hashes of each piece, empty for v2 torrents
+    pub pieces: Vec<u8>,
+    /// Layout of the files, which for v2 torrents is derived from the file tree
+    pub mode: FileKind,
+    /// Files of a v2 or hybrid torrent sorted by path, each with the root of its piece hashes
+    pub file_tree: Option<Vec<FileEntry>>,
+    /// Whether peers may only be found through the trackers, from BEP 27
+    private: bool,
+    /// Raw bytes of the `source` key, which private trackers set so the info hash is unique to them
+    source: Option<Vec<u8>>,
+    version: TorrentVersion,
+    /// Optional MD5 hash of the contents of a single file torrent
+    md5sum: Option<[u8; 16]>,
+}
+
+/// Layout of the files in a torrent
+#[derive(Debug, PartialEq, Clone)]
+pub enum FileKind {
+    /// Single file named after the torrent
+    Single { length: u64 },
+    /// Directory named after the torrent containing several files
+    Multi { files: Vec<FileEntry> },
+}
+
+/// Index of a file within a torrent, where a single file torrent has one file at index 0
+pub type FileIndex = usize;
+
+/// Index of a piece within a torrent
+pub type PieceIndex = usize;
+
+/// Represents a single file of a multi-file torrent
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileEntry {
+    pub length: u64,
+    /// Path relative to the torrent directory, joined from the path components
+    pub path: PathBuf,
+    /// Merkle root of the SHA-256 hashes of the file's blocks, present for non-empty files of v2 torrents
+    pub pieces_root: Option<[u8; 32]>,
+    /// Optional MD5 hash of the file's contents
+    md5sum: Option<[u8; 16]>,
+    /// Flags from the `attr` key, from BEP 47
+    pub attr: FileAttributes,
+    /// Target of a symlink relative to the torrent directory, from BEP 47
+    symlink_path: Option<PathBuf>,
+}
+
+/// Flags describing a file, parsed from the characters of its `attr` key
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct FileAttributes {
+    /// `l`: the file is a symlink
+    pub symlink: bool,
+    /// `x`: the file should be marked executable
+    pub executable: bool,
+    /// `h`: the file should be hidden
+    pub hidden: bool,
+    /// `p`: the file only aligns the next file to a piece boundary, and should not be written to disk
+    pub padding: bool,
+}
+
+impl FileAttributes {
+    /// Parses the characters of an `attr` key, ignoring any this crate doesn't know
+    fn from_bytes(attr: &[u8]) -> Self {
+        Self {
+            symlink: attr.contains(&b'l'),
+            executable: attr.contains(&b'x'),
+            hidden: attr.contains(&b'h'),
+            padding: attr.contains(&b'p'),
+        }
+    }
+
+    /// Converts the flags back into the characters of an `attr` key
+    fn to_bytes(self) -> Vec<u8> {
+        [
+            (self.symlink, b'l'),
+            (self.executable, b'x'),
+            (self.hidden, b'h'),
+            (self.padding, b'p'),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+}
+
+/// Looks up a required key in a dictionary
+fn required<'a>(item: &'a Item, key: &str, field: &'static str) -> Result<&'a Item, MetaInfoError> {
+    item.get(key).ok_or(MetaInfoError::MissingField { field })
+}
+
+/// Converts an item to a string, failing if it is not a UTF-8 byte array
+fn to_string(item: &Item, field: &'static str) -> Result<String, MetaInfoError> {
+    item.as_str()
+        .map(str::to_owned)
+        .ok_or(MetaInfoError::InvalidField { field })
+}
+
+/// Converts an item to a non-negative integer
+fn to_length(item: &Item, field: &'static str) -> Result<u64, MetaInfoError> {
+    item.as_integer()
+        .and_then(|number| u64::try_from(number).ok())
+        .ok_or(MetaInfoError::InvalidField { field })
+}
+
+/// Looks up an optional string, failing only if it is present with the wrong type
+fn optional_string(
+    item: &Item,
+    key: &str,
+    field: &'static str,
+) -> Result<Option<String>, MetaInfoError> {
+    item.get(key)
+        .map(|value| to_string(value, field))
+        .transpose()
+}
+
+/// Checks a path component can't escape the download directory, rejecting `.`, `..` and names containing separators,
+/// which would otherwise be split into several components
+fn check_component(component: &str, field: &'static str) -> Result<(), MetaInfoError> {
+    if matches!(component, "." | "..") || component.contains(['/', '\\']) {
+        return Err(MetaInfoError::PathTraversal {
+            field,
+            component: component.to_owned(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Converts the value of a piece layers key into the piece hashes of each file, keyed by pieces root
+fn parse_piece_layers(item: &Item) -> Result<HashMap<[u8; 32], Vec<[u8; 32]>>, MetaInfoError> {
+    const INVALID: MetaInfoError = MetaInfoError::InvalidField {
+        field: "piece layers",
+    };
+
+    // roots are raw hashes, so the keys are usually not valid UTF-8
+    let entries: Vec<(&[u8], &Item)> = match item {
+        Item::Dictionary(dict) => dict
+            .iter()
+            .map(|(key, value)| (key.as_bytes(), value))
+            .collect(),
+        Item::RawDictionary(dict) => dict
+            .iter()
+            .map(|(key, value)| (key.as_slice(), value))
+            .collect(),
+        _ => return Err(INVALID),
+    };
+
+    entries
+        .into_iter()
+        .map(|(root, layer)| {
+            let root = root.try_into().map_err(|_| INVALID)?;
+            let layer = layer.as_bytes().ok_or(INVALID)?;
+            if !layer.len().is_multiple_of(32) {
+                return Err(INVALID);
+            }
+
+            let hashes = layer
+                .chunks_exact(32)
+                .map(|hash| hash.try_into().expect("chunks are 32 bytes"))
+                .collect();
+
+            Ok((root, hashes))
+        })
+        .collect()
+}
+
+/// Converts the value of an announce-list key into tiers of tracker URLs
+fn parse_announce_list(item: &Item) -> Result<Vec<Vec<String>>, MetaInfoError> {
+    const FIELD: &str = "announce-list";
+
+    item.as_list()
+        .ok_or(MetaInfoError::InvalidField { field: FIELD })?
+        .iter()
+        .map(|tier| {
+            tier.as_list()
+                .ok_or(MetaInfoError::InvalidField { field: FIELD })?
+                .iter()
+                .map(|tracker| to_string(tracker, FIELD))
+                .collect()
+        })
+        .collect()
+}
+
+/// Converts the value of a nodes key into host and port pairs
+fn parse_nodes(item: &Item) -> Result<Vec<(String, u16)>, MetaInfoError> {
+    const FIELD: &str = "nodes";
+
+    item.as_list()
+        .ok_or(MetaInfoError::InvalidField { field: FIELD })?
+        .iter()
+        .map(|node| match node.as_list() {
+            Some([host, port]) => Ok((
+                to_string(host, FIELD)?,
+                port.as_integer()
+                    .and_then(|port| u16::try_from(port).ok())
+                    .ok_or(MetaInfoError::InvalidField { field: FIELD })?,
+            )),
+            _ => Err(MetaInfoError::InvalidField { field: FIELD }),
+        })
+        .collect()
+}
+
+/// Collects web seeds from `url-list`, which may be a single URL or a list, and the older `httpseeds` list
+fn parse_web_seeds(item: &Item) -> Result<Vec<String>, MetaInfoError> {
+    let mut web_seeds: Vec<String> = Vec::new();
+
+    let url_list = match item.get("url-list") {
+        Some(Item::List(urls)) => urls
+            .iter()
+            .map(|url| to_string(url, "url-list"))
+            .collect::<Result<_, _>>()?,
+        Some(url) => vec![to_string(url, "url-list")?],
+        None => Vec::new(),
+    };
+    let http_seeds = match item.get("httpseeds") {
+        Some(Item::List(urls)) => urls
+            .iter()
+            .map(|url| to_string(url, "httpseeds"))
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(MetaInfoError::InvalidField { field: "httpseeds" }),
+        None => Vec::new(),
+    };
+
+    // some clients write an empty url-list rather than omitting it
+    for url in url_list.into_iter().chain(http_seeds) {
+        if !url.is_empty() && !web_seeds.contains(&url) {
+            web_seeds.push(url);
+        }
+    }
+
+    Ok(web_seeds)
+}
+
+/// Reads the private flag of an info dictionary, which is only set by a value of 1
+fn parse_private(info: &Item) -> Result<bool, MetaInfoError> {
+    let private = info
+        .get("private")
+        .map(|private| {
+            private.as_integer().ok_or(MetaInfoError::InvalidField {
+                field: "info.private",
+            })
+        })
+        .transpose()?;
+
+    Ok(private == Some(1))
+}
+
+/// Joins a non-empty list of path components, rejecting any that could escape the download directory
+fn to_path(item: &Item, field: &'static str) -> Result<PathBuf, MetaInfoError> {
+    let components = item
+        .as_list()
+        .filter(|components| !components.is_empty())
+        .ok_or(MetaInfoError::InvalidField { field })?;
+
+    let mut path = PathBuf::new();
+    for component in components {
+        let component = to_string(component, field)?;
+        check_component(&component, field)?;
+
+        path.push(component);
+    }
+
+    Ok(path)
+}
+
+/// Parses the length of a file, which symlinks may omit as they have no contents
+fn parse_file_length(
+    item: &Item,
+    symlink_path: &Option<PathBuf>,
+    field: &'static str,
+) -> Result<u64, MetaInfoError> {
+    match item.get("length") {
+        Some(length) => to_length(length, field),
+        None if symlink_path.is_some() => Ok(0),
+        None => Err(MetaInfoError::MissingField { field }),
+    }
+}
+
+/// Parses the optional `attr` key of a file
+fn parse_attr(item: &Item, field: &'static str) -> Result<FileAttributes, MetaInfoError> {
+    item.get("attr")
+        .map(|attr| {
+            attr.as_bytes()
+                .map(FileAttributes::from_bytes)
+                .ok_or(MetaInfoError::InvalidField { field })
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Looks up an optional MD5 hash, which must be 32 hex digits if present
+fn parse_md5sum(item: &Item, field: &'static str) -> Result<Option<[u8; 16]>, MetaInfoError> {
+    item.get("md5sum")
+        .map(|md5sum| {
+            md5sum
+                .as_str()
+                .and_then(hex::decode)
+                .ok_or(MetaInfoError::InvalidField { field })
+        })
+        .transpose()
+}
+
+/// Checks the lengths of every file can be summed without overflowing, so [`Info::total_length`] succeeds for any
+/// parsed torrent
+fn check_total_length(mode: &FileKind, field: &'static str) -> Result<(), MetaInfoError> {
+    if let FileKind::Multi { files } = mode {
+        files
+            .iter()
+            .try_fold(0u64, |total, file| total.checked_add(file.length))
+            .ok_or(MetaInfoError::InvalidField { field })?;
+    }
+
+    Ok(())
+}
+
+impl MetaInfo {
+    /// Parses a torrent from a byte array containing exactly one BEncoded dictionary
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MetaInfoError> {
+        // the keys of piece layers are raw hashes rather than strings
+        let (decoded, spans) = DecodeOptions::new()
+            .allow_raw_keys(true)
+            .decode_with_spans(bytes)?;
+        if let Some(trailing) = spans.get(1) {
+            return Err(BencodeError::TrailingData {
+                offset: trailing.start,
+            }
+            .into());
+        }
+
+        let root = decoded.root().expect("decoding produces at least one item");
+
+        Self::parse(root, |_| {
+            let info = spans[0]
+                .get("info")
+                .expect("info has a span if it was decoded");
+
+            Cow::Borrowed(&bytes[info.range()])
+        })
+    }
+
+    /// Parses a torrent by first reading the file to a byte buffer
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, MetaInfoError> {
+        let data = std::fs::read(path).map_err(BencodeError::from)?;
+
+        Self::from_bytes(&data)
+    }
+
+    /// Extracts the torrent fields from a decoded item.
+    ///
+    /// The info hash is computed by re-encoding the info dictionary, so only matches the original file if it was canonical
+    pub fn from_item(item: &Item) -> Result<Self, MetaInfoError> {
+        Self::parse(item, |info| Cow::Owned(info.encode()))
+    }
+
+    /// Extracts the torrent fields from a decoded item, hashing the bytes of the info dictionary returned by `info_bytes`
+    fn parse<'a>(
+        item: &Item,
+        info_bytes: impl FnOnce(&Item) -> Cow<'a, [u8]>,
+    ) -> Result<Self, MetaInfoError> {
+        if item.as_dict().is_none() {
+            return Err(MetaInfoError::InvalidField { field: "root" });
+        }
+
+        let info = required(item, "info", "info")?;
+        let parsed = Info::from_item(info)?;
+
+        let info_bytes = info_bytes(info);
+        let info_hash_v1: Option<[u8; 20]> = match parsed.version() {
+            TorrentVersion::V1 | TorrentVersion::Hybrid => Some(Sha1::digest(&info_bytes).into()),
+            TorrentVersion::V2 => None,
+        };
+        let info_hash_v2: Option<[u8; 32]> = match parsed.version() {
+            TorrentVersion::V2 | TorrentVersion::Hybrid => Some(Sha256::digest(&info_bytes).into()),
+            TorrentVersion::V1 => None,
+        };
+
+        // hybrid torrents are identified by their v1 hash so older clients can join the swarm
+        let info_hash = match (info_hash_v1, info_hash_v2) {
+            (Some(hash), _) => hash,
+            (None, Some(hash)) => hash[..20].try_into().expect("hash is 32 bytes"),
+            (None, None) => unreachable!("every version has at least one hash"),
+        };
+
+        Ok(Self {
+            announce: optional_string(item, "announce", "announce")?,
+            info: parsed,
+            comment: optional_string(item, "comment", "comment")?,
+            created_by: optional_string(item, "created by", "created by")?,
+            creation_date: item
+                .get("creation date")
+                .map(|date| {
+                    date.as_integer().ok_or(MetaInfoError::InvalidField {
+                        field: "creation date",
+                    })
+                })
+                .transpose()?,
+            announce_list: item
+                .get("announce-list")
+                .map(parse_announce_list)
+                .transpose()?,
+            nodes: item.get("nodes").map(parse_nodes).transpose()?,
+            web_seeds: parse_web_seeds(item)?,
+            info_hash,
+            info_hash_v1,
+            info_hash_v2,
+            piece_layers: item
+                .get("piece layers")
+                .map(parse_piece_layers)
+                .transpose()?
+                .unwrap_or_default(),
+            encoding: optional_string(item, "encoding", "encoding")?,
+        })
+    }
+
+    /// Returns when the torrent was created, or None if absent or not representable
+    pub fn creation_date(&self) -> Option<SystemTime> {
+        let seconds = u64::try_from(self.creation_date?).ok()?;
+
+        SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds))
+    }
+
+    /// Returns the tiers of tracker URLs, where trackers in earlier tiers should be tried first
+    pub fn announce_list(&self) -> Option<&[Vec<String>]> {
+        self.announce_list.as_deref()
+    }
+
+    /// Returns the DHT nodes of a trackerless torrent as host and port pairs
+    pub fn nodes(&self) -> Option<&[(String, u16)]> {
+        self.nodes.as_deref()
+    }
+
+    /// Returns the URLs of servers the files can be downloaded from directly, without duplicates
+    pub fn web_seeds(&self) -> &[String] {
+        &self.web_seeds
+    }
+
+    /// Returns the charset declared for byte strings that aren't UTF-8, such as `GBK` or `Shift_JIS`
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /// Decodes the name using the charset declared by [`MetaInfo::encoding`].
+    ///
+    /// Falls back to lossy UTF-8 if no charset is declared or it isn't recognised
+    #[cfg(feature = "encoding")]
+    pub fn decode_name_with_encoding(&self) -> Cow<'_, str> {
+        let name = self.info.name_bytes();
+
+        match self
+            .encoding()
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        {
+            Some(encoding) => encoding.decode_without_bom_handling(name).0,
+            None => String::from_utf8_lossy(name),
+        }
+    }
+
+    /// Returns the piece hashes of every file larger than one piece in a v2 or hybrid torrent, keyed by pieces root
+    pub fn piece_layers(&self) -> &HashMap<[u8; 32], Vec<[u8; 32]>> {
+        &self.piece_layers
+    }
+
+    /// Returns the SHA-256 hashes of each piece of a file from the file tree, if it spans more than one piece.
+    ///
+    /// The pieces root of a file no larger than one piece is the hash of its only piece, so it has no layer
+    pub fn piece_layer(&self, file: &FileEntry) -> Option<&[[u8; 32]]> {
+        self.piece_layers
+            .get(file.pieces_root.as_ref()?)
+            .map(Vec::as_slice)
+    }
+
+    /// Returns every tracker URL without duplicates, starting with the primary announce URL and then each tier in order
+    pub fn trackers(&self) -> Vec<&str> {
+        let mut trackers: Vec<&str> = Vec::new();
+
+        let tiers = self.announce_list.iter().flatten().flatten();
+        for tracker in self.announce.iter().chain(tiers) {
+            if !trackers.contains(&tracker.as_str()) {
+                trackers.push(tracker);
+            }
+        }
+
+        trackers
+    }
+
+    /// Returns the tiers of trackers with the URLs in each tier shuffled, as BEP 12 requires on load.
+    ///
+    /// Without an announce list, the primary announce URL forms a single tier
+    pub fn shuffled_trackers(&self, rng: &mut impl Rng) -> Vec<Vec<String>> {
+        let mut tiers = match (&self.announce_list, &self.announce) {
+            (Some(tiers), _) => tiers.clone(),
+            (None, Some(announce)) => vec![vec![announce.clone()]],
+            (None, None) => Vec::new(),
+        };
+
+        for tier in &mut tiers {
+            tier.shuffle(rng);
+        }
+
+        tiers
+    }
+
+    /// Records a successful announce by moving `tracker` to the front of its tier, keeping the order of the others.
+    ///
+    /// Returns false if the tracker is not in any tier
+    pub fn promote_tracker(tiers: &mut [Vec<String>], tracker: &str) -> bool {
+        for tier in tiers {
+            if let Some(index) = tier.iter().position(|url| url == tracker) {
+                tier[..=index].rotate_right(1);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Returns the hash identifying the torrent to trackers and peers.
+    ///
+    /// This is the SHA-1 hash of the info dictionary, or the truncated SHA-256 hash for v2 torrents
+    pub fn info_hash(&self) -> [u8; 20] {
+        self.info_hash
+    }
+
+    /// Returns the SHA-1 hash of the info dictionary, for v1 and hybrid torrents
+    pub fn info_hash_v1(&self) -> Option<[u8; 20]> {
+        self.info_hash_v1
+    }
+
+    /// Returns the full SHA-256 hash of the info dictionary, for v2 and hybrid torrents
+    pub fn info_hash_v2(&self) -> Option<[u8; 32]> {
+        self.info_hash_v2
+    }
+
+    /// Builds the URL of an announce request to the primary tracker, or the first tracker of the announce list if there is none.
+    ///
+    /// Returns None if the torrent has no trackers
+    pub fn announce_url(&self, params: &AnnounceParams) -> Option<String> {
+        let tracker = self.trackers().into_iter().next()?;
+
+        Some(tracker::announce_url(tracker, &self.info_hash, params))
+    }
+
+    /// Returns a magnet URI identifying the torrent by its info hash, including its name and every tracker
+    pub fn magnet_link(&self) -> String {
+        let mut link = format!(
+            "magnet:?xt=urn:btih:{}&dn={}",
+            hex::encode(&self.info_hash),
+            percent::encode(self.info.name.as_bytes())
+        );
+        for tracker in self.trackers() {
+            link.push_str("&tr=");
+            link.push_str(&percent::encode(tracker.as_bytes()));
+        }
+
+        link
+    }
+}
+
+impl Info {
+    /// Extracts the info fields from a decoded info dictionary
+    pub fn from_item(item: &Item) -> Result<Self, MetaInfoError> {
+        if item.as_dict().is_none() {
+            return Err(MetaInfoError::InvalidField { field: "info" });
+        }
+
+        let raw_name = required(item, "name", "info.name")?
+            .as_bytes()
+            .ok_or(MetaInfoError::InvalidField { field: "info.name" })?;
+        let (name, raw_name) = match std::str::from_utf8(raw_name) {
+            Ok(name) => (name.to_owned(), None),
+            Err(_) => (
+                String::from_utf8_lossy(raw_name).into_owned(),
+                Some(raw_name.to_vec()),
+            ),
+        };
+        let version = match item.get("meta version").map(Item::as_integer) {
+            None | Some(Some(1)) => TorrentVersion::V1,
+            // hybrid torrents keep the v1 piece hashes alongside the file tree
+            Some(Some(2)) if item.get("pieces").is_some() => TorrentVersion::Hybrid,
+            Some(Some(2)) => TorrentVersion::V2,
+            Some(_) => {
+                return Err(MetaInfoError::InvalidField {
+                    field: "info.meta version",
+                })
+            }
+        };
+        let piece_length = to_length(
+            required(item, "piece length", "info.piece length")?,
+            "info.piece length",
+        )?;
+        let private = parse_private(item)?;
+        let source = item
+            .get("source")
+            .map(|source| {
+                source
+                    .as_bytes()
+                    .map(<[u8]>::to_vec)
+                    .ok_or(MetaInfoError::InvalidField {
+                        field: "info.source",
+                    })
+            })
+            .transpose()?;
+
+        if version == TorrentVersion::V2 {
+            let files = file_tree::parse_file_tree(required(item, "file tree", "info.file tree")?)?;
+
+            // a single file torrent has one file named after the torrent at the root of the tree
+            let mode = match &files[..] {
+                [file] if file.path == Path::new(&name) => FileKind::Single {
+                    length: file.length,
+                },
+                _ => FileKind::Multi {
+                    files: files.clone(),
+                },
+            };
+            check_total_length(&mode, "info.file tree.length")?;
+
+            return Ok(Self {
+                name,
+                raw_name,
+                piece_length,
+                pieces: Vec::new(),
+                mode,
+                file_tree: Some(files),
+                private,
+                source,
+                version,
+                md5sum: None,
+            });
+        }
+
+        let mode = match (item.get("length"), item.get("files")) {
+            (Some(length), None) => FileKind::Single {
+                length: to_length(length, "info.length")?,
+            },
+            (None, Some(files)) => FileKind::Multi {
+                files: files
+                    .as_list()
+                    .ok_or(MetaInfoError::InvalidField {
+                        field: "info.files",
+                    })?
+                    .iter()
+                    .map(FileEntry::from_item)
+                    .collect::<Result<_, _>>()?,
+            },
+            // exactly one of the keys must be present
+            (Some(_), Some(_)) => {
+                return Err(MetaInfoError::InvalidField {
+                    field: "info.files",
+                })
+            }
+            (None, None) => {
+                return Err(MetaInfoError::MissingField {
+                    field: "info.length",
+                })
+            }
+        };
+        check_total_length(&mode, "info.files.length")?;
+
+        let file_tree = match version {
+            TorrentVersion::Hybrid => {
+                let files =
+                    file_tree::parse_file_tree(required(item, "file tree", "info.file tree")?)?;
+                if !file_tree::matches_v1(&name, &mode, &files) {
+                    return Err(MetaInfoError::InvalidField {
+                        field: "info.file tree",
+                    });
+                }
+
+                Some(files)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            name,
+            raw_name,
+            piece_length,
+            pieces: required(item, "pieces", "info.pieces")?
+                .as_bytes()
+                .ok_or(MetaInfoError::InvalidField {
+                    field: "info.pieces",
+                })?
+                .to_vec(),
+            mode,
+            file_tree,
+            private,
+            source,
+            version,
+            md5sum: parse_md5sum(item, "info.md5sum")?,
+        })
+    }
+
+    /// Returns the MD5 hash of the contents of a single file torrent, if it included one.
+    ///
+    /// Hashes of the files of a multi-file torrent are available from [`FileEntry::md5sum`]
+    pub fn md5sum(&self) -> Option<[u8; 16]> {
+        self.md5sum
+    }
+
+    /// Returns the files of a multi-file torrent, excluding padding files.
+    ///
+    /// Padding files still occupy space in the pieces, so remain in [`FileKind::Multi`] for locating data
+    pub fn files(&self) -> impl Iterator<Item = &FileEntry> {
+        self.files_with_padding().filter(|file| !file.is_padding())
+    }
+
+    /// Returns every file of a multi-file torrent, including padding files
+    pub fn files_with_padding(&self) -> impl Iterator<Item = &FileEntry> {
+        let files = match &self.mode {
+            FileKind::Single { .. } => &[][..],
+            FileKind::Multi { files } => files,
+        };
+
+        files.iter()
+    }
+
+    /// Returns the version of the protocol the torrent was created for
+    pub fn version(&self) -> TorrentVersion {
+        self.version
+    }
+
+    /// Returns the bytes of the name as they appeared in the torrent, which may not be UTF-8, unless it has since been changed
+    pub fn name_bytes(&self) -> &[u8] {
+        match &self.raw_name {
+            Some(raw) if String::from_utf8_lossy(raw) == self.name => raw,
+            _ => self.name.as_bytes(),
+        }
+    }
+
+    /// Returns the `source` key that private trackers add to make the info hash unique to them, if it is valid UTF-8
+    pub fn source(&self) -> Option<&str> {
+        self.source
+            .as_deref()
+            .and_then(|source| std::str::from_utf8(source).ok())
+    }
+
+    /// Returns whether the torrent is private, in which case peers must not be found or shared using DHT or PEX
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns the length of each file in order, which for a single file torrent is just its length.
+    ///
+    /// Symlinks have no contents in the pieces, so count as empty
+    fn file_lengths(&self) -> impl Iterator<Item = u64> + '_ {
+        let (single, files) = match &self.mode {
+            FileKind::Single { length } => (Some(*length), None),
+            FileKind::Multi { files } => (
+                None,
+                Some(
+                    files
+                        .iter()
+                        .map(|file| if file.is_symlink() { 0 } else { file.length }),
+                ),
+            ),
+        };
+
+        single.into_iter().chain(files.into_iter().flatten())
+    }
+
+    /// Returns the combined length of every file in bytes, or None if it doesn't fit in a `u64`
+    pub fn total_length(&self) -> Option<u64> {
+        self.file_lengths().try_fold(0u64, u64::checked_add)
+    }
+
+    /// Finds the file and piece containing a byte offset into the concatenated contents of every file,
+    /// along with the offset of that byte within the piece.
+    ///
+    /// Returns None if the offset is past the end of the torrent
+    pub fn locate(&self, global_offset: u64) -> Option<(FileIndex, PieceIndex, usize)> {
+        if self.piece_length == 0 {
+            return None;
+        }
+
+        // empty files never contain an offset, so the byte belongs to the next non-empty file
+        let mut end = 0u64;
+        let file = self.file_lengths().position(|length| {
+            end = end.saturating_add(length);
+            global_offset < end
+        })?;
+
+        Some((
+            file,
+            (global_offset / self.piece_length) as usize,
+            (global_offset % self.piece_length) as usize,
+        ))
+    }
+
+    /// Checks a downloaded piece against its SHA-1 hash, returning false if the index is out of range.
+    ///
+    /// The final piece is usually shorter than the piece length, and its data must not be padded
+    pub fn verify_piece(&self, index: usize, data: &[u8]) -> bool {
+        if data.len() as u64 > self.piece_length {
+            return false;
+        }
+
+        let hash = index
+            .checked_mul(20)
+            .and_then(|start| self.pieces.get(start..start.checked_add(20)?));
+
+        match hash {
+            Some(hash) => Sha1::digest(data)[..] == *hash,
+            None => false,
+        }
+    }
+
+    /// Splits the pieces field into the SHA-1 hash of each piece, failing if its length is not a multiple of 20
+    pub fn piece_hashes(&self) -> Result<impl Iterator<Item = &[u8; 20]>, MetaInfoError> {
+        if !self.pieces.len().is_multiple_of(20) {
+            return Err(MetaInfoError::InvalidField {
+                field: "info.pieces",
+            });
+        }
+
+        Ok(self
+            .pieces
+            .chunks_exact(20)
+            .map(|hash| hash.try_into().expect("chunks are 20 bytes")))
+    }
+}
+
+impl FileEntry {
+    /// Extracts a file from an element of the info files list, rejecting paths that could escape the download directory
+    fn from_item(item: &Item) -> Result<Self, MetaInfoError> {
+        let path = to_path(
+            required(item, "path", "info.files.path")?,
+            "info.files.path",
+        )?;
+        let symlink_path = item
+            .get("symlink path")
+            .map(|target| to_path(target, "info.files.symlink path"))
+            .transpose()?;
+
+        Ok(Self {
+            length: parse_file_length(item, &symlink_path, "info.files.length")?,
+            path,
+            pieces_root: None,
+            md5sum: parse_md5sum(item, "info.files.md5sum")?,
+            attr: parse_attr(item, "info.files.attr")?,
+            symlink_path,
+        })
+    }
+
+    /// Returns the MD5 hash of the file's contents, if the torrent included one
+    pub fn md5sum(&self) -> Option<[u8; 16]> {
+        self.md5sum
+    }
+
+    /// Returns the target of the symlink relative to the torrent directory, if the file is one
+    pub fn symlink_path(&self) -> Option<&Path> {
+        self.symlink_path.as_deref()
+    }
+
+    /// Returns whether the file is a symlink, which should be created in place of writing any contents
+    pub fn is_symlink(&self) -> bool {
+        self.symlink_path.is_some()
+    }
+
+    /// Returns whether the file only pads the next file to a piece boundary, so has no contents worth showing or saving
+    pub fn is_padding(&self) -> bool {
+        self.attr.padding
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bencoding::BEncoding, peer::PeerId};
+
+    #[test]
+    fn test_sample() {
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+
+        assert_eq!(
+            meta.announce.as_deref(),
+            Some("udp://tracker.openbittorrent.com:80")
+        );
+        assert_eq!(meta.comment, None);
+        assert_eq!(meta.created_by, None);
+        assert_eq!(meta.creation_date, Some(1327049827));
+        assert_eq!(meta.info.name, "sample.txt");
+        assert_eq!(meta.info.piece_length, 65536);
+        assert_eq!(meta.info.pieces.len(), 20);
+        assert_eq!(meta.info.mode, FileKind::Single { length: 20 });
+    }
+
+    #[test]
+    fn test_arch() {
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+
+        assert_eq!(meta.announce, None);
+        assert_eq!(
+            meta.comment.as_deref(),
+            Some("Arch Linux 2022.10.01 <https://archlinux.org>")
+        );
+        assert_eq!(meta.created_by.as_deref(), Some("mktorrent 1.1"));
+        assert_eq!(meta.creation_date, Some(1664612189));
+        assert_eq!(meta.info.name, "archlinux-2022.10.01-x86_64.iso");
+        assert_eq!(meta.info.piece_length, 524288);
+        assert_eq!(meta.info.pieces.len(), 31940);
+        assert_eq!(meta.info.mode, FileKind::Single { length: 837103616 });
+    }
+
+    #[test]
+    fn test_info_hash() {
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(
+            hex::encode(&meta.info_hash()),
+            "d0d14c926e6e99761a2fdcff27b403d96376eff6"
+        );
+
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert_eq!(
+            hex::encode(&meta.info_hash()),
+            "7cb890a8886ae03491ba5f706a5b6655963b8f01"
+        );
+
+        // the sample file is canonical, so re-encoding gives the same hash
+        let decoded = BEncoding::decode_path("../sample.torrent").unwrap();
+        let from_item = MetaInfo::from_item(decoded.root().unwrap()).unwrap();
+        assert_eq!(
+            hex::encode(&from_item.info_hash()),
+            "d0d14c926e6e99761a2fdcff27b403d96376eff6"
+        );
+    }
+
+    #[test]
+    fn test_info_hash_uses_original_bytes() {
+        // keys are out of order, so re-encoding would produce different bytes
+        let data = b"d4:infod6:lengthi1e4:name1:a6:pieces0:12:piece lengthi16eee";
+        let meta = MetaInfo::from_bytes(data).unwrap();
+
+        assert_eq!(
+            meta.info_hash(),
+            <[u8; 20]>::from(Sha1::digest(&data[7..58]))
+        );
+    }
+
+    #[test]
+    fn test_piece_hashes() {
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        let FileKind::Single { length } = meta.info.mode else {
+            panic!("expected single file torrent");
+        };
+
+        let hashes: Vec<_> = meta.info.piece_hashes().unwrap().collect();
+        assert_eq!(hashes.len() as u64, length.div_ceil(meta.info.piece_length));
+        assert_eq!(hashes[0][..], meta.info.pieces[..20]);
+        assert_eq!(hashes[1596][..], meta.info.pieces[31920..]);
+
+        let mut info = meta.info;
+        info.pieces.pop();
+        assert!(matches!(
+            info.piece_hashes(),
+            Err(MetaInfoError::InvalidField {
+                field: "info.pieces"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_piece() {
+        let data: Vec<u8> = (0..40).collect();
+        let pieces: Vec<u8> = data.chunks(16).flat_map(Sha1::digest).collect();
+
+        let mut torrent =
+            b"d4:infod6:lengthi40e4:name4:test12:piece lengthi16e6:pieces60:".to_vec();
+        torrent.extend_from_slice(&pieces);
+        torrent.extend_from_slice(b"ee");
+        let info = MetaInfo::from_bytes(&torrent).unwrap().info;
+
+        assert!(info.verify_piece(0, &data[..16]));
+        assert!(info.verify_piece(1, &data[16..32]));
+        assert!(!info.verify_piece(0, &data[16..32]));
+
+        let mut corrupted = data[..16].to_vec();
+        corrupted[3] ^= 1;
+        assert!(!info.verify_piece(0, &corrupted));
+
+        // the final piece only holds the remaining 8 bytes
+        assert!(info.verify_piece(2, &data[32..]));
+        assert!(!info.verify_piece(2, &[&data[32..], &[0; 8]].concat()));
+
+        assert!(!info.verify_piece(3, &data[32..]));
+        assert!(!info.verify_piece(usize::MAX / 20, &[]));
+    }
+
+    #[test]
+    fn test_total_length() {
+        let single = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert_eq!(single.info.total_length(), Some(837103616));
+
+        let multi = MetaInfo::from_bytes(
+            b"d4:infod5:filesld6:lengthi3e4:pathl1:aeed6:lengthi4e4:pathl1:beee4:name3:dir12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        assert_eq!(multi.info.total_length(), Some(7));
+
+        // three files of i64::MAX bytes don't fit in a u64
+        let file = format!("d6:lengthi{}e4:pathl1:aee", i64::MAX);
+        let overflowing = format!(
+            "d4:infod5:filesl{}e4:name3:dir12:piece lengthi16e6:pieces0:ee",
+            file.repeat(3)
+        );
+        assert!(matches!(
+            MetaInfo::from_bytes(overflowing.as_bytes()),
+            Err(MetaInfoError::InvalidField {
+                field: "info.files.length"
+            })
+        ));
+
+        // lengths changed after parsing are checked too
+        let mut info = multi.info;
+        if let FileKind::Multi { files } = &mut info.mode {
+            files[0].length = u64::MAX;
+        }
+        assert_eq!(info.total_length(), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        // piece 0 holds all of a, piece 1 straddles b and d, and c is empty
+        let meta = MetaInfo::from_bytes(
+            b"d4:infod5:filesld6:lengthi16e4:pathl1:aeed6:lengthi10e4:pathl1:beed6:lengthi0e4:pathl1:ceed6:lengthi20e4:pathl1:deee4:name3:dir12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+
+        assert_eq!(meta.info.locate(0), Some((0, 0, 0)));
+        assert_eq!(meta.info.locate(15), Some((0, 0, 15)));
+        assert_eq!(meta.info.locate(16), Some((1, 1, 0)));
+        assert_eq!(meta.info.locate(25), Some((1, 1, 9)));
+        assert_eq!(meta.info.locate(26), Some((3, 1, 10)));
+        assert_eq!(meta.info.locate(45), Some((3, 2, 13)));
+        assert_eq!(meta.info.locate(46), None);
+        assert_eq!(meta.info.locate(u64::MAX), None);
+
+        let single = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(single.info.locate(19), Some((0, 0, 19)));
+        assert_eq!(single.info.locate(20), None);
+    }
+
+    #[test]
+    fn test_multi_file() {
+        let meta = MetaInfo::from_bytes(
+            b"d4:infod5:filesld6:lengthi3e4:pathl1:a1:beed6:lengthi4e4:pathl1:ceee4:name3:dir12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.info.mode,
+            FileKind::Multi {
+                files: vec![
+                    FileEntry {
+                        length: 3,
+                        path: PathBuf::from("a").join("b"),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                    FileEntry {
+                        length: 4,
+                        path: PathBuf::from("c"),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_md5sum() {
+        let meta = MetaInfo::from_bytes(
+            b"d4:infod5:filesld6:lengthi3e6:md5sum32:900150983cd24fb0d6963f7d28e17f724:pathl1:aeed6:lengthi4e4:pathl1:beee4:name3:dir12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        let FileKind::Multi { files } = &meta.info.mode else {
+            panic!("expected multi-file torrent");
+        };
+
+        assert_eq!(
+            files[0].md5sum(),
+            Some([
+                0x90, 0x01, 0x50, 0x98, 0x3c, 0xd2, 0x4f, 0xb0, 0xd6, 0x96, 0x3f, 0x7d, 0x28, 0xe1,
+                0x7f, 0x72
+            ])
+        );
+        assert_eq!(files[1].md5sum(), None);
+        assert_eq!(meta.info.md5sum(), None);
+        assert_eq!(MetaInfo::from_bytes(&meta.encode()).unwrap(), meta);
+
+        let single = MetaInfo::from_bytes(
+            b"d4:infod6:lengthi3e6:md5sum32:900150983CD24FB0D6963F7D28E17F724:name1:a12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        assert_eq!(single.info.md5sum(), files[0].md5sum());
+        // the hash is re-encoded in lowercase, so only the info hash differs
+        assert_eq!(
+            MetaInfo::from_bytes(&single.encode()).unwrap().info,
+            single.info
+        );
+
+        assert!(matches!(
+            MetaInfo::from_bytes(
+                b"d4:infod6:lengthi3e6:md5sum3:abc4:name1:a12:piece lengthi16e6:pieces0:ee"
+            ),
+            Err(MetaInfoError::InvalidField {
+                field: "info.md5sum"
+            })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod5:filesld6:lengthi3e6:md5sumi1e4:pathl1:aeee4:name3:dir12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::InvalidField {
+                field: "info.files.md5sum"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_path_traversal() {
+        let data = b"d4:infod5:filesld6:lengthi1e4:pathl2:..2:..3:etc6:passwdeee4:name3:dir12:piece lengthi16e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+
+        match MetaInfo::from_bytes(data) {
+            Err(MetaInfoError::PathTraversal { field, component }) => {
+                assert_eq!(field, "info.files.path");
+                assert_eq!(component, "..");
+            }
+            other => panic!("expected path traversal error, got {other:?}"),
+        }
+
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod5:filesld6:lengthi1e4:pathl4:/etc6:passwdeee4:name3:dir12:piece lengthi16e6:pieces20:aaaaaaaaaaaaaaaaaaaaee"),
+            Err(MetaInfoError::PathTraversal { .. })
+        ));
+    }
+
+    #[test]
+    fn test_multi_file_invalid_paths() {
+        let parse = |files: &str| {
+            MetaInfo::from_bytes(
+                format!("d4:infod5:filesl{files}e4:name3:dir12:piece lengthi16e6:pieces0:ee")
+                    .as_bytes(),
+            )
+        };
+
+        assert!(parse("d6:lengthi1e4:pathl3:a/beee").is_err());
+        assert!(parse("d6:lengthi1e4:pathl3:a\\beee").is_err());
+        assert!(parse("d6:lengthi1e4:pathl1:.1:aee").is_err());
+        assert!(parse("d6:lengthi1e4:pathl6:a..txtee").is_ok());
+        assert!(parse("d6:lengthi1e4:pathlee").is_err());
+        assert!(parse("d6:lengthi1e4:pathli1eee").is_err());
+        assert!(matches!(
+            parse("d4:pathl1:aee"),
+            Err(MetaInfoError::MissingField {
+                field: "info.files.length"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_announce_list() {
+        let meta = MetaInfo::from_bytes(
+            b"d8:announce3:one13:announce-listll3:one3:twoel5:threeee4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.announce_list().unwrap(),
+            [
+                vec!["one".to_owned(), "two".to_owned()],
+                vec!["three".to_owned()]
+            ]
+        );
+        assert_eq!(meta.trackers(), ["one", "two", "three"]);
+
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(meta.announce_list(), None);
+        assert_eq!(meta.trackers(), ["udp://tracker.openbittorrent.com:80"]);
+
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert!(meta.trackers().is_empty());
+
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d13:announce-listl3:onee4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::InvalidField {
+                field: "announce-list"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_shuffled_trackers() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let meta = MetaInfo::from_bytes(
+            b"d13:announce-listll1:a1:b1:c1:d1:eel1:fel1:g1:hee4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+
+        let tiers = meta.shuffled_trackers(&mut StdRng::seed_from_u64(7));
+        assert_eq!(tiers, meta.shuffled_trackers(&mut StdRng::seed_from_u64(7)));
+
+        // shuffling only reorders trackers within their own tier
+        for (shuffled, original) in tiers.iter().zip(meta.announce_list().unwrap()) {
+            let mut shuffled = shuffled.clone();
+            shuffled.sort();
+            assert_eq!(&shuffled, original);
+        }
+
+        assert_eq!(tiers.len(), 3);
+        assert_eq!(tiers[1], ["f"]);
+
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(
+            meta.shuffled_trackers(&mut StdRng::seed_from_u64(7)),
+            [["udp://tracker.openbittorrent.com:80"]]
+        );
+
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert!(meta
+            .shuffled_trackers(&mut StdRng::seed_from_u64(7))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_promote_tracker() {
+        let mut tiers = vec![
+            vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            vec!["d".to_owned(), "e".to_owned()],
+        ];
+
+        assert!(MetaInfo::promote_tracker(&mut tiers, "c"));
+        assert_eq!(tiers[0], ["c", "a", "b"]);
+
+        assert!(MetaInfo::promote_tracker(&mut tiers, "e"));
+        assert_eq!(tiers[1], ["e", "d"]);
+
+        assert!(MetaInfo::promote_tracker(&mut tiers, "c"));
+        assert_eq!(tiers[0], ["c", "a", "b"]);
+
+        assert!(!MetaInfo::promote_tracker(&mut tiers, "f"));
+    }
+
+    #[test]
+    fn test_trackerless() {
+        let data = b"d4:infod6:lengthi20e4:name8:test.txt12:piece lengthi65536e6:pieces20:\
+            aaaaaaaaaaaaaaaaaaaae5:nodesll9:127.0.0.1i6881eel19:router.utorrent.comi6881eeee";
+        let metainfo = MetaInfo::from_bytes(data).unwrap();
+
+        assert_eq!(metainfo.announce, None);
+        assert!(metainfo.trackers().is_empty());
+        assert_eq!(
+            metainfo.nodes(),
+            Some(
+                &[
+                    ("127.0.0.1".to_owned(), 6881),
+                    ("router.utorrent.com".to_owned(), 6881)
+                ][..]
+            )
+        );
+
+        assert_eq!(
+            MetaInfo::from_path("../sample.torrent").unwrap().nodes(),
+            None
+        );
+        assert!(matches!(
+            MetaInfo::from_bytes(
+                b"d4:infod6:lengthi20e4:name1:a12:piece lengthi1e6:pieces0:e5:nodesll1:ai70000eeee"
+            ),
+            Err(MetaInfoError::InvalidField { field: "nodes" })
+        ));
+    }
+
+    #[test]
+    fn test_web_seeds() {
+        let arch = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert_eq!(arch.web_seeds().len(), 315);
+        assert_eq!(
+            arch.web_seeds()[0],
+            "https://mirror.aarnet.edu.au/pub/archlinux/iso/2022.10.01/"
+        );
+
+        let info = "4:infod6:lengthi20e4:name1:a12:piece lengthi1e6:pieces0:e";
+        let parse = |extra: &str| MetaInfo::from_bytes(format!("d{extra}{info}e").as_bytes());
+
+        assert_eq!(
+            parse("9:httpseedsl17:http://seed/a.phpe")
+                .unwrap()
+                .web_seeds(),
+            ["http://seed/a.php"]
+        );
+        assert!(parse("").unwrap().web_seeds().is_empty());
+        assert!(matches!(
+            parse("9:httpseeds1:a"),
+            Err(MetaInfoError::InvalidField { field: "httpseeds" })
+        ));
+    }
+
+    #[test]
+    fn test_web_seeds_single() {
+        let info = "4:infod6:lengthi20e4:name1:a12:piece lengthi1e6:pieces0:e";
+        let parse = |extra: &str| MetaInfo::from_bytes(format!("d{info}{extra}e").as_bytes());
+
+        assert_eq!(
+            parse("8:url-list14:http://seed/a/").unwrap().web_seeds(),
+            ["http://seed/a/"]
+        );
+        assert!(parse("8:url-list0:").unwrap().web_seeds().is_empty());
+        assert!(matches!(
+            parse("8:url-listi1e"),
+            Err(MetaInfoError::InvalidField { field: "url-list" })
+        ));
+    }
+
+    #[test]
+    fn test_v2() {
+        let info = b"d9:file treed4:docsd5:a.txtd0:d6:lengthi5e11:pieces root32:\
+            rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee5:emptyd0:d6:lengthi0eeee\
+            12:meta versioni2e4:name4:test12:piece lengthi16384ee";
+        let data = [&b"d4:info"[..], info, b"e"].concat();
+        let meta = MetaInfo::from_bytes(&data).unwrap();
+
+        assert_eq!(meta.info.version(), TorrentVersion::V2);
+        assert!(meta.info.pieces.is_empty());
+        assert_eq!(
+            meta.info.file_tree.as_deref(),
+            Some(
+                &[
+                    FileEntry {
+                        length: 5,
+                        path: PathBuf::from("docs").join("a.txt"),
+                        pieces_root: Some([b'r'; 32]),
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                    FileEntry {
+                        length: 0,
+                        path: PathBuf::from("empty"),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                ][..]
+            )
+        );
+        assert!(matches!(meta.info.mode, FileKind::Multi { ref files } if files.len() == 2));
+
+        let hash: [u8; 32] = Sha256::digest(info).into();
+        assert_eq!(meta.info_hash_v2(), Some(hash));
+        assert_eq!(meta.info_hash_v1(), None);
+        assert_eq!(meta.info_hash(), hash[..20]);
+        assert_eq!(
+            MetaInfo::from_item(&BEncoding::decode(&data).unwrap().into_items()[0]).unwrap(),
+            meta
+        );
+    }
+
+    #[test]
+    fn test_piece_layers() {
+        // a.txt spans three 16 KiB pieces, so its layer has three hashes, while b.txt fits in one piece and has none
+        let info = [
+            &b"d9:file treed5:a.txtd0:d6:lengthi40000e11:pieces root32:"[..],
+            &[0xff; 32],
+            b"ee5:b.txtd0:d6:lengthi5e11:pieces root32:",
+            &[0x11; 32],
+            b"eee12:meta versioni2e4:name4:test12:piece lengthi16384ee",
+        ]
+        .concat();
+        let layer = [[0xa1; 32], [0xa2; 32], [0xa3; 32]];
+        let data = [
+            &b"d4:info"[..],
+            &info,
+            b"12:piece layersd32:",
+            &[0xff; 32],
+            b"96:",
+            &layer.concat(),
+            b"ee",
+        ]
+        .concat();
+        let meta = MetaInfo::from_bytes(&data).unwrap();
+
+        let files = meta.info.file_tree.as_deref().unwrap();
+        assert_eq!(meta.piece_layers().len(), 1);
+        assert_eq!(meta.piece_layer(&files[0]), Some(&layer[..]));
+        assert_eq!(meta.piece_layer(&files[1]), None);
+        assert_eq!(MetaInfo::from_bytes(&meta.encode()).unwrap(), meta);
+
+        let sample = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert!(sample.piece_layers().is_empty());
+
+        for layers in [
+            &b"12:piece layersi1e"[..],
+            b"12:piece layersd3:abc0:e",
+            b"12:piece layersd32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrr3:abce",
+        ] {
+            let data = [&b"d4:info"[..], &info, layers, b"e"].concat();
+            assert!(matches!(
+                MetaInfo::from_bytes(&data),
+                Err(MetaInfoError::InvalidField {
+                    field: "piece layers"
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_v2_single_file() {
+        let meta = MetaInfo::from_bytes(
+            b"d4:infod9:file treed4:testd0:d6:lengthi3eeee12:meta versioni2e4:name4:test12:piece lengthi16384eee",
+        )
+        .unwrap();
+
+        assert_eq!(meta.info.mode, FileKind::Single { length: 3 });
+        assert_eq!(
+            MetaInfo::from_path("../sample.torrent")
+                .unwrap()
+                .info_hash_v2(),
+            None
+        );
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod12:meta versioni3e4:name1:a12:piece lengthi1eee"),
+            Err(MetaInfoError::InvalidField {
+                field: "info.meta version"
+            })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod12:meta versioni2e4:name1:a12:piece lengthi1eee"),
+            Err(MetaInfoError::MissingField {
+                field: "info.file tree"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_hybrid() {
+        let info = b"d9:file treed5:a.txtd0:d6:lengthi5e11:pieces root32:\
+            aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaee5:b.txtd0:d6:lengthi3e11:pieces root32:\
+            bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbeee5:filesld6:lengthi5e4:pathl5:a.txteed4:attr1:p\
+            6:lengthi16379e4:pathl4:.pad5:16379eed6:lengthi3e4:pathl5:b.txteee\
+            12:meta versioni2e4:name4:test12:piece lengthi16384e6:pieces40:\
+            0123456789012345678901234567890123456789e";
+        let data = [&b"d4:info"[..], info, b"12:piece layersdee"].concat();
+        let meta = MetaInfo::from_bytes(&data).unwrap();
+
+        assert_eq!(meta.info.version(), TorrentVersion::Hybrid);
+        assert_eq!(meta.info.piece_hashes().unwrap().count(), 2);
+        assert!(matches!(meta.info.mode, FileKind::Multi { ref files } if files.len() == 3));
+        assert_eq!(meta.info.file_tree.as_ref().map(Vec::len), Some(2));
+
+        let v1: [u8; 20] = Sha1::digest(info).into();
+        let v2: [u8; 32] = Sha256::digest(info).into();
+        assert_eq!(meta.info_hash_v1(), Some(v1));
+        assert_eq!(meta.info_hash_v2(), Some(v2));
+        assert_eq!(meta.info_hash(), v1);
+
+        // the v1 files must describe the same files as the file tree
+        let mismatched = String::from_utf8(data)
+            .unwrap()
+            .replace("lengthi3e4:pathl5:b.txt", "lengthi4e4:pathl5:b.txt");
+        assert!(matches!(
+            MetaInfo::from_bytes(mismatched.as_bytes()),
+            Err(MetaInfoError::InvalidField {
+                field: "info.file tree"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_padding_files() {
+        let info =
+            b"d5:filesld6:lengthi5e4:pathl5:a.txteed4:attr1:p6:lengthi11e4:pathl4:.pad2:11eed\
+            4:attr2:xh6:lengthi3e4:pathl5:b.txteee4:name4:test12:piece lengthi16e6:pieces40:\
+            0123456789012345678901234567890123456789e";
+        let meta = MetaInfo::from_bytes(&[&b"d4:info"[..], info, b"e"].concat()).unwrap();
+
+        let FileKind::Multi { files } = &meta.info.mode else {
+            panic!("expected a multi-file torrent");
+        };
+        assert!(!files[0].is_padding());
+        assert!(files[1].is_padding());
+        assert_eq!(
+            files[2].attr,
+            FileAttributes {
+                executable: true,
+                hidden: true,
+                ..Default::default()
+            }
+        );
+
+        // padding is hidden from the file list, but still counts towards the pieces
+        let names: Vec<&Path> = meta.info.files().map(|file| file.path.as_path()).collect();
+        assert_eq!(names, [Path::new("a.txt"), Path::new("b.txt")]);
+        assert_eq!(meta.info.files_with_padding().count(), 3);
+        assert_eq!(meta.info.total_length(), Some(19));
+        assert_eq!(meta.info.to_item().encode(), info);
+
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod5:filesld4:attri1e6:lengthi1e4:pathl1:aeee4:name1:a12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::InvalidField {
+                field: "info.files.attr"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_symlinks() {
+        let info = b"d9:file treed4:datad0:d6:lengthi20e11:pieces root32:\
+            rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrree4:linkd0:d4:attr1:l6:lengthi0e12:symlink pathl4:dataeeee\
+            12:meta versioni2e4:name4:test12:piece lengthi16384ee";
+        let meta = MetaInfo::from_bytes(&[&b"d4:info"[..], info, b"e"].concat()).unwrap();
+
+        let files = meta.info.file_tree.as_ref().unwrap();
+        assert!(!files[0].is_symlink());
+        assert!(files[1].is_symlink());
+        assert!(files[1].attr.symlink);
+        assert_eq!(files[1].symlink_path(), Some(Path::new("data")));
+        assert_eq!(meta.info.total_length(), Some(20));
+        assert_eq!(meta.info.locate(19).map(|(file, ..)| file), Some(0));
+        assert_eq!(meta.info.locate(20), None);
+        assert_eq!(meta.info.to_item().encode(), info);
+
+        // symlinks in the v1 file list may still carry a length, which doesn't count towards the pieces
+        let meta = MetaInfo::from_bytes(b"d4:infod5:filesld4:attr1:l6:lengthi7e4:pathl4:linke12:symlink pathl3:dir6:targeteed6:lengthi3e4:pathl1:beee4:name3:dir12:piece lengthi16e6:pieces0:ee").unwrap();
+        assert_eq!(
+            meta.info.files().next().unwrap().symlink_path(),
+            Some(Path::new("dir").join("target").as_path())
+        );
+        assert_eq!(meta.info.total_length(), Some(3));
+        assert_eq!(meta.info.locate(0).map(|(file, ..)| file), Some(1));
+
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod5:filesld4:pathl4:linke12:symlink pathl2:..eee4:name3:dir12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::PathTraversal {
+                field: "info.files.symlink path",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_private() {
+        let parse = |private: &str| {
+            MetaInfo::from_bytes(
+                format!("d4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:{private}ee")
+                    .as_bytes(),
+            )
+            .map(|meta| meta.info.is_private())
+        };
+
+        assert!(parse("7:privatei1e").unwrap());
+        assert!(!parse("7:privatei0e").unwrap());
+        assert!(!parse("").unwrap());
+        assert!(parse("7:private1:1").is_err());
+
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert!(meta.info.is_private());
+    }
+
+    #[test]
+    fn test_source() {
+        let info = b"d6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:7:privatei1e6:source3:XYZe";
+        let meta = MetaInfo::from_bytes(&[&b"d4:info"[..], info, b"e"].concat()).unwrap();
+
+        assert_eq!(meta.info.source(), Some("XYZ"));
+        assert_eq!(meta.info.to_item().encode(), info);
+        assert_eq!(
+            MetaInfo::from_bytes(&meta.encode()).unwrap().info_hash(),
+            meta.info_hash()
+        );
+
+        // non-UTF-8 sources are hidden but still preserved
+        let info = b"d6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:6:source2:\xff\xfee";
+        let meta = MetaInfo::from_bytes(&[&b"d4:info"[..], info, b"e"].concat()).unwrap();
+        assert_eq!(meta.info.source(), None);
+        assert_eq!(meta.info.to_item().encode(), info);
+
+        assert!(matches!(
+            MetaInfo::from_bytes(
+                b"d4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:6:sourcei1eee"
+            ),
+            Err(MetaInfoError::InvalidField {
+                field: "info.source"
+            })
+        ));
+        assert_eq!(
+            MetaInfo::from_path("../sample.torrent")
+                .unwrap()
+                .info
+                .source(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        // "中文" encoded as GBK
+        let info = b"d6:lengthi1e4:name4:\xd6\xd0\xce\xc412:piece lengthi16e6:pieces0:e";
+        let meta =
+            MetaInfo::from_bytes(&[&b"d8:encoding3:GBK4:info"[..], info, b"e"].concat()).unwrap();
+
+        assert_eq!(meta.encoding(), Some("GBK"));
+        assert_eq!(meta.info.name, "\u{fffd}\u{fffd}\u{fffd}\u{fffd}");
+        assert_eq!(meta.info.name_bytes(), b"\xd6\xd0\xce\xc4");
+        assert_eq!(meta.info.to_item().encode(), info);
+        #[cfg(feature = "encoding")]
+        assert_eq!(meta.decode_name_with_encoding(), "中文");
+
+        let mut renamed = meta.clone();
+        renamed.info.name = "renamed".to_owned();
+        assert_eq!(renamed.info.name_bytes(), b"renamed");
+        #[cfg(feature = "encoding")]
+        assert_eq!(renamed.decode_name_with_encoding(), "renamed");
+
+        let sample = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(sample.encoding(), None);
+        #[cfg(feature = "encoding")]
+        assert_eq!(sample.decode_name_with_encoding(), "sample.txt");
+    }
+
+    #[test]
+    fn test_creation_date() {
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(
+            meta.creation_date(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1327049827))
+        );
+
+        let parse = |date: &str| {
+            MetaInfo::from_bytes(
+                format!("d{date}4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee")
+                    .as_bytes(),
+            )
+            .unwrap()
+            .creation_date()
+        };
+
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("13:creation datei-1e"), None);
+        assert_eq!(parse("13:creation datei0e"), Some(SystemTime::UNIX_EPOCH));
+        // whether this is representable depends on the platform, but it must not panic
+        let _ = parse("13:creation datei9223372036854775807e");
+    }
+
+    #[test]
+    fn test_magnet_link() {
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(
+            meta.magnet_link(),
+            "magnet:?xt=urn:btih:d0d14c926e6e99761a2fdcff27b403d96376eff6&dn=sample.txt&tr=udp%3A%2F%2Ftracker.openbittorrent.com%3A80"
+        );
+
+        let meta = MetaInfo::from_bytes(
+            b"d8:announce3:one13:announce-listll3:one3:twoee4:infod6:lengthi1e4:name5:a b&c12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        assert!(meta.magnet_link().ends_with("&dn=a%20b%26c&tr=one&tr=two"));
+    }
+
+    #[test]
+    fn test_announce_url() {
+        let params = AnnounceParams {
+            peer_id: PeerId(*b"-TR3000-000000000000"),
+            port: 6881,
+            uploaded: 0,
+            downloaded: 0,
+            left: 20,
+            event: None,
+            tracker_id: None,
+        };
+
+        let meta = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert_eq!(
+            meta.announce_url(&params).unwrap(),
+            "udp://tracker.openbittorrent.com:80?info_hash=%D0%D1L%92nn%99v%1A%2F%DC%FF%27%B4%03%D9cv%EF%F6\
+            &peer_id=-TR3000-000000000000&port=6881&uploaded=0&downloaded=0&left=20&compact=1"
+        );
+
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert_eq!(meta.announce_url(&params), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infoi1ee"),
+            Err(MetaInfoError::InvalidField { field: "info" })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d8:announce3:urle"),
+            Err(MetaInfoError::MissingField { field: "info" })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod6:lengthi1e12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::MissingField { field: "info.name" })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:infod6:lengthi-1e4:name1:a12:piece lengthi16e6:pieces0:ee"),
+            Err(MetaInfoError::InvalidField {
+                field: "info.length"
+            })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(
+                b"d8:announcei1e4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee"
+            ),
+            Err(MetaInfoError::InvalidField { field: "announce" })
+        ));
+        assert!(matches!(
+            MetaInfo::from_bytes(b"d4:info"),
+            Err(MetaInfoError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,507 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use sha1::{Digest, Sha1};
+
+use super::{BuildError, FileAttributes, FileEntry, FileKind, Info, MetaInfo, TorrentVersion};
+
+/// Source of files added to a builder
+#[derive(Debug, Clone)]
+enum Source {
+    File(PathBuf),
+    Dir(PathBuf),
+}
+
+/// File found on disk, with its path inside the torrent
+#[derive(Debug)]
+struct DiskFile {
+    disk_path: PathBuf,
+    path: Vec<String>,
+}
+
+/// Creates a torrent by hashing files on disk.
+///
+/// A single added file produces a single file torrent, and anything else produces a multi-file torrent
+#[derive(Debug, Clone)]
+pub struct TorrentBuilder {
+    sources: Vec<Source>,
+    name: Option<String>,
+    piece_length: u64,
+    announce: Option<String>,
+    comment: Option<String>,
+    created_by: Option<String>,
+    private: bool,
+    source: Option<String>,
+    threads: usize,
+}
+
+impl Default for TorrentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TorrentBuilder {
+    /// Piece length used unless another is set
+    pub const DEFAULT_PIECE_LENGTH: u64 = 1 << 18;
+
+    /// Creates a builder with no files
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            name: None,
+            piece_length: Self::DEFAULT_PIECE_LENGTH,
+            announce: None,
+            comment: None,
+            created_by: None,
+            private: false,
+            source: None,
+            threads: 1,
+        }
+    }
+
+    /// Adds a file, which is named after its file name inside the torrent
+    pub fn add_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sources.push(Source::File(path.into()));
+        self
+    }
+
+    /// Adds every file within a directory, which is read when the torrent is built
+    pub fn add_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sources.push(Source::Dir(path.into()));
+        self
+    }
+
+    /// Sets the name of the torrent, which otherwise defaults to the name of the first file or directory added
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the number of bytes in each piece, which must be a power of two
+    pub fn piece_length(mut self, piece_length: u64) -> Self {
+        self.piece_length = piece_length;
+        self
+    }
+
+    /// Sets the URL of the tracker
+    pub fn announce(mut self, announce: impl Into<String>) -> Self {
+        self.announce = Some(announce.into());
+        self
+    }
+
+    /// Sets a free-form comment describing the torrent
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Sets the name and version of the program creating the torrent
+    pub fn created_by(mut self, created_by: impl Into<String>) -> Self {
+        self.created_by = Some(created_by.into());
+        self
+    }
+
+    /// Marks the torrent as private, from BEP 27
+    pub fn private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    /// Sets the `source` key, which private trackers use to make the info hash unique to them
+    pub fn source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Sets the number of threads used to hash pieces, which defaults to hashing on the calling thread.
+    ///
+    /// [`std::thread::available_parallelism`] is a reasonable choice for large torrents
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Reads and hashes every file, producing the torrent
+    pub fn build(&self) -> Result<MetaInfo, BuildError> {
+        if !self.piece_length.is_power_of_two() {
+            return Err(BuildError::InvalidPieceLength {
+                piece_length: self.piece_length,
+            });
+        }
+
+        let single_file = matches!(&self.sources[..], [Source::File(_)]);
+        let files = self.collect_files()?;
+        if files.is_empty() {
+            return Err(BuildError::NoFiles);
+        }
+
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => {
+                let first = match &self.sources[0] {
+                    Source::File(path) | Source::Dir(path) => path,
+                };
+                file_name(first)?
+            }
+        };
+
+        let (pieces, lengths) = hash_pieces(&files, self.piece_length as usize, self.threads)?;
+
+        let mode = if single_file {
+            FileKind::Single { length: lengths[0] }
+        } else {
+            FileKind::Multi {
+                files: files
+                    .iter()
+                    .zip(lengths)
+                    .map(|(file, length)| FileEntry {
+                        length,
+                        path: file.path.iter().collect(),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    })
+                    .collect(),
+            }
+        };
+
+        let info = Info {
+            name,
+            raw_name: None,
+            piece_length: self.piece_length,
+            pieces,
+            mode,
+            file_tree: None,
+            private: self.private,
+            source: self.source.clone().map(String::into_bytes),
+            version: TorrentVersion::V1,
+            md5sum: None,
+        };
+        let info_hash = Sha1::digest(info.to_item().encode()).into();
+
+        Ok(MetaInfo {
+            announce: self.announce.clone(),
+            info,
+            comment: self.comment.clone(),
+            created_by: self.created_by.clone(),
+            creation_date: None,
+            announce_list: None,
+            nodes: None,
+            web_seeds: Vec::new(),
+            info_hash,
+            info_hash_v1: Some(info_hash),
+            info_hash_v2: None,
+            piece_layers: HashMap::new(),
+            encoding: None,
+        })
+    }
+
+    /// Lists the files of every source in order, with directories walked in ascending order of path.
+    ///
+    /// If the only source is a directory its files are placed at the root of the torrent, otherwise each source is placed under its own name
+    fn collect_files(&self) -> Result<Vec<DiskFile>, BuildError> {
+        let nest = self.sources.len() > 1;
+        let mut files = Vec::new();
+
+        for source in &self.sources {
+            match source {
+                Source::File(path) => files.push(DiskFile {
+                    disk_path: path.clone(),
+                    path: vec![file_name(path)?],
+                }),
+                Source::Dir(dir) => {
+                    let prefix = if nest { vec![file_name(dir)?] } else { vec![] };
+                    walk_dir(dir, prefix, &mut files)?;
+                }
+            }
+        }
+
+        Ok(files)
+    }
+}
+
+/// Returns the final component of a path, which must be valid UTF-8
+fn file_name(path: &Path) -> Result<String, BuildError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_owned)
+        .ok_or_else(|| BuildError::InvalidPath {
+            path: path.to_owned(),
+        })
+}
+
+fn walk_dir(dir: &Path, prefix: Vec<String>, files: &mut Vec<DiskFile>) -> Result<(), BuildError> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.and_then(|entry| Ok((entry.path(), entry.file_type()?))))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (entry, file_type) in entries {
+        let mut path = prefix.clone();
+        path.push(file_name(&entry)?);
+
+        // the file type doesn't follow symlinks, so a link back up the tree can't make this recurse forever
+        if file_type.is_dir() {
+            walk_dir(&entry, path, files)?;
+        } else if file_type.is_symlink() && entry.is_dir() {
+            // links to directories are skipped, while links to files are added with the contents of their target
+            continue;
+        } else {
+            files.push(DiskFile {
+                disk_path: entry,
+                path,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Hashes the files as one continuous stream split into pieces, returning the concatenated hashes and the length of each file.
+///
+/// Pieces may span several files, and the final piece is shorter if the total length isn't a multiple of the piece length.
+/// Files are read sequentially, and with more than one thread batches of whole pieces are hashed in parallel
+fn hash_pieces(
+    files: &[DiskFile],
+    piece_length: usize,
+    threads: usize,
+) -> Result<(Vec<u8>, Vec<u64>), BuildError> {
+    let batch_len = if threads > 1 { threads * 4 } else { 1 };
+
+    let mut pieces = Vec::new();
+    let mut lengths = Vec::with_capacity(files.len());
+    let mut batch = Vec::with_capacity(batch_len);
+
+    let mut buffer = vec![0; piece_length];
+    let mut filled = 0;
+
+    for file in files {
+        let mut reader = File::open(&file.disk_path)?;
+        let mut length = 0;
+
+        loop {
+            let read = reader.read(&mut buffer[filled..])?;
+            if read == 0 {
+                break;
+            }
+
+            length += read as u64;
+            filled += read;
+            if filled == piece_length {
+                batch.push(std::mem::replace(&mut buffer, vec![0; piece_length]));
+                filled = 0;
+
+                if batch.len() == batch_len {
+                    hash_batch(&mut batch, threads, &mut pieces);
+                }
+            }
+        }
+
+        lengths.push(length);
+    }
+
+    if filled > 0 {
+        buffer.truncate(filled);
+        batch.push(buffer);
+    }
+    hash_batch(&mut batch, threads, &mut pieces);
+
+    Ok((pieces, lengths))
+}
+
+/// Hashes a batch of pieces, splitting it between up to `threads` threads, and appends the hashes in order
+fn hash_batch(batch: &mut Vec<Vec<u8>>, threads: usize, pieces: &mut Vec<u8>) {
+    if threads <= 1 || batch.len() <= 1 {
+        for piece in batch.iter() {
+            pieces.extend_from_slice(&Sha1::digest(piece));
+        }
+    } else {
+        let chunk_len = batch.len().div_ceil(threads);
+        let hashes: Vec<Vec<u8>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .chunks(chunk_len)
+                .map(|chunk| scope.spawn(move || chunk.iter().flat_map(Sha1::digest).collect()))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("hashing thread panicked"))
+                .collect()
+        });
+
+        for hash in hashes {
+            pieces.extend_from_slice(&hash);
+        }
+    }
+
+    batch.clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Creates an empty directory unique to a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("torrent-builder-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_multi_file() {
+        let dir = temp_dir("multi");
+        std::fs::write(dir.join("a.txt"), b"hello").unwrap();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("b.bin"), [7; 40]).unwrap();
+
+        let meta = TorrentBuilder::new()
+            .add_dir(&dir)
+            .name("test")
+            .piece_length(16)
+            .announce("http://tracker.example/announce")
+            .build()
+            .unwrap();
+
+        assert_eq!(meta.info.name, "test");
+        assert_eq!(
+            meta.info.mode,
+            FileKind::Multi {
+                files: vec![
+                    FileEntry {
+                        length: 5,
+                        path: PathBuf::from("a.txt"),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                    FileEntry {
+                        length: 40,
+                        path: PathBuf::from("sub").join("b.bin"),
+                        pieces_root: None,
+                        md5sum: None,
+                        attr: FileAttributes::default(),
+                        symlink_path: None,
+                    },
+                ]
+            }
+        );
+
+        // 45 bytes split into two full pieces spanning both files and a short final piece
+        let data = [&b"hello"[..], &[7; 40]].concat();
+        let expected: Vec<u8> = data.chunks(16).flat_map(Sha1::digest).collect();
+        assert_eq!(meta.info.pieces, expected);
+
+        let parsed = MetaInfo::from_bytes(&meta.encode()).unwrap();
+        assert_eq!(parsed, meta);
+        assert_eq!(parsed.info_hash(), meta.info_hash());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_single_file() {
+        let dir = temp_dir("single");
+        std::fs::write(dir.join("file.txt"), [1; 32]).unwrap();
+
+        let meta = TorrentBuilder::new()
+            .add_file(dir.join("file.txt"))
+            .piece_length(16)
+            .private(true)
+            .source("tracker")
+            .build()
+            .unwrap();
+
+        assert_eq!(meta.info.name, "file.txt");
+        assert_eq!(meta.info.mode, FileKind::Single { length: 32 });
+        assert_eq!(meta.info.piece_hashes().unwrap().count(), 2);
+        assert!(meta.info.is_private());
+        assert_eq!(meta.info.source(), Some("tracker"));
+        assert_eq!(MetaInfo::from_bytes(&meta.encode()).unwrap(), meta);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel() {
+        let dir = temp_dir("parallel");
+        std::fs::write(dir.join("a"), (0..=255).collect::<Vec<u8>>()).unwrap();
+        std::fs::write(dir.join("b"), [3; 1000]).unwrap();
+        std::fs::write(dir.join("c"), [9; 77]).unwrap();
+
+        let builder = TorrentBuilder::new().add_dir(&dir).piece_length(16);
+        let serial = builder.clone().build().unwrap();
+
+        for threads in [2, 3, 8] {
+            let parallel = builder.clone().threads(threads).build().unwrap();
+
+            assert_eq!(parallel.info.pieces, serial.info.pieces);
+            assert_eq!(parallel.info_hash(), serial.info_hash());
+        }
+
+        let data = [(0..=255).collect(), vec![3; 1000], vec![9; 77]].concat();
+        let expected: Vec<u8> = data.chunks(16).flat_map(Sha1::digest).collect();
+        assert_eq!(serial.info.pieces, expected);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop() {
+        let dir = temp_dir("symlink");
+        std::fs::write(dir.join("a"), [1; 10]).unwrap();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("b"), [2; 20]).unwrap();
+        std::os::unix::fs::symlink("..", dir.join("sub").join("loop")).unwrap();
+        std::os::unix::fs::symlink("a", dir.join("link")).unwrap();
+
+        let meta = TorrentBuilder::new()
+            .add_dir(&dir)
+            .piece_length(16)
+            .build()
+            .unwrap();
+
+        let paths: Vec<_> = meta.info.files().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                PathBuf::from("a"),
+                PathBuf::from("link"),
+                ["sub", "b"].iter().collect()
+            ]
+        );
+        assert_eq!(meta.info.total_length(), Some(40));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            TorrentBuilder::new().build(),
+            Err(BuildError::NoFiles)
+        ));
+        assert!(matches!(
+            TorrentBuilder::new()
+                .add_file("a")
+                .piece_length(1000)
+                .build(),
+            Err(BuildError::InvalidPieceLength { piece_length: 1000 })
+        ));
+        assert!(matches!(
+            TorrentBuilder::new().add_file("/nonexistent/file").build(),
+            Err(BuildError::Io(_))
+        ));
+    }
+}
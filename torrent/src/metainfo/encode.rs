@@ -0,0 +1,213 @@
+use std::path::Path;
+
+use super::{FileAttributes, FileEntry, FileKind, Info, MetaInfo};
+use crate::{
+    bencoding::{Dictionary, Item},
+    hex,
+};
+
+/// Converts a path into its components, replacing any invalid UTF-8
+fn path_components(path: &Path) -> Item {
+    Item::List(
+        path.iter()
+            .map(|component| Item::from(component.to_string_lossy().into_owned()))
+            .collect(),
+    )
+}
+
+/// Builds the nested file tree of a v2 torrent from files sorted by path
+fn file_tree(files: &[FileEntry]) -> Item {
+    let mut root = Dictionary::new();
+
+    for file in files {
+        let mut node = &mut root;
+        for component in file.path.iter() {
+            let child = node
+                .entry(component.to_string_lossy().into_owned())
+                .or_insert_with(|| Item::Dictionary(Dictionary::new()));
+
+            node = match child {
+                Item::Dictionary(child) => child,
+                _ => unreachable!("nodes of the tree are always dictionaries"),
+            };
+        }
+
+        let mut leaf = Dictionary::new();
+        if file.attr != FileAttributes::default() {
+            leaf.insert("attr".to_owned(), Item::from(file.attr.to_bytes()));
+        }
+        leaf.insert("length".to_owned(), Item::Integer(file.length as i64));
+        if let Some(root) = &file.pieces_root {
+            leaf.insert("pieces root".to_owned(), Item::from(&root[..]));
+        }
+        if let Some(target) = &file.symlink_path {
+            leaf.insert("symlink path".to_owned(), path_components(target));
+        }
+        node.insert(String::new(), Item::Dictionary(leaf));
+    }
+
+    Item::Dictionary(root)
+}
+
+impl MetaInfo {
+    /// Converts the torrent back into a dictionary, with keys inserted in ascending order.
+    ///
+    /// Keys this crate doesn't parse are lost, so the info hash only matches the original file if its info dictionary had none
+    pub fn to_item(&self) -> Item {
+        let mut dict = Dictionary::new();
+
+        if let Some(announce) = &self.announce {
+            dict.insert("announce".to_owned(), Item::from(announce.as_str()));
+        }
+        if let Some(tiers) = &self.announce_list {
+            let tiers = tiers
+                .iter()
+                .map(|tier| Item::List(tier.iter().map(|url| Item::from(url.as_str())).collect()))
+                .collect();
+            dict.insert("announce-list".to_owned(), Item::List(tiers));
+        }
+        if let Some(comment) = &self.comment {
+            dict.insert("comment".to_owned(), Item::from(comment.as_str()));
+        }
+        if let Some(created_by) = &self.created_by {
+            dict.insert("created by".to_owned(), Item::from(created_by.as_str()));
+        }
+        if let Some(creation_date) = self.creation_date {
+            dict.insert("creation date".to_owned(), Item::Integer(creation_date));
+        }
+        if let Some(encoding) = &self.encoding {
+            dict.insert("encoding".to_owned(), Item::from(encoding.as_str()));
+        }
+        dict.insert("info".to_owned(), self.info.to_item());
+        if let Some(nodes) = &self.nodes {
+            let nodes = nodes
+                .iter()
+                .map(|(host, port)| {
+                    Item::List(vec![
+                        Item::from(host.as_str()),
+                        Item::Integer((*port).into()),
+                    ])
+                })
+                .collect();
+            dict.insert("nodes".to_owned(), Item::List(nodes));
+        }
+        if !self.piece_layers.is_empty() {
+            let mut layers: Vec<_> = self.piece_layers.iter().collect();
+            layers.sort_unstable_by_key(|&(root, _)| root);
+
+            let layers = layers
+                .into_iter()
+                .map(|(root, layer)| (root.to_vec(), Item::ByteArray(layer.concat())))
+                .collect();
+            dict.insert("piece layers".to_owned(), Item::RawDictionary(layers));
+        }
+        if !self.web_seeds.is_empty() {
+            let urls = self
+                .web_seeds
+                .iter()
+                .map(|url| Item::from(url.as_str()))
+                .collect();
+            dict.insert("url-list".to_owned(), Item::List(urls));
+        }
+
+        Item::Dictionary(dict)
+    }
+
+    /// Encodes the torrent as the contents of a .torrent file
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_item().encode()
+    }
+}
+
+impl Info {
+    /// Converts the info back into a dictionary, with keys inserted in ascending order
+    pub fn to_item(&self) -> Item {
+        let mut dict = Dictionary::new();
+
+        if let Some(files) = &self.file_tree {
+            dict.insert("file tree".to_owned(), file_tree(files));
+        }
+
+        // v2 torrents only describe their files with the file tree
+        let v1 = self.file_tree.is_none() || !self.pieces.is_empty();
+        match &self.mode {
+            FileKind::Multi { files } if v1 => {
+                let files = files
+                    .iter()
+                    .map(|file| {
+                        let mut entry = Dictionary::new();
+                        if file.attr != FileAttributes::default() {
+                            entry.insert("attr".to_owned(), Item::from(file.attr.to_bytes()));
+                        }
+                        entry.insert("length".to_owned(), Item::Integer(file.length as i64));
+                        if let Some(md5sum) = &file.md5sum {
+                            entry.insert("md5sum".to_owned(), Item::from(hex::encode(md5sum)));
+                        }
+                        entry.insert("path".to_owned(), path_components(&file.path));
+                        if let Some(target) = &file.symlink_path {
+                            entry.insert("symlink path".to_owned(), path_components(target));
+                        }
+
+                        Item::Dictionary(entry)
+                    })
+                    .collect();
+                dict.insert("files".to_owned(), Item::List(files));
+            }
+            FileKind::Single { length } if v1 => {
+                dict.insert("length".to_owned(), Item::Integer(*length as i64));
+                if let Some(md5sum) = &self.md5sum {
+                    dict.insert("md5sum".to_owned(), Item::from(hex::encode(md5sum)));
+                }
+            }
+            _ => {}
+        }
+
+        if self.file_tree.is_some() {
+            dict.insert("meta version".to_owned(), Item::Integer(2));
+        }
+        dict.insert("name".to_owned(), Item::from(self.name_bytes()));
+        dict.insert(
+            "piece length".to_owned(),
+            Item::Integer(self.piece_length as i64),
+        );
+        if v1 {
+            dict.insert("pieces".to_owned(), Item::from(&self.pieces[..]));
+        }
+        if self.private {
+            dict.insert("private".to_owned(), Item::Integer(1));
+        }
+        if let Some(source) = &self.source {
+            dict.insert("source".to_owned(), Item::from(&source[..]));
+        }
+
+        Item::Dictionary(dict)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for path in [
+            "../sample.torrent",
+            "../archlinux-2022.10.01-x86_64.iso.torrent",
+        ] {
+            let meta = MetaInfo::from_path(path).unwrap();
+            let encoded = meta.encode();
+
+            assert_eq!(MetaInfo::from_bytes(&encoded).unwrap(), meta);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_v2() {
+        let data = b"d4:infod9:file treed4:docsd5:a.txtd0:d6:lengthi5e11:pieces root32:\
+            rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreee5:emptyd0:d6:lengthi0eeee\
+            12:meta versioni2e4:name4:test12:piece lengthi16384eee";
+        let meta = MetaInfo::from_bytes(data).unwrap();
+
+        assert_eq!(meta.encode(), data);
+    }
+}
@@ -0,0 +1,234 @@
+use std::{fmt::Display, path::PathBuf};
+
+use crate::bencoding::BencodeError;
+
+/// Represents an error encountered while reading a torrent file, with `field` being the path to the offending key
+#[derive(Debug)]
+pub enum MetaInfoError {
+    /// File was not valid BEncode
+    Decode(BencodeError),
+    /// Required key was absent
+    MissingField { field: &'static str },
+    /// Key was present but had the wrong type or an invalid value
+    InvalidField { field: &'static str },
+    /// File path had a `.` or `..` component or one containing a separator, which could escape the download directory
+    PathTraversal {
+        field: &'static str,
+        component: String,
+    },
+}
+
+impl Display for MetaInfoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode torrent: {err}"),
+            Self::MissingField { field } => write!(f, "missing field {field:?}"),
+            Self::InvalidField { field } => write!(f, "field {field:?} has an invalid value"),
+            Self::PathTraversal { field, component } => write!(
+                f,
+                "path component {component:?} in field {field:?} escapes the download directory"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MetaInfoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BencodeError> for MetaInfoError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Represents an error encountered while creating a torrent from files on disk
+#[derive(Debug)]
+pub enum BuildError {
+    /// No files were added, or the added directories were empty
+    NoFiles,
+    /// Piece length was not a power of two
+    InvalidPieceLength { piece_length: u64 },
+    /// Path had no file name or it was not valid UTF-8
+    InvalidPath { path: PathBuf },
+    /// Failed to read a file
+    Io(std::io::Error),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoFiles => write!(f, "no files were added to the torrent"),
+            Self::InvalidPieceLength { piece_length } => {
+                write!(f, "piece length {piece_length} is not a power of two")
+            }
+            Self::InvalidPath { path } => write!(f, "invalid file name in {}", path.display()),
+            Self::Io(err) => write!(f, "failed to read file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BuildError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Represents a way a torrent breaks the invariants of the BitTorrent protocol, found by [`super::MetaInfo::validate`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ValidationError {
+    /// Piece length was not a power of two
+    InvalidPieceLength { piece_length: u64 },
+    /// Concatenated piece hashes were not a whole number of 20 byte hashes
+    InvalidPiecesLength { length: usize },
+    /// Number of piece hashes didn't match the number of pieces needed for the total length
+    PieceCountMismatch { expected: u64, actual: usize },
+    /// Combined length of every file didn't fit in a `u64`
+    TotalLengthOverflow,
+    /// Multi-file torrent had no files
+    NoFiles,
+    /// Name or file path was absolute or contained `.` or `..` components, so it could escape the download directory
+    PathTraversal { path: PathBuf },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidPieceLength { piece_length } => {
+                write!(f, "piece length {piece_length} is not a power of two")
+            }
+            Self::InvalidPiecesLength { length } => {
+                write!(f, "pieces length {length} is not a multiple of 20")
+            }
+            Self::PieceCountMismatch { expected, actual } => {
+                write!(f, "expected {expected} piece hashes but found {actual}")
+            }
+            Self::TotalLengthOverflow => write!(f, "total length of every file is too large"),
+            Self::NoFiles => write!(f, "torrent has no files"),
+            Self::PathTraversal { path } => {
+                write!(f, "path {} escapes the download directory", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (
+                MetaInfoError::Decode(BencodeError::UnexpectedEof { offset: 3 }),
+                "failed to decode torrent: unexpected end of input at byte 3",
+            ),
+            (
+                MetaInfoError::MissingField { field: "info" },
+                "missing field \"info\"",
+            ),
+            (
+                MetaInfoError::InvalidField {
+                    field: "info.piece length",
+                },
+                "field \"info.piece length\" has an invalid value",
+            ),
+            (
+                MetaInfoError::PathTraversal {
+                    field: "info.files.path",
+                    component: "..".to_owned(),
+                },
+                "path component \"..\" in field \"info.files.path\" escapes the download directory",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+
+        let cases = [
+            (BuildError::NoFiles, "no files were added to the torrent"),
+            (
+                BuildError::InvalidPieceLength { piece_length: 1000 },
+                "piece length 1000 is not a power of two",
+            ),
+            (
+                BuildError::InvalidPath {
+                    path: PathBuf::from("/"),
+                },
+                "invalid file name in /",
+            ),
+            (
+                BuildError::Io(std::io::Error::other("disk on fire")),
+                "failed to read file: disk on fire",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+
+        let cases = [
+            (
+                ValidationError::InvalidPieceLength { piece_length: 1000 },
+                "piece length 1000 is not a power of two",
+            ),
+            (
+                ValidationError::InvalidPiecesLength { length: 30 },
+                "pieces length 30 is not a multiple of 20",
+            ),
+            (
+                ValidationError::PieceCountMismatch {
+                    expected: 2,
+                    actual: 1,
+                },
+                "expected 2 piece hashes but found 1",
+            ),
+            (
+                ValidationError::TotalLengthOverflow,
+                "total length of every file is too large",
+            ),
+            (ValidationError::NoFiles, "torrent has no files"),
+            (
+                ValidationError::PathTraversal {
+                    path: PathBuf::from("../etc/passwd"),
+                },
+                "path ../etc/passwd escapes the download directory",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_source() {
+        let err = MetaInfoError::from(BencodeError::InvalidItem { offset: 0 });
+        assert!(err.source().unwrap().is::<BencodeError>());
+        assert!(MetaInfoError::MissingField { field: "info" }
+            .source()
+            .is_none());
+
+        let err = BuildError::from(std::io::Error::other("disk on fire"));
+        assert!(err.source().unwrap().is::<std::io::Error>());
+        assert!(BuildError::NoFiles.source().is_none());
+    }
+}
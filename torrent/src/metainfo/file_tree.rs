@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    check_component, parse_attr, parse_file_length, to_path, FileEntry, FileKind, MetaInfoError,
+};
+use crate::bencoding::Item;
+
+const INVALID_TREE: MetaInfoError = MetaInfoError::InvalidField {
+    field: "info.file tree",
+};
+
+/// Flattens the nested file tree of a v2 torrent into its files, sorted by path as required by BEP 52.
+///
+/// Each directory is a dictionary of its children, and each file is a dictionary holding only an empty key
+pub(super) fn parse_file_tree(item: &Item) -> Result<Vec<FileEntry>, MetaInfoError> {
+    let mut files = Vec::new();
+    walk(item, PathBuf::new(), &mut files)?;
+
+    Ok(files)
+}
+
+fn walk(node: &Item, path: PathBuf, files: &mut Vec<FileEntry>) -> Result<(), MetaInfoError> {
+    let children = node.as_dict().ok_or(INVALID_TREE)?;
+
+    if let Some(file) = children.get("") {
+        // a file can't be the root of the tree or have siblings under the empty key
+        if children.len() != 1 || path.as_os_str().is_empty() {
+            return Err(INVALID_TREE);
+        }
+
+        let symlink_path = file
+            .get("symlink path")
+            .map(|target| to_path(target, "info.file tree.symlink path"))
+            .transpose()?;
+
+        files.push(FileEntry {
+            length: parse_file_length(file, &symlink_path, "info.file tree.length")?,
+            path,
+            md5sum: None,
+            attr: parse_attr(file, "info.file tree.attr")?,
+            symlink_path,
+            pieces_root: file
+                .get("pieces root")
+                .map(|root| {
+                    root.as_bytes().and_then(|root| root.try_into().ok()).ok_or(
+                        MetaInfoError::InvalidField {
+                            field: "info.file tree.pieces root",
+                        },
+                    )
+                })
+                .transpose()?,
+        });
+
+        return Ok(());
+    }
+
+    if children.is_empty() {
+        return Err(INVALID_TREE);
+    }
+
+    let mut names: Vec<&String> = children.keys().collect();
+    names.sort();
+
+    for name in names {
+        check_component(name, "info.file tree")?;
+
+        walk(&children[name.as_str()], path.join(name), files)?;
+    }
+
+    Ok(())
+}
+
+/// Checks that the v1 files of a hybrid torrent describe the same files as its file tree.
+///
+/// The v1 file list may contain padding files, marked with `p` in their `attr`, which are inserted to align each file to a piece boundary
+pub(super) fn matches_v1(name: &str, mode: &FileKind, tree: &[FileEntry]) -> bool {
+    let v1: Vec<(&Path, u64)> = match mode {
+        FileKind::Single { length } => vec![(Path::new(name), *length)],
+        FileKind::Multi { files } => files
+            .iter()
+            .filter(|file| !file.is_padding())
+            .map(|file| (file.path.as_path(), file.length))
+            .collect(),
+    };
+
+    v1.len() == tree.len()
+        && v1
+            .iter()
+            .zip(tree)
+            .all(|(&(path, length), file)| path == file.path && length == file.length)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bencoding::BEncoding, metainfo::FileAttributes};
+
+    fn parse(bytes: &[u8]) -> Result<Vec<FileEntry>, MetaInfoError> {
+        let (tree, _) = BEncoding::decode_with_remainder(bytes).unwrap();
+        parse_file_tree(&tree)
+    }
+
+    #[test]
+    fn test_nested() {
+        let files = parse(
+            b"d1:bd0:d6:lengthi0eee3:dird1:ad0:d6:lengthi5e11:pieces root32:rrrrrrrrrrrrrrrrrrrrrrrrrrrrrrrreeee",
+        )
+        .unwrap();
+
+        assert_eq!(
+            files,
+            [
+                FileEntry {
+                    length: 0,
+                    path: PathBuf::from("b"),
+                    pieces_root: None,
+                    md5sum: None,
+                    attr: FileAttributes::default(),
+                    symlink_path: None,
+                },
+                FileEntry {
+                    length: 5,
+                    path: PathBuf::from("dir").join("a"),
+                    pieces_root: Some([b'r'; 32]),
+                    md5sum: None,
+                    attr: FileAttributes::default(),
+                    symlink_path: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for tree in [
+            &b"d0:d6:lengthi1eee"[..],
+            b"d1:ad0:d6:lengthi1ee1:bd0:d6:lengthi1eeeee",
+            b"d1:adee",
+            b"d1:ai1ee",
+        ] {
+            assert!(matches!(
+                parse(tree),
+                Err(MetaInfoError::InvalidField {
+                    field: "info.file tree"
+                })
+            ));
+        }
+
+        for tree in [
+            &b"d3:a/bd0:d6:lengthi1eeee"[..],
+            b"d2:..d6:passwdd0:d6:lengthi1eeeee",
+            b"d1:.d1:ad0:d6:lengthi1eeeee",
+        ] {
+            assert!(matches!(
+                parse(tree),
+                Err(MetaInfoError::PathTraversal {
+                    field: "info.file tree",
+                    ..
+                })
+            ));
+        }
+
+        assert!(matches!(
+            parse(b"d1:ad0:d6:lengthi1e11:pieces root3:abceee"),
+            Err(MetaInfoError::InvalidField {
+                field: "info.file tree.pieces root"
+            })
+        ));
+        assert!(matches!(
+            parse(b"d1:ad0:deee"),
+            Err(MetaInfoError::MissingField {
+                field: "info.file tree.length"
+            })
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::{Component, Path};
+
+use super::{FileKind, MetaInfo, TorrentVersion, ValidationError};
+
+/// Returns whether a path stays within the directory it is relative to, having only normal components
+fn is_contained(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+impl MetaInfo {
+    /// Checks the torrent is safe and consistent to download, returning every problem found.
+    ///
+    /// Having both or neither of a single file length and a files list is already rejected while parsing
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let info = &self.info;
+        let mut errors = Vec::new();
+
+        let valid_piece_length = info.piece_length.is_power_of_two();
+        if !valid_piece_length {
+            errors.push(ValidationError::InvalidPieceLength {
+                piece_length: info.piece_length,
+            });
+        }
+
+        // v2 torrents hash pieces per file in the file tree instead
+        if info.version != TorrentVersion::V2 {
+            let actual = info.pieces.len() / 20;
+
+            if !info.pieces.len().is_multiple_of(20) {
+                errors.push(ValidationError::InvalidPiecesLength {
+                    length: info.pieces.len(),
+                });
+            } else if valid_piece_length {
+                match info.total_length() {
+                    Some(total_length) => {
+                        let expected = total_length.div_ceil(info.piece_length);
+
+                        if expected != actual as u64 {
+                            errors.push(ValidationError::PieceCountMismatch { expected, actual });
+                        }
+                    }
+                    None => errors.push(ValidationError::TotalLengthOverflow),
+                }
+            }
+        }
+
+        let name = Path::new(&info.name);
+        if !is_contained(name) || name.components().count() != 1 {
+            errors.push(ValidationError::PathTraversal {
+                path: name.to_path_buf(),
+            });
+        }
+
+        if let FileKind::Multi { files } = &info.mode {
+            if files.is_empty() {
+                errors.push(ValidationError::NoFiles);
+            }
+
+            let files = files.iter().chain(info.file_tree.iter().flatten());
+            for file in files {
+                if !is_contained(&file.path) {
+                    errors.push(ValidationError::PathTraversal {
+                        path: file.path.clone(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::metainfo::{FileAttributes, FileEntry};
+
+    fn sample() -> MetaInfo {
+        MetaInfo::from_path("../sample.torrent").unwrap()
+    }
+
+    fn file(path: &str, length: u64) -> FileEntry {
+        FileEntry {
+            length,
+            path: PathBuf::from(path),
+            pieces_root: None,
+            md5sum: None,
+            attr: FileAttributes::default(),
+            symlink_path: None,
+        }
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(sample().validate(), Ok(()));
+
+        let meta = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert_eq!(meta.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_piece_length() {
+        let mut meta = sample();
+        meta.info.piece_length = 65535;
+
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::InvalidPieceLength {
+                piece_length: 65535
+            }])
+        );
+    }
+
+    #[test]
+    fn test_pieces_length() {
+        let mut meta = sample();
+        meta.info.pieces.truncate(19);
+
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::InvalidPiecesLength { length: 19 }])
+        );
+    }
+
+    #[test]
+    fn test_piece_count() {
+        let mut meta = sample();
+        meta.info.pieces.extend([0; 20]);
+
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::PieceCountMismatch {
+                expected: 1,
+                actual: 2
+            }])
+        );
+    }
+
+    #[test]
+    fn test_total_length_overflow() {
+        let mut meta = sample();
+        meta.info.mode = FileKind::Multi {
+            files: vec![file("a.txt", u64::MAX), file("b.txt", 1)],
+        };
+
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::TotalLengthOverflow])
+        );
+    }
+
+    #[test]
+    fn test_path_traversal() {
+        let mut meta = sample();
+        meta.info.mode = FileKind::Multi {
+            files: vec![file("a.txt", 10), file("../../etc/passwd", 10)],
+        };
+
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::PathTraversal {
+                path: PathBuf::from("../../etc/passwd")
+            }])
+        );
+
+        let mut meta = sample();
+        meta.info.mode = FileKind::Multi {
+            files: vec![file("/etc/passwd", 20)],
+        };
+        assert!(meta.validate().is_err());
+
+        let mut meta = sample();
+        meta.info.name = "..".to_owned();
+        assert_eq!(
+            meta.validate(),
+            Err(vec![ValidationError::PathTraversal {
+                path: PathBuf::from("..")
+            }])
+        );
+
+        let mut meta = sample();
+        meta.info.name = "dir/sample.txt".to_owned();
+        assert!(meta.validate().is_err());
+    }
+
+    #[test]
+    fn test_no_files() {
+        let mut meta = sample();
+        meta.info.mode = FileKind::Multi { files: vec![] };
+        meta.info.pieces.clear();
+
+        assert_eq!(meta.validate(), Err(vec![ValidationError::NoFiles]));
+    }
+
+    #[test]
+    fn test_several_errors() {
+        let mut meta = sample();
+        meta.info.piece_length = 0;
+        meta.info.pieces.truncate(10);
+        meta.info.name = "".to_owned();
+
+        assert_eq!(meta.validate().unwrap_err().len(), 3);
+    }
+}
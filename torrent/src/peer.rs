@@ -0,0 +1,99 @@
+mod bitfield;
+mod error;
+mod extension;
+mod handshake;
+mod message;
+mod metadata;
+mod pex;
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use crate::percent;
+
+pub use bitfield::Bitfield;
+pub use error::{ExtensionError, HandshakeError, MessageError, MetadataError};
+pub use extension::ExtensionHandshake;
+pub use handshake::Handshake;
+pub use message::PeerMessage;
+pub use metadata::{
+    MetadataAssembler, MetadataMessage, MetadataPiece, MetadataRequest, METADATA_PIECE_LEN,
+};
+pub use pex::PexMessage;
+
+/// 20 byte identifier a client uses for itself when announcing and connecting to peers
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct PeerId(pub [u8; 20]);
+
+impl PeerId {
+    /// Generates a peer id starting with `client_prefix`, such as the Azureus-style `-TR3000-`, followed by random alphanumeric characters.
+    ///
+    /// Prefixes longer than 20 bytes are truncated
+    pub fn generate(client_prefix: &str) -> Self {
+        let prefix = &client_prefix.as_bytes()[..client_prefix.len().min(20)];
+
+        let mut id = [0; 20];
+        id[..prefix.len()].copy_from_slice(prefix);
+        for (byte, random) in id[prefix.len()..]
+            .iter_mut()
+            .zip(rand::thread_rng().sample_iter(Alphanumeric))
+        {
+            *byte = random;
+        }
+
+        Self(id)
+    }
+
+    /// Returns the raw bytes of the id
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// Returns the id percent-encoded for use in announce URLs
+    pub fn as_urlencoded(&self) -> String {
+        percent::encode(&self.0)
+    }
+}
+
+impl From<[u8; 20]> for PeerId {
+    fn from(id: [u8; 20]) -> Self {
+        Self(id)
+    }
+}
+
+impl From<PeerId> for [u8; 20] {
+    fn from(id: PeerId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let id = PeerId::generate("-TR3000-");
+        assert_eq!(id.as_bytes().len(), 20);
+        assert!(id.as_bytes().starts_with(b"-TR3000-"));
+        assert!(id.as_bytes()[8..].iter().all(u8::is_ascii_alphanumeric));
+        assert_ne!(id, PeerId::generate("-TR3000-"));
+
+        let id = PeerId::generate("");
+        assert!(id.as_bytes().iter().all(u8::is_ascii_alphanumeric));
+
+        let id = PeerId::generate("-abcdefghijklmnopqrstuvwxyz-");
+        assert_eq!(id.as_bytes(), b"-abcdefghijklmnopqrs");
+    }
+
+    #[test]
+    fn test_as_urlencoded() {
+        let id = PeerId::generate("-TR3000-");
+        assert_eq!(
+            id.as_urlencoded(),
+            std::str::from_utf8(id.as_bytes()).unwrap()
+        );
+
+        let id = PeerId(*b"-TR3000-  ~\xff00000000");
+        assert_eq!(id.as_urlencoded(), "-TR3000-%20%20~%FF00000000");
+    }
+}
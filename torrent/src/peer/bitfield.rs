@@ -0,0 +1,141 @@
+/// Set of pieces a peer has, stored with the most significant bit of the first byte being piece 0
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Bitfield {
+    bytes: Vec<u8>,
+    /// Number of pieces in the torrent, which may be less than the number of bits
+    len: usize,
+}
+
+impl Bitfield {
+    /// Creates a bitfield for `len` pieces, with none set
+    pub fn new(len: usize) -> Self {
+        Self {
+            bytes: vec![0; len.div_ceil(8)],
+            len,
+        }
+    }
+
+    /// Creates a bitfield for `len` pieces from the payload of a bitfield message.
+    ///
+    /// Returns None if the payload is the wrong length for the number of pieces, or any spare bits are set
+    pub fn from_bytes(bytes: Vec<u8>, len: usize) -> Option<Self> {
+        if bytes.len() != len.div_ceil(8) {
+            return None;
+        }
+
+        let spare = bytes.len() * 8 - len;
+        if spare > 0
+            && bytes
+                .last()
+                .is_some_and(|last| last & ((1 << spare) - 1) != 0)
+        {
+            return None;
+        }
+
+        Some(Self { bytes, len })
+    }
+
+    /// Returns the bytes as sent in a bitfield message
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the number of pieces the bitfield covers
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the bitfield covers no pieces
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the piece is set, which is false for pieces out of range
+    pub fn has(&self, index: usize) -> bool {
+        index < self.len && self.bytes[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+
+    /// Marks the piece as set.
+    ///
+    /// # Panics
+    /// Panics if the index is out of range
+    pub fn set(&mut self, index: usize) {
+        assert!(
+            index < self.len,
+            "index {index} out of bounds for bitfield of length {}",
+            self.len
+        );
+
+        self.bytes[index / 8] |= 0x80 >> (index % 8);
+    }
+
+    /// Returns the number of pieces that are set
+    pub fn count_set(&self) -> usize {
+        self.bytes
+            .iter()
+            .map(|byte| byte.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns the indices of the pieces that are set, in ascending order
+    pub fn iter_set(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&index| self.has(index))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bit_order() {
+        let bitfield = Bitfield::from_bytes(vec![0b1010_0000, 0b0100_0000], 10).unwrap();
+
+        assert!(bitfield.has(0));
+        assert!(!bitfield.has(1));
+        assert!(bitfield.has(2));
+        assert!(bitfield.has(9));
+        assert_eq!(bitfield.count_set(), 3);
+        assert_eq!(bitfield.iter_set().collect::<Vec<_>>(), [0, 2, 9]);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let bitfield = Bitfield::from_bytes(vec![0xff, 0xc0], 10).unwrap();
+
+        assert!(bitfield.has(9));
+        assert!(!bitfield.has(10));
+        assert!(!bitfield.has(1000));
+    }
+
+    #[test]
+    fn test_set() {
+        let mut bitfield = Bitfield::new(10);
+        assert_eq!(bitfield.as_bytes(), [0, 0]);
+        assert_eq!(bitfield.count_set(), 0);
+
+        bitfield.set(0);
+        bitfield.set(9);
+        bitfield.set(9);
+        assert_eq!(bitfield.as_bytes(), [0b1000_0000, 0b0100_0000]);
+        assert_eq!(bitfield.count_set(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 10 out of bounds for bitfield of length 10")]
+    fn test_set_out_of_range() {
+        Bitfield::new(10).set(10);
+    }
+
+    #[test]
+    fn test_from_bytes_invalid() {
+        // spare bits are set
+        assert_eq!(Bitfield::from_bytes(vec![0xff, 0xe0], 10), None);
+        // wrong number of bytes
+        assert_eq!(Bitfield::from_bytes(vec![0xff], 10), None);
+        assert_eq!(Bitfield::from_bytes(vec![0xff, 0, 0], 10), None);
+
+        assert!(Bitfield::from_bytes(vec![], 0).unwrap().is_empty());
+        assert!(Bitfield::from_bytes(vec![0xff], 8).is_some());
+    }
+}
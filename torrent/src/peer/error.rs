@@ -0,0 +1,222 @@
+use std::fmt::Display;
+
+use crate::{bencoding::BencodeError, metainfo::MetaInfoError};
+
+/// Represents an error encountered while decoding a peer handshake
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HandshakeError {
+    /// Handshake was not exactly 68 bytes
+    InvalidLength { length: usize },
+    /// Peer is not speaking the BitTorrent protocol
+    InvalidProtocol,
+}
+
+impl Display for HandshakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidLength { length } => {
+                write!(f, "handshake is {length} bytes instead of 68")
+            }
+            Self::InvalidProtocol => write!(f, "handshake is not for the BitTorrent protocol"),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// Represents an error encountered while decoding a peer wire message
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MessageError {
+    /// Buffer holds only part of a message, and at least `needed` more bytes must be read
+    Incomplete { needed: usize },
+    /// Length prefix exceeded [`PeerMessage::MAX_LEN`](super::PeerMessage::MAX_LEN)
+    TooLong { length: u32 },
+    /// Payload had the wrong length for the message id
+    InvalidPayload { id: u8 },
+}
+
+impl Display for MessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Incomplete { needed } => {
+                write!(f, "message is incomplete, needing {needed} more bytes")
+            }
+            Self::TooLong { length } => write!(f, "message length {length} is too long"),
+            Self::InvalidPayload { id } => {
+                write!(f, "message with id {id} has an invalid payload")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+/// Represents an error encountered while decoding an extension protocol message
+#[derive(Debug)]
+pub enum ExtensionError {
+    /// Payload was not valid BEncode
+    Decode(BencodeError),
+    /// Required key was absent
+    MissingField { field: &'static str },
+    /// Key was present but had the wrong type or an invalid value
+    InvalidField { field: &'static str },
+}
+
+impl Display for ExtensionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode extension message: {err}"),
+            Self::MissingField { field } => write!(f, "missing field {field:?}"),
+            Self::InvalidField { field } => write!(f, "field {field:?} has an invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for ExtensionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BencodeError> for ExtensionError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Represents an error encountered while assembling metadata received from peers
+#[derive(Debug)]
+pub enum MetadataError {
+    /// Advertised metadata size was zero or unreasonably large
+    InvalidSize { total_size: u32 },
+    /// Piece had the wrong index, size or total size
+    InvalidPiece { piece: u32 },
+    /// Not every piece has been received
+    Incomplete,
+    /// Assembled metadata didn't match the info hash
+    HashMismatch,
+    /// Assembled metadata was not a valid info dictionary
+    Info(MetaInfoError),
+}
+
+impl Display for MetadataError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidSize { total_size } => write!(f, "invalid metadata size {total_size}"),
+            Self::InvalidPiece { piece } => write!(f, "invalid metadata piece {piece}"),
+            Self::Incomplete => write!(f, "metadata is incomplete"),
+            Self::HashMismatch => write!(f, "metadata does not match the info hash"),
+            Self::Info(err) => write!(f, "invalid metadata: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Info(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<MetaInfoError> for MetadataError {
+    fn from(err: MetaInfoError) -> Self {
+        Self::Info(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            HandshakeError::InvalidLength { length: 67 }.to_string(),
+            "handshake is 67 bytes instead of 68"
+        );
+        assert_eq!(
+            HandshakeError::InvalidProtocol.to_string(),
+            "handshake is not for the BitTorrent protocol"
+        );
+
+        let cases = [
+            (
+                MessageError::Incomplete { needed: 4 },
+                "message is incomplete, needing 4 more bytes",
+            ),
+            (
+                MessageError::TooLong { length: 1 << 20 },
+                "message length 1048576 is too long",
+            ),
+            (
+                MessageError::InvalidPayload { id: 4 },
+                "message with id 4 has an invalid payload",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+
+        let cases = [
+            (
+                ExtensionError::Decode(BencodeError::InvalidItem { offset: 0 }),
+                "failed to decode extension message: invalid item at byte 0",
+            ),
+            (
+                ExtensionError::MissingField { field: "m" },
+                "missing field \"m\"",
+            ),
+            (
+                ExtensionError::InvalidField { field: "reqq" },
+                "field \"reqq\" has an invalid value",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+
+        let cases = [
+            (
+                MetadataError::InvalidSize { total_size: 0 },
+                "invalid metadata size 0",
+            ),
+            (
+                MetadataError::InvalidPiece { piece: 3 },
+                "invalid metadata piece 3",
+            ),
+            (MetadataError::Incomplete, "metadata is incomplete"),
+            (
+                MetadataError::HashMismatch,
+                "metadata does not match the info hash",
+            ),
+            (
+                MetadataError::Info(MetaInfoError::MissingField { field: "info.name" }),
+                "invalid metadata: missing field \"info.name\"",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_source() {
+        let err = ExtensionError::from(BencodeError::InvalidItem { offset: 0 });
+        assert!(err.source().unwrap().is::<BencodeError>());
+
+        let err = MetadataError::from(MetaInfoError::MissingField { field: "info.name" });
+        assert!(err.source().unwrap().is::<MetaInfoError>());
+
+        assert!(MetadataError::HashMismatch.source().is_none());
+        assert!(HandshakeError::InvalidProtocol.source().is_none());
+    }
+}
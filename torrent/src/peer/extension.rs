@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use super::{ExtensionError, PeerMessage};
+use crate::bencoding::{BEncoding, Dictionary, Item};
+
+/// Extracts an optional key which must fit in a `u32` if present
+fn optional_u32(dict: &Item, field: &'static str) -> Result<Option<u32>, ExtensionError> {
+    dict.get(field)
+        .map(|value| {
+            value
+                .as_integer()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or(ExtensionError::InvalidField { field })
+        })
+        .transpose()
+}
+
+/// Represents the handshake of the extension protocol, from BEP 10
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ExtensionHandshake {
+    /// Extension names mapped to the message id the sender wants to receive them with, where 0 means disabled
+    pub m: HashMap<String, u8>,
+    /// Client name and version
+    pub v: Option<String>,
+    /// Number of outstanding requests the sender supports
+    pub reqq: Option<u32>,
+    /// Size of the info dictionary in bytes, if the sender has it and supports `ut_metadata`
+    pub metadata_size: Option<u32>,
+}
+
+impl ExtensionHandshake {
+    /// Message id of the extension handshake within extended messages
+    pub const ID: u8 = 0;
+
+    /// Returns the message id the sender uses for an extension, if it is supported
+    pub fn extension_id(&self, name: &str) -> Option<u8> {
+        self.m.get(name).copied().filter(|&id| id != 0)
+    }
+
+    /// Decodes the payload of an extension handshake, ignoring keys for other extensions
+    pub fn decode(payload: &[u8]) -> Result<Self, ExtensionError> {
+        let decoded = BEncoding::decode_strict(payload)?;
+        let root = decoded.root().expect("strict decoding produces one item");
+
+        let m = match root.get("m") {
+            Some(Item::Dictionary(m)) => m
+                .iter()
+                .map(|(name, id)| {
+                    let id = id
+                        .as_integer()
+                        .and_then(|id| u8::try_from(id).ok())
+                        .ok_or(ExtensionError::InvalidField { field: "m" })?;
+
+                    Ok((name.clone(), id))
+                })
+                .collect::<Result<_, ExtensionError>>()?,
+            Some(_) => return Err(ExtensionError::InvalidField { field: "m" }),
+            None => return Err(ExtensionError::MissingField { field: "m" }),
+        };
+
+        Ok(Self {
+            m,
+            v: root
+                .get("v")
+                .map(|v| {
+                    v.as_str()
+                        .map(str::to_owned)
+                        .ok_or(ExtensionError::InvalidField { field: "v" })
+                })
+                .transpose()?,
+            reqq: optional_u32(root, "reqq")?,
+            metadata_size: optional_u32(root, "metadata_size")?,
+        })
+    }
+
+    /// Encodes the handshake as the payload of an extended message
+    pub fn encode(&self) -> Vec<u8> {
+        let mut names: Vec<_> = self.m.iter().collect();
+        names.sort();
+
+        // keys are inserted in sorted order so the output is canonical with the `ordered` feature
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "m".to_owned(),
+            Item::Dictionary(
+                names
+                    .into_iter()
+                    .map(|(name, &id)| (name.clone(), Item::Integer(id.into())))
+                    .collect(),
+            ),
+        );
+        if let Some(metadata_size) = self.metadata_size {
+            dict.insert(
+                "metadata_size".to_owned(),
+                Item::Integer(metadata_size.into()),
+            );
+        }
+        if let Some(reqq) = self.reqq {
+            dict.insert("reqq".to_owned(), Item::Integer(reqq.into()));
+        }
+        if let Some(v) = &self.v {
+            dict.insert("v".to_owned(), Item::from(v.as_str()));
+        }
+
+        Item::Dictionary(dict).encode()
+    }
+
+    /// Wraps the handshake in an extended peer message
+    pub fn to_message(&self) -> PeerMessage {
+        PeerMessage::Extended {
+            id: Self::ID,
+            payload: self.encode(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Extension handshake captured from a qBittorrent peer
+    const CAPTURED: &[u8] = b"d1:md11:lt_donthavei7e10:share_modei8e11:upload_onlyi3e12:ut_holepunchi4e11:ut_metadatai2e6:ut_pexi1ee13:metadata_sizei31235e4:reqqi500e11:upload_onlyi0e1:v17:qBittorrent/4.4.5e";
+
+    #[test]
+    fn test_decode() {
+        let handshake = ExtensionHandshake::decode(CAPTURED).unwrap();
+
+        assert_eq!(handshake.extension_id("ut_metadata"), Some(2));
+        assert_eq!(handshake.extension_id("ut_pex"), Some(1));
+        assert_eq!(handshake.extension_id("missing"), None);
+        assert_eq!(handshake.m.len(), 6);
+        assert_eq!(handshake.v.as_deref(), Some("qBittorrent/4.4.5"));
+        assert_eq!(handshake.reqq, Some(500));
+        assert_eq!(handshake.metadata_size, Some(31235));
+    }
+
+    #[test]
+    fn test_disabled_extension() {
+        let handshake = ExtensionHandshake::decode(b"d1:md6:ut_pexi0eee").unwrap();
+
+        assert_eq!(handshake.m["ut_pex"], 0);
+        assert_eq!(handshake.extension_id("ut_pex"), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        let handshake = ExtensionHandshake {
+            m: HashMap::from([("ut_pex".to_owned(), 1), ("ut_metadata".to_owned(), 2)]),
+            v: Some("torrent 0.1".to_owned()),
+            reqq: Some(250),
+            metadata_size: Some(31235),
+        };
+
+        assert_eq!(
+            handshake.encode(),
+            b"d1:md11:ut_metadatai2e6:ut_pexi1ee13:metadata_sizei31235e4:reqqi250e1:v11:torrent 0.1e"
+        );
+        assert_eq!(
+            ExtensionHandshake::decode(&handshake.encode()).unwrap(),
+            handshake
+        );
+
+        let message = handshake.to_message().encode();
+        assert_eq!(message[4], 20);
+        assert_eq!(message[5], 0);
+        assert_eq!(&message[6..], handshake.encode());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            ExtensionHandshake::decode(b"de"),
+            Err(ExtensionError::MissingField { field: "m" })
+        ));
+        assert!(matches!(
+            ExtensionHandshake::decode(b"d1:md6:ut_pexi256eee"),
+            Err(ExtensionError::InvalidField { field: "m" })
+        ));
+        assert!(matches!(
+            ExtensionHandshake::decode(b"d1:mdee"),
+            Ok(ExtensionHandshake {
+                v: None,
+                reqq: None,
+                ..
+            })
+        ));
+        assert!(matches!(
+            ExtensionHandshake::decode(b"d1:m"),
+            Err(ExtensionError::Decode(_))
+        ));
+    }
+}
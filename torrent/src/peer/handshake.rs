@@ -0,0 +1,136 @@
+use super::{HandshakeError, PeerId};
+
+/// Protocol identifier sent at the start of every handshake
+const PROTOCOL: &[u8; 19] = b"BitTorrent protocol";
+
+/// Represents the first message sent by each side of a peer connection, from BEP 3
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Handshake {
+    /// Flags advertising support for protocol extensions
+    pub reserved: [u8; 8],
+    pub info_hash: [u8; 20],
+    pub peer_id: PeerId,
+}
+
+impl Handshake {
+    /// Length of an encoded handshake
+    pub const LEN: usize = 68;
+
+    /// Creates a handshake with no extension flags set
+    pub fn new(info_hash: [u8; 20], peer_id: PeerId) -> Self {
+        Self {
+            reserved: [0; 8],
+            info_hash,
+            peer_id,
+        }
+    }
+
+    /// Returns whether the sender supports the extension protocol from BEP 10
+    pub fn supports_extensions(&self) -> bool {
+        self.reserved[5] & 0x10 != 0
+    }
+
+    /// Sets the reserved bit advertising support for the extension protocol from BEP 10
+    pub fn with_extensions(mut self) -> Self {
+        self.reserved[5] |= 0x10;
+        self
+    }
+
+    /// Encodes the handshake as sent on the wire
+    pub fn encode(&self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        bytes[0] = PROTOCOL.len() as u8;
+        bytes[1..20].copy_from_slice(PROTOCOL);
+        bytes[20..28].copy_from_slice(&self.reserved);
+        bytes[28..48].copy_from_slice(&self.info_hash);
+        bytes[48..68].copy_from_slice(self.peer_id.as_bytes());
+
+        bytes
+    }
+
+    /// Decodes a handshake, failing if it is not exactly 68 bytes or not for the BitTorrent protocol
+    pub fn decode(bytes: &[u8]) -> Result<Self, HandshakeError> {
+        let bytes: &[u8; Self::LEN] =
+            bytes
+                .try_into()
+                .map_err(|_| HandshakeError::InvalidLength {
+                    length: bytes.len(),
+                })?;
+
+        if bytes[0] as usize != PROTOCOL.len() || &bytes[1..20] != PROTOCOL {
+            return Err(HandshakeError::InvalidProtocol);
+        }
+
+        Ok(Self {
+            reserved: bytes[20..28].try_into().expect("range is 8 bytes"),
+            info_hash: bytes[28..48].try_into().expect("range is 20 bytes"),
+            peer_id: PeerId(bytes[48..68].try_into().expect("range is 20 bytes")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handshake() -> Handshake {
+        Handshake {
+            reserved: [0, 0, 0, 0, 0, 0x10, 0, 0x01],
+            info_hash: [0xab; 20],
+            peer_id: PeerId(*b"-TR3000-000000000000"),
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let bytes = handshake().encode();
+
+        assert_eq!(bytes[0], 19);
+        assert_eq!(&bytes[1..20], b"BitTorrent protocol");
+        assert_eq!(bytes[20..28], [0, 0, 0, 0, 0, 0x10, 0, 0x01]);
+        assert_eq!(bytes[28..48], [0xab; 20]);
+        assert_eq!(&bytes[48..], b"-TR3000-000000000000");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(Handshake::decode(&handshake().encode()), Ok(handshake()));
+
+        let plain = Handshake::new([1; 20], PeerId([2; 20]));
+        assert_eq!(Handshake::decode(&plain.encode()), Ok(plain));
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert!(handshake().supports_extensions());
+
+        let plain = Handshake::new([1; 20], PeerId([2; 20]));
+        assert!(!plain.supports_extensions());
+
+        let extended = plain.with_extensions();
+        assert!(extended.supports_extensions());
+        assert_eq!(extended.reserved, [0, 0, 0, 0, 0, 0x10, 0, 0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut bytes = handshake().encode();
+        bytes[1] = b'b';
+        assert_eq!(
+            Handshake::decode(&bytes),
+            Err(HandshakeError::InvalidProtocol)
+        );
+
+        let mut bytes = handshake().encode();
+        bytes[0] = 18;
+        assert_eq!(
+            Handshake::decode(&bytes),
+            Err(HandshakeError::InvalidProtocol)
+        );
+
+        assert_eq!(
+            Handshake::decode(&handshake().encode()[..67]),
+            Err(HandshakeError::InvalidLength { length: 67 })
+        );
+    }
+}
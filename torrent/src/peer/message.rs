@@ -0,0 +1,386 @@
+use super::MessageError;
+
+/// Represents a message sent between peers after the handshake, from BEP 3
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PeerMessage {
+    /// Empty message sent to stop the connection timing out
+    KeepAlive,
+    /// Sender will not respond to requests
+    Choke,
+    /// Sender will respond to requests
+    Unchoke,
+    /// Sender wants to request pieces
+    Interested,
+    /// Sender doesn't want to request pieces
+    NotInterested,
+    /// Sender has finished downloading the piece with this index
+    Have(u32),
+    /// Pieces the sender has, with the most significant bit of the first byte being piece 0
+    Bitfield(Vec<u8>),
+    /// Requests `length` bytes of the piece at `index` starting at offset `begin`
+    Request { index: u32, begin: u32, length: u32 },
+    /// Block of data of the piece at `index` starting at offset `begin`
+    Piece {
+        index: u32,
+        begin: u32,
+        block: Vec<u8>,
+    },
+    /// Cancels an earlier request
+    Cancel { index: u32, begin: u32, length: u32 },
+    /// Message of the extension protocol from BEP 10, where an `id` of 0 is the extension handshake
+    Extended { id: u8, payload: Vec<u8> },
+    /// Message with an id this crate doesn't understand, such as PORT from BEP 5 or those of the fast extension, kept
+    /// so it can be skipped without dropping the connection
+    Unknown { id: u8, payload: Vec<u8> },
+}
+
+/// Reads a big endian `u32` at `offset`, which must be in bounds
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(
+        bytes[offset..offset + 4]
+            .try_into()
+            .expect("slice is 4 bytes"),
+    )
+}
+
+impl PeerMessage {
+    /// Largest length prefix accepted, which is far larger than any legitimate message
+    pub const MAX_LEN: u32 = 1 << 20;
+
+    const CHOKE: u8 = 0;
+    const UNCHOKE: u8 = 1;
+    const INTERESTED: u8 = 2;
+    const NOT_INTERESTED: u8 = 3;
+    const HAVE: u8 = 4;
+    const BITFIELD: u8 = 5;
+    const REQUEST: u8 = 6;
+    const PIECE: u8 = 7;
+    const CANCEL: u8 = 8;
+    const EXTENDED: u8 = 20;
+
+    /// Decodes a message from the start of a buffer, returning it along with the number of bytes consumed.
+    ///
+    /// Returns [`MessageError::Incomplete`] if the buffer doesn't yet hold the entire message
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), MessageError> {
+        if buf.len() < 4 {
+            return Err(MessageError::Incomplete {
+                needed: 4 - buf.len(),
+            });
+        }
+
+        let length = read_u32(buf, 0);
+        if length > Self::MAX_LEN {
+            return Err(MessageError::TooLong { length });
+        }
+
+        let consumed = 4 + length as usize;
+        if buf.len() < consumed {
+            return Err(MessageError::Incomplete {
+                needed: consumed - buf.len(),
+            });
+        }
+        if length == 0 {
+            return Ok((PeerMessage::KeepAlive, consumed));
+        }
+
+        let id = buf[4];
+        let payload = &buf[5..consumed];
+        let invalid = MessageError::InvalidPayload { id };
+
+        let message = match id {
+            Self::CHOKE | Self::UNCHOKE | Self::INTERESTED | Self::NOT_INTERESTED
+                if !payload.is_empty() =>
+            {
+                return Err(invalid)
+            }
+            Self::CHOKE => PeerMessage::Choke,
+            Self::UNCHOKE => PeerMessage::Unchoke,
+            Self::INTERESTED => PeerMessage::Interested,
+            Self::NOT_INTERESTED => PeerMessage::NotInterested,
+            Self::HAVE if payload.len() == 4 => PeerMessage::Have(read_u32(payload, 0)),
+            Self::BITFIELD => PeerMessage::Bitfield(payload.to_vec()),
+            Self::REQUEST if payload.len() == 12 => PeerMessage::Request {
+                index: read_u32(payload, 0),
+                begin: read_u32(payload, 4),
+                length: read_u32(payload, 8),
+            },
+            Self::PIECE if payload.len() >= 8 => PeerMessage::Piece {
+                index: read_u32(payload, 0),
+                begin: read_u32(payload, 4),
+                block: payload[8..].to_vec(),
+            },
+            Self::CANCEL if payload.len() == 12 => PeerMessage::Cancel {
+                index: read_u32(payload, 0),
+                begin: read_u32(payload, 4),
+                length: read_u32(payload, 8),
+            },
+            Self::EXTENDED if !payload.is_empty() => PeerMessage::Extended {
+                id: payload[0],
+                payload: payload[1..].to_vec(),
+            },
+            Self::HAVE | Self::REQUEST | Self::PIECE | Self::CANCEL | Self::EXTENDED => {
+                return Err(invalid)
+            }
+            _ => PeerMessage::Unknown {
+                id,
+                payload: payload.to_vec(),
+            },
+        };
+
+        Ok((message, consumed))
+    }
+
+    /// Encodes the message with its length prefix, as sent on the wire
+    pub fn encode(&self) -> Vec<u8> {
+        let (id, payload): (u8, Vec<u8>) = match self {
+            PeerMessage::KeepAlive => return vec![0; 4],
+            PeerMessage::Choke => (Self::CHOKE, vec![]),
+            PeerMessage::Unchoke => (Self::UNCHOKE, vec![]),
+            PeerMessage::Interested => (Self::INTERESTED, vec![]),
+            PeerMessage::NotInterested => (Self::NOT_INTERESTED, vec![]),
+            PeerMessage::Have(index) => (Self::HAVE, index.to_be_bytes().to_vec()),
+            PeerMessage::Bitfield(bits) => (Self::BITFIELD, bits.clone()),
+            PeerMessage::Request {
+                index,
+                begin,
+                length,
+            } => (
+                Self::REQUEST,
+                [*index, *begin, *length].map(u32::to_be_bytes).concat(),
+            ),
+            PeerMessage::Piece {
+                index,
+                begin,
+                block,
+            } => (
+                Self::PIECE,
+                [&index.to_be_bytes()[..], &begin.to_be_bytes(), block].concat(),
+            ),
+            PeerMessage::Cancel {
+                index,
+                begin,
+                length,
+            } => (
+                Self::CANCEL,
+                [*index, *begin, *length].map(u32::to_be_bytes).concat(),
+            ),
+            PeerMessage::Extended { id, payload } => {
+                (Self::EXTENDED, [&[*id][..], payload].concat())
+            }
+            PeerMessage::Unknown { id, payload } => (*id, payload.clone()),
+        };
+
+        let mut bytes = Vec::with_capacity(5 + payload.len());
+        bytes.extend_from_slice(&(1 + payload.len() as u32).to_be_bytes());
+        bytes.push(id);
+        bytes.extend_from_slice(&payload);
+
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_simple() {
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 0]),
+            Ok((PeerMessage::KeepAlive, 4))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 0]),
+            Ok((PeerMessage::Choke, 5))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 1]),
+            Ok((PeerMessage::Unchoke, 5))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 2]),
+            Ok((PeerMessage::Interested, 5))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 3]),
+            Ok((PeerMessage::NotInterested, 5))
+        );
+    }
+
+    #[test]
+    fn test_decode_payloads() {
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 5, 4, 0, 0, 1, 2]),
+            Ok((PeerMessage::Have(258), 9))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 3, 5, 0b1010_0000, 0xff]),
+            Ok((PeerMessage::Bitfield(vec![0b1010_0000, 0xff]), 7))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 13, 6, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0x40, 0]),
+            Ok((
+                PeerMessage::Request {
+                    index: 1,
+                    begin: 2,
+                    length: 16384
+                },
+                17
+            ))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 12, 7, 0, 0, 0, 1, 0, 0, 0, 2, 0xaa, 0xbb, 0xcc]),
+            Ok((
+                PeerMessage::Piece {
+                    index: 1,
+                    begin: 2,
+                    block: vec![0xaa, 0xbb, 0xcc]
+                },
+                16
+            ))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 13, 8, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0x40, 0]),
+            Ok((
+                PeerMessage::Cancel {
+                    index: 1,
+                    begin: 2,
+                    length: 16384
+                },
+                17
+            ))
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 42]),
+            Ok((
+                PeerMessage::Unknown {
+                    id: 42,
+                    payload: vec![]
+                },
+                5
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_consumed() {
+        // only the first message is decoded
+        let buf = [0, 0, 0, 1, 2, 0, 0, 0, 0];
+        let (message, consumed) = PeerMessage::decode(&buf).unwrap();
+        assert_eq!(message, PeerMessage::Interested);
+        assert_eq!(
+            PeerMessage::decode(&buf[consumed..]),
+            Ok((PeerMessage::KeepAlive, 4))
+        );
+
+        // a PORT message from a DHT client is skipped over rather than ending the stream
+        let buf = [0, 0, 0, 3, 9, 0x1a, 0xe1, 0, 0, 0, 1, 1];
+        let (message, consumed) = PeerMessage::decode(&buf).unwrap();
+        assert_eq!(
+            message,
+            PeerMessage::Unknown {
+                id: 9,
+                payload: vec![0x1a, 0xe1]
+            }
+        );
+        assert_eq!(
+            PeerMessage::decode(&buf[consumed..]),
+            Ok((PeerMessage::Unchoke, 5))
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(PeerMessage::KeepAlive.encode(), [0, 0, 0, 0]);
+        assert_eq!(PeerMessage::Unchoke.encode(), [0, 0, 0, 1, 1]);
+        assert_eq!(PeerMessage::Have(258).encode(), [0, 0, 0, 5, 4, 0, 0, 1, 2]);
+        assert_eq!(
+            PeerMessage::Request {
+                index: 1,
+                begin: 2,
+                length: 16384
+            }
+            .encode(),
+            [0, 0, 0, 13, 6, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0x40, 0]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let messages = [
+            PeerMessage::KeepAlive,
+            PeerMessage::Choke,
+            PeerMessage::Unchoke,
+            PeerMessage::Interested,
+            PeerMessage::NotInterested,
+            PeerMessage::Have(u32::MAX),
+            PeerMessage::Bitfield(vec![0b1000_0001, 0]),
+            PeerMessage::Bitfield(vec![]),
+            PeerMessage::Request {
+                index: 7,
+                begin: 16384,
+                length: 16384,
+            },
+            PeerMessage::Piece {
+                index: 7,
+                begin: 16384,
+                block: vec![1, 2, 3, 4],
+            },
+            PeerMessage::Piece {
+                index: 0,
+                begin: 0,
+                block: vec![],
+            },
+            PeerMessage::Cancel {
+                index: 7,
+                begin: 16384,
+                length: 16384,
+            },
+            PeerMessage::Extended {
+                id: 0,
+                payload: b"de".to_vec(),
+            },
+            PeerMessage::Unknown {
+                id: 13,
+                payload: vec![0, 0, 0, 7],
+            },
+        ];
+
+        for message in messages {
+            let bytes = message.encode();
+            assert_eq!(PeerMessage::decode(&bytes), Ok((message, bytes.len())));
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            PeerMessage::decode(&[0, 0]),
+            Err(MessageError::Incomplete { needed: 2 })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 5, 4, 0]),
+            Err(MessageError::Incomplete { needed: 3 })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0xff, 0xff, 0xff, 0xff]),
+            Err(MessageError::TooLong { length: u32::MAX })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 2, 0, 0]),
+            Err(MessageError::InvalidPayload { id: 0 })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 4, 4, 0, 0, 1]),
+            Err(MessageError::InvalidPayload { id: 4 })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 5, 7, 0, 0, 0, 1]),
+            Err(MessageError::InvalidPayload { id: 7 })
+        );
+        assert_eq!(
+            PeerMessage::decode(&[0, 0, 0, 1, 20]),
+            Err(MessageError::InvalidPayload { id: 20 })
+        );
+    }
+}
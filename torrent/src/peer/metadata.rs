@@ -0,0 +1,330 @@
+use sha1::{Digest, Sha1};
+
+use super::{ExtensionError, MetadataError, PeerMessage};
+use crate::{
+    bencoding::{BEncoding, Dictionary, Item},
+    metainfo::{Info, MetaInfoError},
+};
+
+/// Size of every metadata piece except the last
+pub const METADATA_PIECE_LEN: usize = 16384;
+
+/// Requests a piece of the info dictionary
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MetadataRequest {
+    pub piece: u32,
+}
+
+/// Piece of the info dictionary sent in response to a request
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MetadataPiece {
+    pub piece: u32,
+    /// Size of the entire info dictionary in bytes
+    pub total_size: u32,
+    pub data: Vec<u8>,
+}
+
+/// Represents a message of the metadata extension, from BEP 9
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MetadataMessage {
+    Request(MetadataRequest),
+    Data(MetadataPiece),
+    /// Sender will not send the requested piece
+    Reject(MetadataRequest),
+}
+
+impl MetadataMessage {
+    /// Name the extension is advertised with in the extension handshake
+    pub const NAME: &'static str = "ut_metadata";
+
+    const REQUEST: i64 = 0;
+    const DATA: i64 = 1;
+    const REJECT: i64 = 2;
+
+    /// Decodes the payload of an extended message, where piece data follows the dictionary
+    pub fn decode(payload: &[u8]) -> Result<Self, ExtensionError> {
+        let (dict, data) = BEncoding::decode_with_remainder(payload)?;
+
+        let field = |field: &'static str| {
+            dict.get(field)
+                .ok_or(ExtensionError::MissingField { field })?
+                .as_integer()
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or(ExtensionError::InvalidField { field })
+        };
+
+        let msg_type = dict
+            .get("msg_type")
+            .ok_or(ExtensionError::MissingField { field: "msg_type" })?
+            .as_integer();
+        let piece = field("piece")?;
+
+        match msg_type {
+            Some(Self::REQUEST) => Ok(Self::Request(MetadataRequest { piece })),
+            Some(Self::DATA) => Ok(Self::Data(MetadataPiece {
+                piece,
+                total_size: field("total_size")?,
+                data: data.to_vec(),
+            })),
+            Some(Self::REJECT) => Ok(Self::Reject(MetadataRequest { piece })),
+            _ => Err(ExtensionError::InvalidField { field: "msg_type" }),
+        }
+    }
+
+    /// Encodes the message as the payload of an extended message
+    pub fn encode(&self) -> Vec<u8> {
+        let (msg_type, piece) = match self {
+            Self::Request(request) => (Self::REQUEST, request.piece),
+            Self::Data(piece) => (Self::DATA, piece.piece),
+            Self::Reject(request) => (Self::REJECT, request.piece),
+        };
+
+        let mut dict = Dictionary::new();
+        dict.insert("msg_type".to_owned(), Item::Integer(msg_type));
+        dict.insert("piece".to_owned(), Item::Integer(piece.into()));
+
+        match self {
+            Self::Data(piece) => {
+                dict.insert(
+                    "total_size".to_owned(),
+                    Item::Integer(piece.total_size.into()),
+                );
+
+                let mut bytes = Item::Dictionary(dict).encode();
+                bytes.extend_from_slice(&piece.data);
+                bytes
+            }
+            _ => Item::Dictionary(dict).encode(),
+        }
+    }
+
+    /// Wraps the message in an extended peer message, using the id the receiver advertised for the extension
+    pub fn to_message(&self, id: u8) -> PeerMessage {
+        PeerMessage::Extended {
+            id,
+            payload: self.encode(),
+        }
+    }
+}
+
+/// Collects metadata pieces from peers and verifies the assembled info dictionary
+#[derive(Debug, Clone)]
+pub struct MetadataAssembler {
+    info_hash: [u8; 20],
+    total_size: u32,
+    pieces: Vec<Option<Vec<u8>>>,
+}
+
+impl MetadataAssembler {
+    /// Largest info dictionary accepted, to stop peers making us allocate arbitrary amounts of memory
+    pub const MAX_SIZE: u32 = 1 << 24;
+
+    /// Creates an assembler for metadata of `total_size` bytes, as advertised in an extension handshake
+    pub fn new(info_hash: [u8; 20], total_size: u32) -> Result<Self, MetadataError> {
+        if total_size == 0 || total_size > Self::MAX_SIZE {
+            return Err(MetadataError::InvalidSize { total_size });
+        }
+
+        let count = (total_size as usize).div_ceil(METADATA_PIECE_LEN);
+
+        Ok(Self {
+            info_hash,
+            total_size,
+            pieces: vec![None; count],
+        })
+    }
+
+    /// Returns the size of the assembled metadata in bytes
+    pub fn total_size(&self) -> u32 {
+        self.total_size
+    }
+
+    /// Returns the indices of pieces which still need to be requested
+    pub fn missing(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, piece)| piece.is_none())
+            .map(|(index, _)| index as u32)
+    }
+
+    /// Returns whether every piece has been received
+    pub fn is_complete(&self) -> bool {
+        self.pieces.iter().all(Option::is_some)
+    }
+
+    /// Stores a received piece, failing if it doesn't belong to this metadata
+    pub fn add(&mut self, piece: MetadataPiece) -> Result<(), MetadataError> {
+        let index = piece.piece as usize;
+        let invalid = MetadataError::InvalidPiece { piece: piece.piece };
+
+        if piece.total_size != self.total_size || index >= self.pieces.len() {
+            return Err(invalid);
+        }
+
+        // every piece is full size except the last
+        let expected_len =
+            (self.total_size as usize - index * METADATA_PIECE_LEN).min(METADATA_PIECE_LEN);
+        if piece.data.len() != expected_len {
+            return Err(invalid);
+        }
+
+        self.pieces[index] = Some(piece.data);
+        Ok(())
+    }
+
+    /// Joins the pieces and checks them against the info hash, returning the info dictionary
+    pub fn finish(&self) -> Result<Info, MetadataError> {
+        let bytes = self
+            .pieces
+            .iter()
+            .map(|piece| piece.as_deref().ok_or(MetadataError::Incomplete))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+
+        if <[u8; 20]>::from(Sha1::digest(&bytes)) != self.info_hash {
+            return Err(MetadataError::HashMismatch);
+        }
+
+        let decoded = BEncoding::decode_strict(&bytes).map_err(MetaInfoError::Decode)?;
+        let info = decoded.root().expect("strict decoding produces one item");
+
+        Ok(Info::from_item(info)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{magnet::Magnet, metainfo::MetaInfo};
+
+    #[test]
+    fn test_request() {
+        let message = MetadataMessage::Request(MetadataRequest { piece: 3 });
+
+        assert_eq!(message.encode(), b"d8:msg_typei0e5:piecei3ee");
+        assert_eq!(MetadataMessage::decode(&message.encode()).unwrap(), message);
+    }
+
+    #[test]
+    fn test_data() {
+        let payload = b"d8:msg_typei1e5:piecei0e10:total_sizei4eeabcd";
+        let message = MetadataMessage::decode(payload).unwrap();
+
+        assert_eq!(
+            message,
+            MetadataMessage::Data(MetadataPiece {
+                piece: 0,
+                total_size: 4,
+                data: b"abcd".to_vec()
+            })
+        );
+        assert_eq!(message.encode(), payload);
+    }
+
+    #[test]
+    fn test_reject() {
+        let message = MetadataMessage::decode(b"d8:msg_typei2e5:piecei1ee").unwrap();
+
+        assert_eq!(
+            message,
+            MetadataMessage::Reject(MetadataRequest { piece: 1 })
+        );
+        assert_eq!(message.encode(), b"d8:msg_typei2e5:piecei1ee");
+        assert_eq!(
+            message.to_message(3),
+            PeerMessage::Extended {
+                id: 3,
+                payload: message.encode()
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            MetadataMessage::decode(b"d5:piecei0ee"),
+            Err(ExtensionError::MissingField { field: "msg_type" })
+        ));
+        assert!(matches!(
+            MetadataMessage::decode(b"d8:msg_typei7e5:piecei0ee"),
+            Err(ExtensionError::InvalidField { field: "msg_type" })
+        ));
+        assert!(matches!(
+            MetadataMessage::decode(b"d8:msg_typei1e5:piecei0ee"),
+            Err(ExtensionError::MissingField {
+                field: "total_size"
+            })
+        ));
+        assert!(matches!(
+            MetadataMessage::decode(b"d8:msg_typei0e5:piecei-1ee"),
+            Err(ExtensionError::InvalidField { field: "piece" })
+        ));
+    }
+
+    #[test]
+    fn test_assemble() {
+        let data = std::fs::read("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        let metainfo = MetaInfo::from_bytes(&data).unwrap();
+        let magnet = Magnet::parse(&metainfo.magnet_link()).unwrap();
+
+        let (torrent, _) = BEncoding::decode_with_remainder(&data).unwrap();
+        let info = torrent.get("info").unwrap().encode();
+        let total_size = info.len() as u32;
+
+        let mut assembler = MetadataAssembler::new(magnet.info_hash, total_size).unwrap();
+        assert_eq!(assembler.missing().count(), 2);
+
+        for (index, chunk) in info.chunks(METADATA_PIECE_LEN).enumerate().rev() {
+            assert!(matches!(assembler.finish(), Err(MetadataError::Incomplete)));
+
+            assembler
+                .add(MetadataPiece {
+                    piece: index as u32,
+                    total_size,
+                    data: chunk.to_vec(),
+                })
+                .unwrap();
+        }
+
+        assert!(assembler.is_complete());
+        assert_eq!(assembler.finish().unwrap(), metainfo.info);
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(matches!(
+            MetadataAssembler::new([0; 20], 0),
+            Err(MetadataError::InvalidSize { total_size: 0 })
+        ));
+
+        let mut assembler = MetadataAssembler::new([0; 20], 20000).unwrap();
+        let piece = |piece, total_size, len| MetadataPiece {
+            piece,
+            total_size,
+            data: vec![0; len],
+        };
+
+        assert!(matches!(
+            assembler.add(piece(2, 20000, 0)),
+            Err(MetadataError::InvalidPiece { piece: 2 })
+        ));
+        assert!(matches!(
+            assembler.add(piece(0, 30000, METADATA_PIECE_LEN)),
+            Err(MetadataError::InvalidPiece { piece: 0 })
+        ));
+        assert!(matches!(
+            assembler.add(piece(1, 20000, 10)),
+            Err(MetadataError::InvalidPiece { piece: 1 })
+        ));
+
+        assembler.add(piece(0, 20000, METADATA_PIECE_LEN)).unwrap();
+        assembler
+            .add(piece(1, 20000, 20000 - METADATA_PIECE_LEN))
+            .unwrap();
+        assert!(matches!(
+            assembler.finish(),
+            Err(MetadataError::HashMismatch)
+        ));
+    }
+}
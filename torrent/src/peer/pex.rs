@@ -0,0 +1,151 @@
+use std::net::SocketAddr;
+
+use super::ExtensionError;
+use crate::{
+    bencoding::{BEncoding, Item},
+    metainfo::Info,
+    tracker::{parse_compact_v4, parse_compact_v6, Peer},
+};
+
+/// Represents a peer exchange message listing peers connected to and disconnected from since the last message, from BEP 11.
+///
+/// IPv4 and IPv6 peers are combined, with IPv4 peers first
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PexMessage {
+    pub added: Vec<SocketAddr>,
+    /// Flags for each added peer, in the same order
+    pub added_flags: Vec<u8>,
+    pub dropped: Vec<SocketAddr>,
+}
+
+impl PexMessage {
+    /// Name the extension is advertised with in the extension handshake
+    pub const NAME: &'static str = "ut_pex";
+
+    /// Flag set if the peer prefers encrypted connections
+    pub const PREFERS_ENCRYPTION: u8 = 0x01;
+    /// Flag set if the peer is a seed
+    pub const SEED: u8 = 0x02;
+    /// Flag set if the peer supports uTP
+    pub const SUPPORTS_UTP: u8 = 0x04;
+    /// Flag set if the peer supports holepunching
+    pub const SUPPORTS_HOLEPUNCH: u8 = 0x08;
+    /// Flag set if the peer is reachable without holepunching
+    pub const REACHABLE: u8 = 0x10;
+
+    /// Returns whether peer exchange may be used for a torrent, which is forbidden for private torrents
+    pub fn is_allowed(info: &Info) -> bool {
+        !info.is_private()
+    }
+
+    /// Decodes the payload of an extended message, where missing keys are treated as empty
+    pub fn decode(payload: &[u8]) -> Result<Self, ExtensionError> {
+        let decoded = BEncoding::decode_strict(payload)?;
+        let root = decoded.root().expect("strict decoding produces one item");
+        if root.as_dict().is_none() {
+            return Err(ExtensionError::InvalidField { field: "root" });
+        }
+
+        let added = compact_peers(root, "added", parse_compact_v4)?;
+        let added6 = compact_peers(root, "added6", parse_compact_v6)?;
+
+        let mut added_flags = flags(root, "added.f", added.len())?;
+        added_flags.extend(flags(root, "added6.f", added6.len())?);
+
+        let mut dropped = compact_peers(root, "dropped", parse_compact_v4)?;
+        dropped.extend(compact_peers(root, "dropped6", parse_compact_v6)?);
+
+        Ok(Self {
+            added: added.into_iter().chain(added6).collect(),
+            added_flags,
+            dropped,
+        })
+    }
+}
+
+/// Extracts a list of compact peers, which is empty if the key is absent
+fn compact_peers(
+    dict: &Item,
+    field: &'static str,
+    parse: fn(&[u8]) -> Option<Vec<Peer>>,
+) -> Result<Vec<SocketAddr>, ExtensionError> {
+    match dict.get(field) {
+        Some(Item::ByteArray(bytes)) => Ok(parse(bytes)
+            .ok_or(ExtensionError::InvalidField { field })?
+            .iter()
+            .map(Peer::addr)
+            .collect()),
+        Some(_) => Err(ExtensionError::InvalidField { field }),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Extracts one flag byte per peer, which are all zero if the key is absent
+fn flags(dict: &Item, field: &'static str, count: usize) -> Result<Vec<u8>, ExtensionError> {
+    match dict.get(field) {
+        Some(Item::ByteArray(flags)) if flags.len() == count => Ok(flags.clone()),
+        Some(_) => Err(ExtensionError::InvalidField { field }),
+        None => Ok(vec![0; count]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metainfo::MetaInfo;
+
+    #[test]
+    fn test_decode() {
+        let payload = b"d5:added12:\x7f\x00\x00\x01\x1a\xe1\x0a\x00\x00\x02\x1a\xe27:added.f2:\x02\x126:added618:\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x1a\xe37:dropped6:\xc0\xa8\x01\x01\x00\x50e";
+        let message = PexMessage::decode(payload).unwrap();
+
+        assert_eq!(
+            message.added,
+            [
+                "127.0.0.1:6881".parse::<SocketAddr>().unwrap(),
+                "10.0.0.2:6882".parse().unwrap(),
+                "[::1]:6883".parse().unwrap(),
+            ]
+        );
+        assert_eq!(message.added_flags, [PexMessage::SEED, 0x12, 0]);
+        assert_eq!(
+            message.added_flags[1] & PexMessage::REACHABLE,
+            PexMessage::REACHABLE
+        );
+        assert_eq!(message.dropped, ["192.168.1.1:80".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_decode_empty() {
+        assert_eq!(PexMessage::decode(b"de").unwrap(), PexMessage::default());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            PexMessage::decode(b"d5:added5:\x7f\x00\x00\x01\x1ae"),
+            Err(ExtensionError::InvalidField { field: "added" })
+        ));
+        assert!(matches!(
+            PexMessage::decode(b"d5:added6:\x7f\x00\x00\x01\x1a\xe17:added.f2:\x00\x00e"),
+            Err(ExtensionError::InvalidField { field: "added.f" })
+        ));
+        assert!(matches!(
+            PexMessage::decode(b"d7:droppedi0ee"),
+            Err(ExtensionError::InvalidField { field: "dropped" })
+        ));
+        assert!(matches!(
+            PexMessage::decode(b"le"),
+            Err(ExtensionError::InvalidField { field: "root" })
+        ));
+    }
+
+    #[test]
+    fn test_private() {
+        let private = MetaInfo::from_path("../sample.torrent").unwrap();
+        assert!(!PexMessage::is_allowed(&private.info));
+
+        let public = MetaInfo::from_path("../archlinux-2022.10.01-x86_64.iso.torrent").unwrap();
+        assert!(PexMessage::is_allowed(&public.info));
+    }
+}
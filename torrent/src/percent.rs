@@ -0,0 +1,75 @@
+//! Percent-encoding of URL components, as described in RFC 3986
+
+/// Returns whether a byte can appear in a URL component without being encoded
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Percent-encodes every byte other than unreserved characters
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    encoded
+}
+
+/// Decodes percent-encoded bytes, treating `+` as a space as in query strings.
+///
+/// Returns None if a `%` is not followed by two hex digits
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len());
+
+    let mut bytes = encoded.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'%' => {
+                let digits = [bytes.next()?, bytes.next()?];
+                if !digits.iter().all(u8::is_ascii_hexdigit) {
+                    return None;
+                }
+
+                let digits = std::str::from_utf8(&digits).expect("hex digits are ASCII");
+                decoded.push(u8::from_str_radix(digits, 16).expect("digits were checked"));
+            }
+            b'+' => decoded.push(b' '),
+            _ => decoded.push(byte),
+        }
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(b"sample.txt"), "sample.txt");
+        assert_eq!(encode(b"a b&c=d"), "a%20b%26c%3Dd");
+        assert_eq!(encode(b"udp://x:80"), "udp%3A%2F%2Fx%3A80");
+        assert_eq!(encode(&[0x00, 0xff]), "%00%FF");
+        assert_eq!(encode("é".as_bytes()), "%C3%A9");
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("sample.txt").unwrap(), b"sample.txt");
+        assert_eq!(decode("a%20b+c%3d").unwrap(), b"a b c=");
+        assert_eq!(decode("%C3%A9").unwrap(), "é".as_bytes());
+        assert_eq!(
+            decode(&encode(&[0x00, 0xff, b'+'])).unwrap(),
+            [0x00, 0xff, b'+']
+        );
+
+        assert_eq!(decode("%2"), None);
+        assert_eq!(decode("%zz"), None);
+        assert_eq!(decode("%+1"), None);
+    }
+}
@@ -0,0 +1,268 @@
+mod error;
+
+use std::path::Path;
+
+use crate::bencoding::{BEncoding, BencodeError, Dictionary, Item};
+
+pub use error::ResumeError;
+
+/// Represents the resume data a client saves to continue a torrent later, such as a libtorrent `.fastresume` file
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ResumeData {
+    pub info_hash: [u8; 20],
+    /// Size in bytes and modification time in seconds since the Unix epoch of each file, used to detect changes on disk
+    pub file_sizes: Vec<(u64, i64)>,
+    /// Flags for each piece, with the lowest bit set if the piece has been downloaded
+    pub pieces: Vec<u8>,
+    pub total_uploaded: u64,
+    pub total_downloaded: u64,
+    /// Directory the files are saved in
+    pub save_path: Option<String>,
+}
+
+/// Extracts an optional key which must be a non-negative integer if present, defaulting to 0
+fn optional_counter(item: &Item, field: &'static str) -> Result<u64, ResumeError> {
+    item.get(field)
+        .map(|value| {
+            value
+                .as_integer()
+                .and_then(|value| u64::try_from(value).ok())
+                .ok_or(ResumeError::InvalidField { field })
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Converts the value of a file-sizes key into size and modification time pairs
+fn parse_file_sizes(item: &Item) -> Result<Vec<(u64, i64)>, ResumeError> {
+    const FIELD: &str = "file-sizes";
+
+    item.as_list()
+        .ok_or(ResumeError::InvalidField { field: FIELD })?
+        .iter()
+        .map(|entry| match entry.as_list() {
+            Some([Item::Integer(size), Item::Integer(mtime)]) => Ok((
+                u64::try_from(*size).map_err(|_| ResumeError::InvalidField { field: FIELD })?,
+                *mtime,
+            )),
+            _ => Err(ResumeError::InvalidField { field: FIELD }),
+        })
+        .collect()
+}
+
+impl ResumeData {
+    /// Flag set in [`ResumeData::pieces`] for pieces that have been downloaded
+    pub const HAVE_PIECE: u8 = 0x01;
+
+    /// Decodes resume data, ignoring keys that are not understood
+    pub fn decode(bytes: &[u8]) -> Result<Self, ResumeError> {
+        let decoded = BEncoding::decode_strict(bytes)?;
+        let root = decoded.root().expect("strict decoding produces one item");
+
+        if root.as_dict().is_none() {
+            return Err(ResumeError::InvalidField { field: "root" });
+        }
+
+        let info_hash = root
+            .get("info-hash")
+            .ok_or(ResumeError::MissingField { field: "info-hash" })?
+            .as_bytes()
+            .and_then(|hash| hash.try_into().ok())
+            .ok_or(ResumeError::InvalidField { field: "info-hash" })?;
+
+        let pieces = root
+            .get("pieces")
+            .map(|pieces| {
+                pieces
+                    .as_bytes()
+                    .map(<[u8]>::to_vec)
+                    .ok_or(ResumeError::InvalidField { field: "pieces" })
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            info_hash,
+            file_sizes: root
+                .get("file-sizes")
+                .map(parse_file_sizes)
+                .transpose()?
+                .unwrap_or_default(),
+            pieces,
+            total_uploaded: optional_counter(root, "total_uploaded")?,
+            total_downloaded: optional_counter(root, "total_downloaded")?,
+            save_path: root
+                .get("save_path")
+                .map(|path| {
+                    path.as_str()
+                        .map(str::to_owned)
+                        .ok_or(ResumeError::InvalidField { field: "save_path" })
+                })
+                .transpose()?,
+        })
+    }
+
+    /// Reads and decodes a resume data file
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ResumeError> {
+        let data = std::fs::read(path).map_err(BencodeError::from)?;
+
+        Self::decode(&data)
+    }
+
+    /// Encodes the resume data as canonical BEncode, with the header keys libtorrent expects
+    pub fn encode(&self) -> Vec<u8> {
+        // keys are inserted in sorted order so the output is canonical with the `ordered` feature
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "file-format".to_owned(),
+            Item::from("libtorrent resume file"),
+        );
+        if !self.file_sizes.is_empty() {
+            let file_sizes = self
+                .file_sizes
+                .iter()
+                .map(|&(size, mtime)| {
+                    Item::List(vec![
+                        Item::Integer(size.try_into().unwrap_or(i64::MAX)),
+                        Item::Integer(mtime),
+                    ])
+                })
+                .collect();
+            dict.insert("file-sizes".to_owned(), Item::List(file_sizes));
+        }
+        dict.insert("file-version".to_owned(), Item::Integer(1));
+        dict.insert("info-hash".to_owned(), Item::from(&self.info_hash[..]));
+        if !self.pieces.is_empty() {
+            dict.insert("pieces".to_owned(), Item::from(self.pieces.as_slice()));
+        }
+        if let Some(save_path) = &self.save_path {
+            dict.insert("save_path".to_owned(), Item::from(save_path.as_str()));
+        }
+        dict.insert(
+            "total_downloaded".to_owned(),
+            Item::Integer(self.total_downloaded.try_into().unwrap_or(i64::MAX)),
+        );
+        dict.insert(
+            "total_uploaded".to_owned(),
+            Item::Integer(self.total_uploaded.try_into().unwrap_or(i64::MAX)),
+        );
+
+        Item::Dictionary(dict).encode()
+    }
+
+    /// Returns whether the piece at `index` has been downloaded
+    pub fn has_piece(&self, index: usize) -> bool {
+        self.pieces
+            .get(index)
+            .is_some_and(|flags| flags & Self::HAVE_PIECE != 0)
+    }
+
+    /// Returns the number of pieces that have been downloaded
+    pub fn completed_pieces(&self) -> usize {
+        self.pieces
+            .iter()
+            .filter(|&&flags| flags & Self::HAVE_PIECE != 0)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Resume data for an 8 piece torrent with two files, laid out as libtorrent writes it
+    const FASTRESUME: &[u8] = b"d11:active_timei5321e10:added_timei1665000000e10:allocation6:sparse\
+        11:file-format22:libtorrent resume file12:file-versioni1e\
+        10:file-sizesll\
+        i524288ei1664990000eel\
+        i3670016ei1664990001eee\
+        9:info-hash20:\x12\x34\x56\x78\x9a\xbc\xde\xf0\x12\x34\x56\x78\x9a\xbc\xde\xf0\x12\x34\x56\x78\
+        18:libtorrent-version6:1.2.18\
+        6:pieces8:\x01\x01\x00\x01\x03\x00\x00\x01\
+        9:save_path18:/home/user/Torrent\
+        16:total_downloadedi2621440e14:total_uploadedi1048576ee";
+
+    #[test]
+    fn test_decode() {
+        let resume = ResumeData::decode(FASTRESUME).unwrap();
+
+        assert_eq!(
+            resume.info_hash,
+            *b"\x12\x34\x56\x78\x9a\xbc\xde\xf0\x12\x34\x56\x78\x9a\xbc\xde\xf0\x12\x34\x56\x78"
+        );
+        assert_eq!(
+            resume.file_sizes,
+            [(524288, 1664990000), (3670016, 1664990001)]
+        );
+        assert_eq!(resume.pieces.len(), 8);
+        assert_eq!(resume.completed_pieces(), 5);
+        assert!(resume.has_piece(4));
+        assert!(!resume.has_piece(5));
+        assert!(!resume.has_piece(8));
+        assert_eq!(resume.total_uploaded, 1048576);
+        assert_eq!(resume.total_downloaded, 2621440);
+        assert_eq!(resume.save_path.as_deref(), Some("/home/user/Torrent"));
+    }
+
+    #[test]
+    fn test_encode() {
+        let resume = ResumeData::decode(FASTRESUME).unwrap();
+        let encoded = resume.encode();
+
+        assert!(BEncoding::decode_canonical(&encoded).is_ok());
+        assert_eq!(ResumeData::decode(&encoded).unwrap(), resume);
+        assert_eq!(
+            ResumeData::decode(&encoded).unwrap().pieces,
+            b"\x01\x01\x00\x01\x03\x00\x00\x01"
+        );
+
+        let minimal = ResumeData::decode(b"d9:info-hash20:aaaaaaaaaaaaaaaaaaaae").unwrap();
+        assert_eq!(
+            minimal.encode(),
+            b"d11:file-format22:libtorrent resume file12:file-versioni1e9:info-hash20:aaaaaaaaaaaaaaaaaaaa16:total_downloadedi0e14:total_uploadedi0ee"
+        );
+    }
+
+    #[test]
+    fn test_defaults() {
+        let resume = ResumeData::decode(b"d9:info-hash20:aaaaaaaaaaaaaaaaaaaae").unwrap();
+
+        assert_eq!(resume.info_hash, [b'a'; 20]);
+        assert!(resume.file_sizes.is_empty());
+        assert_eq!(resume.completed_pieces(), 0);
+        assert_eq!(resume.total_uploaded, 0);
+        assert_eq!(resume.save_path, None);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            ResumeData::decode(b"de"),
+            Err(ResumeError::MissingField { field: "info-hash" })
+        ));
+        assert!(matches!(
+            ResumeData::decode(b"d9:info-hash3:abce"),
+            Err(ResumeError::InvalidField { field: "info-hash" })
+        ));
+        assert!(matches!(
+            ResumeData::decode(b"d10:file-sizesli1ee9:info-hash20:aaaaaaaaaaaaaaaaaaaae"),
+            Err(ResumeError::InvalidField {
+                field: "file-sizes"
+            })
+        ));
+        assert!(matches!(
+            ResumeData::decode(b"d9:info-hash20:aaaaaaaaaaaaaaaaaaaa14:total_uploadedi-1ee"),
+            Err(ResumeError::InvalidField {
+                field: "total_uploaded"
+            })
+        ));
+        assert!(matches!(
+            ResumeData::decode(b"le"),
+            Err(ResumeError::InvalidField { field: "root" })
+        ));
+        assert!(matches!(
+            ResumeData::decode(b"d9:info-hash"),
+            Err(ResumeError::Decode(_))
+        ));
+    }
+}
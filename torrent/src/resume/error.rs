@@ -0,0 +1,70 @@
+use std::fmt::Display;
+
+use crate::bencoding::BencodeError;
+
+/// Represents an error encountered while reading resume data, with `field` being the offending key
+#[derive(Debug)]
+pub enum ResumeError {
+    /// Resume data was not valid BEncode
+    Decode(BencodeError),
+    /// Required key was absent
+    MissingField { field: &'static str },
+    /// Key was present but had the wrong type or an invalid value
+    InvalidField { field: &'static str },
+}
+
+impl Display for ResumeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode resume data: {err}"),
+            Self::MissingField { field } => write!(f, "missing field {field:?}"),
+            Self::InvalidField { field } => write!(f, "field {field:?} has an invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for ResumeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BencodeError> for ResumeError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (
+                ResumeError::Decode(BencodeError::InvalidItem { offset: 0 }),
+                "failed to decode resume data: invalid item at byte 0",
+            ),
+            (
+                ResumeError::MissingField { field: "info-hash" },
+                "missing field \"info-hash\"",
+            ),
+            (
+                ResumeError::InvalidField { field: "pieces" },
+                "field \"pieces\" has an invalid value",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+
+        let err = ResumeError::from(BencodeError::InvalidItem { offset: 0 });
+        assert!(err.source().unwrap().is::<BencodeError>());
+    }
+}
@@ -0,0 +1,530 @@
+mod client;
+mod error;
+mod scrape;
+mod udp;
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+
+use crate::{
+    bencoding::{BEncoding, Item},
+    peer::PeerId,
+    percent,
+};
+
+pub use client::{AnnounceClient, Transport};
+pub use error::TrackerError;
+pub use scrape::{ScrapeResponse, ScrapeStats};
+pub use udp::UdpTracker;
+
+/// Represents the response of an HTTP tracker to an announce request, from BEP 3
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrackerResponse {
+    /// Seconds the client should wait before announcing again
+    pub interval: u32,
+    /// Seconds the client must wait before announcing again
+    pub min_interval: Option<u32>,
+    /// Number of peers with the entire torrent
+    pub complete: Option<u32>,
+    /// Number of peers still downloading the torrent
+    pub incomplete: Option<u32>,
+    /// Peers from both the IPv4 `peers` and IPv6 `peers6` keys, in either compact or dictionary form
+    pub peers: Vec<Peer>,
+    /// Peers from a dictionary peer list given by hostname rather than IP address, which are left unresolved so that
+    /// parsing never blocks on a DNS lookup
+    pub peer_hosts: Vec<PeerHost>,
+    pub warning_message: Option<String>,
+    /// Opaque id the tracker expects to be sent back in later announces
+    pub tracker_id: Option<Vec<u8>>,
+}
+
+/// Event reported to a tracker when announcing
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Event {
+    /// First announce after starting the download
+    Started,
+    /// Download is being stopped
+    Stopped,
+    /// Download has just finished
+    Completed,
+}
+
+impl Event {
+    /// Returns the value of the event query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Event::Started => "started",
+            Event::Stopped => "stopped",
+            Event::Completed => "completed",
+        }
+    }
+}
+
+/// Parameters of an announce request, describing the client and its progress
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnnounceParams {
+    pub peer_id: PeerId,
+    /// Port the client is listening for peer connections on
+    pub port: u16,
+    /// Total bytes uploaded since the started event
+    pub uploaded: u64,
+    /// Total bytes downloaded since the started event
+    pub downloaded: u64,
+    /// Bytes remaining until the download is complete
+    pub left: u64,
+    /// None for regular announces
+    pub event: Option<Event>,
+    /// Id given by the tracker in an earlier response, if any
+    pub tracker_id: Option<Vec<u8>>,
+}
+
+/// Builds the URL of an announce request to a tracker, always asking for compact peer lists
+pub fn announce_url(tracker: &str, info_hash: &[u8; 20], params: &AnnounceParams) -> String {
+    // trackers may already include query parameters, such as a passkey
+    let separator = if tracker.contains('?') { '&' } else { '?' };
+
+    let mut url = format!(
+        "{tracker}{separator}info_hash={}&peer_id={}&port={}&uploaded={}&downloaded={}&left={}&compact=1",
+        percent::encode(info_hash),
+        params.peer_id.as_urlencoded(),
+        params.port,
+        params.uploaded,
+        params.downloaded,
+        params.left,
+    );
+    if let Some(event) = params.event {
+        url.push_str("&event=");
+        url.push_str(event.as_str());
+    }
+    if let Some(tracker_id) = &params.tracker_id {
+        url.push_str("&trackerid=");
+        url.push_str(&percent::encode(tracker_id));
+    }
+
+    url
+}
+
+/// Represents a peer returned by a tracker
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Peer {
+    pub ip: IpAddr,
+    pub port: u16,
+}
+
+impl Peer {
+    /// Length of an IPv4 peer in compact form, from BEP 23
+    pub const COMPACT_V4_LEN: usize = 6;
+    /// Length of an IPv6 peer in compact form, from BEP 7
+    pub const COMPACT_V6_LEN: usize = 18;
+
+    /// Returns the address to connect to the peer on
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.port)
+    }
+}
+
+impl From<SocketAddr> for Peer {
+    fn from(addr: SocketAddr) -> Self {
+        Self {
+            ip: addr.ip(),
+            port: addr.port(),
+        }
+    }
+}
+
+impl From<Peer> for SocketAddr {
+    fn from(peer: Peer) -> Self {
+        peer.addr()
+    }
+}
+
+/// Splits a compact peer list into peers, each being a 4 byte IPv4 address followed by a 2 byte port in network byte order.
+///
+/// Returns None if the length is not a multiple of 6
+pub(crate) fn parse_compact_v4(bytes: &[u8]) -> Option<Vec<Peer>> {
+    if !bytes.len().is_multiple_of(Peer::COMPACT_V4_LEN) {
+        return None;
+    }
+
+    Some(
+        bytes
+            .chunks_exact(Peer::COMPACT_V4_LEN)
+            .map(|chunk| Peer {
+                ip: IpAddr::V4(Ipv4Addr::new(chunk[0], chunk[1], chunk[2], chunk[3])),
+                port: u16::from_be_bytes([chunk[4], chunk[5]]),
+            })
+            .collect(),
+    )
+}
+
+/// Splits a compact IPv6 peer list into peers, each being a 16 byte IPv6 address followed by a 2 byte port in network byte order.
+///
+/// Returns None if the length is not a multiple of 18
+pub(crate) fn parse_compact_v6(bytes: &[u8]) -> Option<Vec<Peer>> {
+    if !bytes.len().is_multiple_of(Peer::COMPACT_V6_LEN) {
+        return None;
+    }
+
+    Some(
+        bytes
+            .chunks_exact(Peer::COMPACT_V6_LEN)
+            .map(|chunk| Peer {
+                ip: IpAddr::V6(Ipv6Addr::from(
+                    <[u8; 16]>::try_from(&chunk[..16]).expect("chunk is 18 bytes"),
+                )),
+                port: u16::from_be_bytes([chunk[16], chunk[17]]),
+            })
+            .collect(),
+    )
+}
+
+/// Peer given by hostname and port
+pub type PeerHost = (String, u16);
+
+/// Converts a non-compact peer list, where each peer is a dictionary with `ip` and `port` keys.
+///
+/// The `ip` may be a hostname, in which case the peer is returned separately with its hostname
+fn parse_dictionary_peers(peers: &[Item]) -> Result<(Vec<Peer>, Vec<PeerHost>), TrackerError> {
+    const INVALID: TrackerError = TrackerError::InvalidField { field: "peers" };
+
+    let mut parsed = Vec::with_capacity(peers.len());
+    let mut hosts = Vec::new();
+    for peer in peers {
+        let host = peer.get("ip").and_then(Item::as_str).ok_or(INVALID)?;
+        let port = peer
+            .get("port")
+            .and_then(Item::as_integer)
+            .and_then(|port| u16::try_from(port).ok())
+            .ok_or(INVALID)?;
+
+        match host.parse() {
+            Ok(ip) => parsed.push(Peer { ip, port }),
+            Err(_) => hosts.push((host.to_owned(), port)),
+        }
+    }
+
+    Ok((parsed, hosts))
+}
+
+/// Looks up an optional count, failing only if it is present and not a valid `u32`
+fn optional_u32(item: &Item, key: &'static str) -> Result<Option<u32>, TrackerError> {
+    item.get(key)
+        .map(|value| {
+            value
+                .as_integer()
+                .and_then(|number| u32::try_from(number).ok())
+                .ok_or(TrackerError::InvalidField { field: key })
+        })
+        .transpose()
+}
+
+/// Looks up an optional string, failing only if it is present with the wrong type
+fn optional_string(item: &Item, key: &'static str) -> Result<Option<String>, TrackerError> {
+    item.get(key)
+        .map(|value| {
+            value
+                .as_str()
+                .map(str::to_owned)
+                .ok_or(TrackerError::InvalidField { field: key })
+        })
+        .transpose()
+}
+
+impl TrackerResponse {
+    /// Parses the body of an announce response, returning [`TrackerError::Failure`] if the tracker refused the request
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TrackerError> {
+        let decoded = BEncoding::decode_strict(bytes)?;
+
+        Self::from_item(decoded.root().expect("strict decoding produces one item"))
+    }
+
+    /// Resolves each peer given by hostname, moving those that resolve into `peers` and returning those that don't.
+    ///
+    /// This blocks on DNS lookups, so it is kept separate from parsing
+    pub fn resolve_peer_hosts(&mut self) -> Vec<PeerHost> {
+        let mut unresolved = Vec::new();
+        for (host, port) in self.peer_hosts.drain(..) {
+            let addr = (host.as_str(), port)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next());
+
+            match addr {
+                Some(addr) => self.peers.push(Peer::from(addr)),
+                None => unresolved.push((host, port)),
+            }
+        }
+
+        unresolved
+    }
+
+    /// Extracts the response fields from a decoded item
+    pub fn from_item(item: &Item) -> Result<Self, TrackerError> {
+        if item.as_dict().is_none() {
+            return Err(TrackerError::InvalidField { field: "root" });
+        }
+
+        // other keys are meaningless when the request failed
+        if let Some(reason) = optional_string(item, "failure reason")? {
+            return Err(TrackerError::Failure(reason));
+        }
+
+        let (mut peers, peer_hosts) = match item.get("peers") {
+            Some(Item::ByteArray(bytes)) => (
+                parse_compact_v4(bytes).ok_or(TrackerError::InvalidField { field: "peers" })?,
+                Vec::new(),
+            ),
+            Some(Item::List(peers)) => parse_dictionary_peers(peers)?,
+            Some(_) => return Err(TrackerError::InvalidField { field: "peers" }),
+            None => return Err(TrackerError::MissingField { field: "peers" }),
+        };
+        match item.get("peers6") {
+            Some(Item::ByteArray(bytes)) => peers.extend(
+                parse_compact_v6(bytes).ok_or(TrackerError::InvalidField { field: "peers6" })?,
+            ),
+            Some(_) => return Err(TrackerError::InvalidField { field: "peers6" }),
+            None => {}
+        }
+
+        Ok(Self {
+            interval: optional_u32(item, "interval")?
+                .ok_or(TrackerError::MissingField { field: "interval" })?,
+            min_interval: optional_u32(item, "min interval")?,
+            complete: optional_u32(item, "complete")?,
+            incomplete: optional_u32(item, "incomplete")?,
+            peers,
+            peer_hosts,
+            warning_message: optional_string(item, "warning message")?,
+            tracker_id: match item.get("tracker id") {
+                Some(Item::ByteArray(bytes)) => Some(bytes.to_vec()),
+                Some(_) => {
+                    return Err(TrackerError::InvalidField {
+                        field: "tracker id",
+                    })
+                }
+                None => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Response captured from an opentracker instance, with two compact peers
+    const RESPONSE: &[u8] = b"d8:completei4e10:downloadedi12e10:incompletei1e8:intervali1800e12:min intervali900e5:peers12:\xc0\xa8\x01\x02\x1a\xe1\x0a\x00\x00\x01\xc8\xd5e";
+
+    #[test]
+    fn test_from_bytes() {
+        let response = TrackerResponse::from_bytes(RESPONSE).unwrap();
+
+        assert_eq!(
+            response,
+            TrackerResponse {
+                interval: 1800,
+                min_interval: Some(900),
+                complete: Some(4),
+                incomplete: Some(1),
+                peers: vec![
+                    Peer {
+                        ip: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)),
+                        port: 6881,
+                    },
+                    Peer {
+                        ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                        port: 51413,
+                    },
+                ],
+                peer_hosts: Vec::new(),
+                warning_message: None,
+                tracker_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_compact_peers() {
+        let peers = parse_compact_v4(b"\x7f\x00\x00\x01\x00\x50\x01\x02\x03\x04\xff\xff").unwrap();
+        assert_eq!(
+            peers.iter().map(Peer::addr).collect::<Vec<_>>(),
+            [
+                "127.0.0.1:80".parse::<SocketAddr>().unwrap(),
+                "1.2.3.4:65535".parse().unwrap()
+            ]
+        );
+
+        assert_eq!(parse_compact_v4(b""), Some(vec![]));
+        assert_eq!(parse_compact_v4(b"\x7f\x00\x00\x01\x00"), None);
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers5:\x7f\x00\x00\x01\x00e"),
+            Err(TrackerError::InvalidField { field: "peers" })
+        ));
+    }
+
+    #[test]
+    fn test_compact_peers6() {
+        let response = TrackerResponse::from_bytes(
+            b"d8:intervali60e5:peers6:\x7f\x00\x00\x01\x00\x506:peers618:\x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x1a\xe1e",
+        )
+        .unwrap();
+
+        assert_eq!(
+            response
+                .peers
+                .into_iter()
+                .map(SocketAddr::from)
+                .collect::<Vec<_>>(),
+            [
+                "127.0.0.1:80".parse::<SocketAddr>().unwrap(),
+                "[2001:db8::1]:6881".parse().unwrap()
+            ]
+        );
+
+        assert_eq!(parse_compact_v6(&[0; 17]), None);
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:6:peers61:\x00e"),
+            Err(TrackerError::InvalidField { field: "peers6" })
+        ));
+    }
+
+    #[test]
+    fn test_dictionary_peers() {
+        let response = TrackerResponse::from_bytes(
+            b"d8:intervali60e5:peersld2:ip9:127.0.0.17:peer id20:aaaaaaaaaaaaaaaaaaaa4:porti80eed2:ip11:2001:db8::14:porti6881eed2:ip9:localhost7:peer id20:bbbbbbbbbbbbbbbbbbbb4:porti1eeee",
+        )
+        .unwrap();
+
+        let addrs: Vec<_> = response.peers.iter().map(Peer::addr).collect();
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(addrs[0], "127.0.0.1:80".parse().unwrap());
+        assert_eq!(addrs[1], "[2001:db8::1]:6881".parse().unwrap());
+        assert_eq!(response.peer_hosts, [("localhost".to_owned(), 1)]);
+
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peersld2:ip9:127.0.0.1eee"),
+            Err(TrackerError::InvalidField { field: "peers" })
+        ));
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peersld2:ip9:127.0.0.14:porti65536eeee"),
+            Err(TrackerError::InvalidField { field: "peers" })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_peer_hosts() {
+        let mut response = TrackerResponse::from_bytes(
+            b"d8:intervali60e5:peersld2:ip9:localhost4:porti1eed2:ip12:peer.invalid4:porti2eeee",
+        )
+        .unwrap();
+
+        // hostnames are kept rather than resolved or dropped while parsing
+        assert!(response.peers.is_empty());
+        assert_eq!(response.peer_hosts.len(), 2);
+
+        let unresolved = response.resolve_peer_hosts();
+        assert_eq!(unresolved, [("peer.invalid".to_owned(), 2)]);
+        assert!(response.peer_hosts.is_empty());
+        assert_eq!(response.peers.len(), 1);
+        assert!(response.peers[0].ip.is_loopback());
+        assert_eq!(response.peers[0].port, 1);
+    }
+
+    /// Builds parameters with a peer id that needs encoding
+    fn params(event: Option<Event>) -> AnnounceParams {
+        AnnounceParams {
+            peer_id: PeerId(*b"-TR3000-a b~c.d_e-f\xff"),
+            port: 6881,
+            uploaded: 0,
+            downloaded: 10,
+            left: 20,
+            event,
+            tracker_id: None,
+        }
+    }
+
+    #[test]
+    fn test_announce_url() {
+        let mut info_hash = [0x12; 20];
+        info_hash[0] = 0x00;
+        info_hash[19] = 0xff;
+        info_hash[10] = b'A';
+
+        assert_eq!(
+            announce_url("http://tracker/announce", &info_hash, &params(Some(Event::Started))),
+            "http://tracker/announce?info_hash=%00%12%12%12%12%12%12%12%12%12A%12%12%12%12%12%12%12%12%FF\
+            &peer_id=-TR3000-a%20b~c.d_e-f%FF&port=6881&uploaded=0&downloaded=10&left=20&compact=1&event=started"
+        );
+        assert!(
+            announce_url("http://tracker/announce?key=1", &info_hash, &params(None))
+                .starts_with("http://tracker/announce?key=1&info_hash=%00")
+        );
+        assert!(
+            announce_url("http://tracker/announce", &info_hash, &params(None))
+                .ends_with("&compact=1")
+        );
+    }
+
+    #[test]
+    fn test_warning() {
+        let response =
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:15:warning message4:slowe")
+                .unwrap();
+
+        assert_eq!(response.warning_message.as_deref(), Some("slow"));
+        assert_eq!(response.complete, None);
+    }
+
+    #[test]
+    fn test_tracker_id() {
+        let response =
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:10:tracker id4:\x01\xffabe")
+                .unwrap();
+        assert_eq!(response.tracker_id.as_deref(), Some(&b"\x01\xffab"[..]));
+
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:10:tracker idi1ee"),
+            Err(TrackerError::InvalidField {
+                field: "tracker id"
+            })
+        ));
+
+        let params = AnnounceParams {
+            tracker_id: Some(b"\x01\xffab".to_vec()),
+            ..params(None)
+        };
+        assert!(announce_url("http://tracker/announce", &[0; 20], &params)
+            .ends_with("&compact=1&trackerid=%01%FFab"));
+    }
+
+    #[test]
+    fn test_failure() {
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d14:failure reason17:torrent not founde"),
+            Err(TrackerError::Failure(reason)) if reason == "torrent not found"
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d5:peers0:e"),
+            Err(TrackerError::MissingField { field: "interval" })
+        ));
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali-1e5:peers0:e"),
+            Err(TrackerError::InvalidField { field: "interval" })
+        ));
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:intervali60ee"),
+            Err(TrackerError::MissingField { field: "peers" })
+        ));
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"le"),
+            Err(TrackerError::InvalidField { field: "root" })
+        ));
+        assert!(matches!(
+            TrackerResponse::from_bytes(b"d8:interval"),
+            Err(TrackerError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::metainfo::MetaInfo;
+
+use super::{AnnounceParams, TrackerError, TrackerResponse};
+
+/// Sends a single announce request to the tracker at a URL.
+///
+/// Implemented for closures, so that HTTP and UDP clients can be plugged in alongside mocks
+pub trait Transport {
+    fn announce(
+        &mut self,
+        tracker: &str,
+        info_hash: &[u8; 20],
+        params: &AnnounceParams,
+    ) -> Result<TrackerResponse, TrackerError>;
+}
+
+impl<F> Transport for F
+where
+    F: FnMut(&str, &[u8; 20], &AnnounceParams) -> Result<TrackerResponse, TrackerError>,
+{
+    fn announce(
+        &mut self,
+        tracker: &str,
+        info_hash: &[u8; 20],
+        params: &AnnounceParams,
+    ) -> Result<TrackerResponse, TrackerError> {
+        self(tracker, info_hash, params)
+    }
+}
+
+/// Successful announce, which limits how soon the next regular announce may be sent
+#[derive(Debug, Clone, Copy)]
+struct LastAnnounce {
+    received: Instant,
+    interval: Duration,
+    min_interval: Option<Duration>,
+}
+
+/// Announces a torrent to its trackers tier by tier, from BEP 12, retrying each tracker with exponential backoff
+#[derive(Debug)]
+pub struct AnnounceClient<T> {
+    transport: T,
+    info_hash: [u8; 20],
+    tiers: Vec<Vec<String>>,
+    base_delay: Duration,
+    max_attempts: u32,
+    last_announce: Option<LastAnnounce>,
+    /// Id from the last response of each tracker that included one, keyed by announce URL, which is only ever sent
+    /// back to the tracker that issued it
+    tracker_ids: HashMap<String, Vec<u8>>,
+}
+
+impl<T: Transport> AnnounceClient<T> {
+    /// Delay before the first retry unless another is set, doubling for each retry after
+    pub const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+    /// Attempts made at each tracker unless another number is set
+    pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+    /// Creates a client for the trackers of `meta`, shuffling each tier as BEP 12 requires
+    pub fn new(meta: &MetaInfo, transport: T) -> Self {
+        Self::with_tiers(
+            meta.info_hash(),
+            meta.shuffled_trackers(&mut rand::thread_rng()),
+            transport,
+        )
+    }
+
+    /// Creates a client for tiers of tracker URLs, which are tried in the order given
+    pub fn with_tiers(info_hash: [u8; 20], tiers: Vec<Vec<String>>, transport: T) -> Self {
+        Self {
+            transport,
+            info_hash,
+            tiers,
+            base_delay: Self::DEFAULT_BASE_DELAY,
+            max_attempts: Self::DEFAULT_MAX_ATTEMPTS,
+            last_announce: None,
+            tracker_ids: HashMap::new(),
+        }
+    }
+
+    /// Sets the delay before retrying a tracker for the first time
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Sets the number of attempts made at each tracker before moving to the next, which is at least one
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Returns the tiers of trackers, with the trackers that last succeeded at the front of their tier
+    pub fn tiers(&self) -> &[Vec<String>] {
+        &self.tiers
+    }
+
+    /// Returns the id the tracker at `tracker` asked to be sent back, if any
+    pub fn tracker_id(&self, tracker: &str) -> Option<&[u8]> {
+        self.tracker_ids.get(tracker).map(Vec::as_slice)
+    }
+
+    /// Returns when the next regular announce is due, according to the interval of the last successful announce
+    pub fn next_announce(&self) -> Option<Instant> {
+        self.last_announce.map(|last| last.received + last.interval)
+    }
+
+    /// Returns the earliest time a regular announce may be sent, according to the minimum interval of the last
+    /// successful announce or its interval if there was none
+    pub fn min_next_announce(&self) -> Option<Instant> {
+        self.last_announce
+            .map(|last| last.received + last.min_interval.unwrap_or(last.interval))
+    }
+
+    /// Announces to each tracker in turn until one succeeds, which is then moved to the front of its tier.
+    ///
+    /// Regular announces fail with [`TrackerError::TooSoon`] without being sent until
+    /// [`min_next_announce`](Self::min_next_announce) has passed, while announces reporting an event are always sent.
+    ///
+    /// Each tracker is sent the id from its own earlier response, unless `params` already has one.
+    ///
+    /// Returns [`TrackerError::AllFailed`] with the last error from each tracker if none succeed
+    pub fn announce(&mut self, params: &AnnounceParams) -> Result<TrackerResponse, TrackerError> {
+        if params.event.is_none() {
+            if let Some(retry_at) = self.min_next_announce() {
+                if retry_at > Instant::now() {
+                    return Err(TrackerError::TooSoon { retry_at });
+                }
+            }
+        }
+
+        let mut failures = Vec::new();
+        for tier in 0..self.tiers.len() {
+            for index in 0..self.tiers[tier].len() {
+                let tracker = self.tiers[tier][index].clone();
+
+                match self.announce_with_retries(&tracker, params) {
+                    Ok(response) => {
+                        MetaInfo::promote_tracker(&mut self.tiers, &tracker);
+                        self.last_announce = Some(LastAnnounce {
+                            received: Instant::now(),
+                            interval: Duration::from_secs(response.interval.into()),
+                            min_interval: response
+                                .min_interval
+                                .map(|secs| Duration::from_secs(secs.into())),
+                        });
+                        if let Some(tracker_id) = &response.tracker_id {
+                            self.tracker_ids.insert(tracker, tracker_id.clone());
+                        }
+
+                        return Ok(response);
+                    }
+                    Err(err) => failures.push((tracker, err)),
+                }
+            }
+        }
+
+        Err(TrackerError::AllFailed(failures))
+    }
+
+    /// Announces to a single tracker with the id it issued, doubling the delay after each failure until the attempts
+    /// run out
+    fn announce_with_retries(
+        &mut self,
+        tracker: &str,
+        params: &AnnounceParams,
+    ) -> Result<TrackerResponse, TrackerError> {
+        let params = match (&params.tracker_id, self.tracker_ids.get(tracker)) {
+            (None, Some(tracker_id)) => Cow::Owned(AnnounceParams {
+                tracker_id: Some(tracker_id.clone()),
+                ..params.clone()
+            }),
+            _ => Cow::Borrowed(params),
+        };
+
+        let mut delay = self.base_delay;
+        let mut attempt = 1;
+        loop {
+            match self.transport.announce(tracker, &self.info_hash, &params) {
+                Ok(response) => return Ok(response),
+                // the tracker answered, so asking again will not change its mind
+                Err(err @ TrackerError::Failure(_)) => return Err(err),
+                Err(err) if attempt >= self.max_attempts => return Err(err),
+                Err(_) => {
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{peer::PeerId, tracker::Event};
+
+    fn params() -> AnnounceParams {
+        AnnounceParams {
+            peer_id: PeerId(*b"-TR3000-aaaaaaaaaaaa"),
+            port: 6881,
+            uploaded: 0,
+            downloaded: 0,
+            left: 100,
+            event: Some(Event::Started),
+            tracker_id: None,
+        }
+    }
+
+    fn timed_out() -> TrackerError {
+        std::io::Error::from(std::io::ErrorKind::TimedOut).into()
+    }
+
+    #[test]
+    fn test_retry() {
+        let mut calls = 0;
+        let tracker = |url: &str, _: &[u8; 20], _: &AnnounceParams| {
+            assert_eq!(url, "http://tracker/announce");
+            calls += 1;
+            if calls <= 2 {
+                Err(timed_out())
+            } else {
+                TrackerResponse::from_bytes(b"d8:intervali1800e12:min intervali900e5:peers0:e")
+            }
+        };
+
+        let meta = MetaInfo::from_bytes(
+            b"d8:announce23:http://tracker/announce4:infod6:lengthi1e4:name1:a12:piece lengthi16e6:pieces0:ee",
+        )
+        .unwrap();
+        let mut client = AnnounceClient::new(&meta, tracker).base_delay(Duration::from_millis(1));
+
+        assert_eq!(client.next_announce(), None);
+        let response = client.announce(&params()).unwrap();
+        assert_eq!(response.interval, 1800);
+
+        let next = client.next_announce().unwrap();
+        assert!(next > Instant::now() + Duration::from_secs(1790));
+    }
+
+    #[test]
+    fn test_too_soon() {
+        let mut calls = 0;
+        let tracker = |_: &str, _: &[u8; 20], _: &AnnounceParams| {
+            calls += 1;
+            TrackerResponse::from_bytes(b"d8:intervali1800e12:min intervali900e5:peers0:e")
+        };
+
+        let tiers = vec![vec!["http://tracker/announce".to_owned()]];
+        let mut client = AnnounceClient::with_tiers([0; 20], tiers, tracker);
+
+        assert_eq!(client.min_next_announce(), None);
+        client.announce(&params()).unwrap();
+
+        let earliest = client.min_next_announce().unwrap();
+        assert!(earliest + Duration::from_secs(900) <= client.next_announce().unwrap());
+
+        // a regular announce inside the minimum interval isn't sent
+        let regular = AnnounceParams {
+            event: None,
+            ..params()
+        };
+        assert!(matches!(
+            client.announce(&regular),
+            Err(TrackerError::TooSoon { retry_at }) if retry_at == earliest
+        ));
+
+        // events are still reported straight away
+        client
+            .announce(&AnnounceParams {
+                event: Some(Event::Stopped),
+                ..params()
+            })
+            .unwrap();
+        drop(client);
+
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_tiers() {
+        let mut attempts = Vec::new();
+        let tracker = |url: &str, _: &[u8; 20], _: &AnnounceParams| {
+            attempts.push(url.to_owned());
+            match url {
+                "c" => TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:e"),
+                "b" => Err(TrackerError::Failure("unregistered torrent".to_owned())),
+                _ => Err(timed_out()),
+            }
+        };
+
+        let tiers = vec![
+            vec!["a".to_owned(), "b".to_owned()],
+            vec!["d".to_owned(), "c".to_owned()],
+        ];
+        let mut client = AnnounceClient::with_tiers([0; 20], tiers, tracker)
+            .base_delay(Duration::ZERO)
+            .max_attempts(2);
+
+        assert!(client.announce(&params()).is_ok());
+        assert_eq!(client.tiers()[1], ["c", "d"]);
+        drop(client);
+
+        // failures from the tracker itself are not retried
+        assert_eq!(attempts, ["a", "a", "b", "d", "d", "c"]);
+    }
+
+    #[test]
+    fn test_all_failed() {
+        let tracker = |url: &str, _: &[u8; 20], _: &AnnounceParams| match url {
+            "a" => Err(timed_out()),
+            _ => Err(TrackerError::Failure("unregistered torrent".to_owned())),
+        };
+
+        let tiers = vec![vec!["a".to_owned()], vec!["b".to_owned()]];
+        let mut client =
+            AnnounceClient::with_tiers([0; 20], tiers, tracker).base_delay(Duration::ZERO);
+
+        let Err(TrackerError::AllFailed(failures)) = client.announce(&params()) else {
+            panic!("announce should fail");
+        };
+        assert_eq!(failures.len(), 2);
+        assert!(matches!(&failures[0], (url, TrackerError::Io(_)) if url == "a"));
+        assert!(matches!(&failures[1], (url, TrackerError::Failure(_)) if url == "b"));
+    }
+
+    #[test]
+    fn test_tracker_id() {
+        let mut urls = Vec::new();
+        let tracker = |url: &str, info_hash: &[u8; 20], params: &AnnounceParams| {
+            urls.push(crate::tracker::announce_url(url, info_hash, params));
+            TrackerResponse::from_bytes(b"d8:intervali60e5:peers0:10:tracker id3:\x00abe")
+        };
+
+        let tiers = vec![vec!["http://tracker/announce".to_owned()]];
+        let mut client = AnnounceClient::with_tiers([0; 20], tiers, tracker);
+
+        assert_eq!(client.tracker_id("http://tracker/announce"), None);
+        client.announce(&params()).unwrap();
+        assert_eq!(
+            client.tracker_id("http://tracker/announce"),
+            Some(&b"\x00ab"[..])
+        );
+        client
+            .announce(&AnnounceParams {
+                event: Some(Event::Completed),
+                ..params()
+            })
+            .unwrap();
+        drop(client);
+
+        assert!(!urls[0].contains("trackerid"));
+        assert!(urls[1].ends_with("&event=completed&trackerid=%00ab"));
+    }
+
+    #[test]
+    fn test_tracker_id_per_tracker() {
+        let mut sent = Vec::new();
+        let mut a_up = true;
+        let tracker = |url: &str, _: &[u8; 20], params: &AnnounceParams| {
+            sent.push((url.to_owned(), params.tracker_id.clone()));
+            match url {
+                "a" if a_up => {
+                    a_up = false;
+                    TrackerResponse::from_bytes(b"d8:intervali0e5:peers0:10:tracker id1:Ae")
+                }
+                "a" => Err(timed_out()),
+                _ => TrackerResponse::from_bytes(b"d8:intervali0e5:peers0:e"),
+            }
+        };
+
+        let tiers = vec![vec!["a".to_owned()], vec!["b".to_owned()]];
+        let mut client = AnnounceClient::with_tiers([0; 20], tiers, tracker)
+            .base_delay(Duration::ZERO)
+            .max_attempts(2);
+
+        // a fails after handing out an id, so the second and third announces fail over to b
+        for _ in 0..3 {
+            client.announce(&params()).unwrap();
+        }
+        assert_eq!(client.tracker_id("a"), Some(&b"A"[..]));
+        assert_eq!(client.tracker_id("b"), None);
+        drop(client);
+
+        assert_eq!(
+            sent,
+            [
+                ("a".to_owned(), None),
+                ("a".to_owned(), Some(b"A".to_vec())),
+                ("a".to_owned(), Some(b"A".to_vec())),
+                ("b".to_owned(), None),
+                ("a".to_owned(), Some(b"A".to_vec())),
+                ("a".to_owned(), Some(b"A".to_vec())),
+                ("b".to_owned(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::{fmt::Display, time::Instant};
+
+use crate::bencoding::BencodeError;
+
+/// Represents an error encountered while communicating with a tracker
+#[derive(Debug)]
+pub enum TrackerError {
+    /// Response was not valid BEncode
+    Decode(BencodeError),
+    /// Tracker refused the request, giving a human readable reason
+    Failure(String),
+    /// Required key was absent from the response
+    MissingField { field: &'static str },
+    /// Key was present but had the wrong type or an invalid value
+    InvalidField { field: &'static str },
+    /// Binary response from a UDP tracker was malformed
+    InvalidResponse,
+    /// Failed to communicate with the tracker, including timeouts
+    Io(std::io::Error),
+    /// Regular announce was attempted before the minimum interval given by the tracker had passed
+    TooSoon { retry_at: Instant },
+    /// Every tracker failed, giving the URL of each with its last error
+    AllFailed(Vec<(String, TrackerError)>),
+}
+
+impl Display for TrackerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Decode(err) => write!(f, "failed to decode tracker response: {err}"),
+            Self::Failure(reason) => write!(f, "tracker returned failure: {reason}"),
+            Self::MissingField { field } => write!(f, "missing field {field:?}"),
+            Self::InvalidField { field } => write!(f, "field {field:?} has an invalid value"),
+            Self::InvalidResponse => write!(f, "tracker sent a malformed response"),
+            Self::Io(err) => write!(f, "failed to communicate with tracker: {err}"),
+            Self::TooSoon { .. } => write!(f, "announced before the minimum interval passed"),
+            Self::AllFailed(failures) if failures.is_empty() => {
+                write!(f, "no trackers to announce to")
+            }
+            Self::AllFailed(failures) => {
+                write!(f, "every tracker failed")?;
+                for (index, (tracker, err)) in failures.iter().enumerate() {
+                    let separator = if index == 0 { ": " } else { "; " };
+                    write!(f, "{separator}{tracker} ({err})")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decode(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<BencodeError> for TrackerError {
+    fn from(err: BencodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<std::io::Error> for TrackerError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        let cases = [
+            (
+                TrackerError::Decode(BencodeError::InvalidItem { offset: 0 }),
+                "failed to decode tracker response: invalid item at byte 0",
+            ),
+            (
+                TrackerError::Failure("unregistered torrent".to_owned()),
+                "tracker returned failure: unregistered torrent",
+            ),
+            (
+                TrackerError::MissingField { field: "peers" },
+                "missing field \"peers\"",
+            ),
+            (
+                TrackerError::InvalidField { field: "interval" },
+                "field \"interval\" has an invalid value",
+            ),
+            (
+                TrackerError::InvalidResponse,
+                "tracker sent a malformed response",
+            ),
+            (
+                TrackerError::Io(std::io::ErrorKind::TimedOut.into()),
+                "failed to communicate with tracker: timed out",
+            ),
+            (
+                TrackerError::TooSoon {
+                    retry_at: Instant::now(),
+                },
+                "announced before the minimum interval passed",
+            ),
+            (
+                TrackerError::AllFailed(Vec::new()),
+                "no trackers to announce to",
+            ),
+            (
+                TrackerError::AllFailed(vec![
+                    ("udp://a".to_owned(), TrackerError::InvalidResponse),
+                    (
+                        "http://b".to_owned(),
+                        TrackerError::Failure("unregistered torrent".to_owned()),
+                    ),
+                ]),
+                "every tracker failed: udp://a (tracker sent a malformed response); \
+                http://b (tracker returned failure: unregistered torrent)",
+            ),
+        ];
+
+        for (err, message) in cases {
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_source() {
+        let err = TrackerError::from(BencodeError::InvalidItem { offset: 0 });
+        assert!(err.source().unwrap().is::<BencodeError>());
+
+        let err = TrackerError::from(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(err.source().unwrap().is::<std::io::Error>());
+
+        assert!(TrackerError::InvalidResponse.source().is_none());
+    }
+}
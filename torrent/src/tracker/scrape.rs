@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use super::{optional_string, optional_u32, TrackerError};
+use crate::bencoding::{BEncoding, Item};
+
+/// Represents the response of an HTTP tracker to a scrape request, giving statistics for each requested torrent
+#[derive(Debug, PartialEq, Clone)]
+pub struct ScrapeResponse {
+    /// Statistics keyed by info hash
+    pub files: HashMap<[u8; 20], ScrapeStats>,
+}
+
+/// Statistics about the swarm of a single torrent
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ScrapeStats {
+    /// Number of peers with the entire torrent
+    pub complete: u32,
+    /// Number of times the torrent has been downloaded
+    pub downloaded: u32,
+    /// Number of peers still downloading the torrent
+    pub incomplete: u32,
+}
+
+impl ScrapeStats {
+    /// Extracts the statistics from an entry of the files dictionary
+    fn from_item(item: &Item) -> Result<Self, TrackerError> {
+        let count = |key| optional_u32(item, key)?.ok_or(TrackerError::MissingField { field: key });
+
+        Ok(Self {
+            complete: count("complete")?,
+            downloaded: count("downloaded")?,
+            incomplete: count("incomplete")?,
+        })
+    }
+}
+
+impl ScrapeResponse {
+    /// Parses the body of a scrape response, returning [`TrackerError::Failure`] if the tracker refused the request
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TrackerError> {
+        // info hashes are raw bytes, so are rarely valid UTF-8
+        let decoded = BEncoding::decode_raw_keys(bytes)?;
+        let root = decoded.root().expect("decoding produces at least one item");
+
+        if root.as_dict().is_none() {
+            return Err(TrackerError::InvalidField { field: "root" });
+        }
+        if let Some(reason) = optional_string(root, "failure reason")? {
+            return Err(TrackerError::Failure(reason));
+        }
+
+        let entries: Vec<(&[u8], &Item)> = match root.get("files") {
+            Some(Item::RawDictionary(files)) => files
+                .iter()
+                .map(|(hash, stats)| (hash.as_slice(), stats))
+                .collect(),
+            Some(Item::Dictionary(files)) => files
+                .iter()
+                .map(|(hash, stats)| (hash.as_bytes(), stats))
+                .collect(),
+            Some(_) => return Err(TrackerError::InvalidField { field: "files" }),
+            None => return Err(TrackerError::MissingField { field: "files" }),
+        };
+
+        let mut files = HashMap::with_capacity(entries.len());
+        for (hash, stats) in entries {
+            let hash = hash
+                .try_into()
+                .map_err(|_| TrackerError::InvalidField { field: "files" })?;
+
+            files.insert(hash, ScrapeStats::from_item(stats)?);
+        }
+
+        Ok(Self { files })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Reply captured from a tracker scraping two torrents
+    const RESPONSE: &[u8] = b"d5:filesd20:\xd0\xd1\x4c\x92\x6e\x6e\x99\x76\x1a\x2f\xdc\xff\x27\xb4\x03\xd9\x63\x76\xef\xf6d8:completei5e10:downloadedi50e10:incompletei10ee20:aaaaaaaaaaaaaaaaaaaad8:completei0e10:downloadedi1e10:incompletei2eeee";
+
+    #[test]
+    fn test_from_bytes() {
+        let response = ScrapeResponse::from_bytes(RESPONSE).unwrap();
+
+        assert_eq!(response.files.len(), 2);
+        assert_eq!(
+            response.files[b"\xd0\xd1\x4c\x92\x6e\x6e\x99\x76\x1a\x2f\xdc\xff\x27\xb4\x03\xd9\x63\x76\xef\xf6"],
+            ScrapeStats {
+                complete: 5,
+                downloaded: 50,
+                incomplete: 10
+            }
+        );
+        assert_eq!(
+            response.files[b"aaaaaaaaaaaaaaaaaaaa"],
+            ScrapeStats {
+                complete: 0,
+                downloaded: 1,
+                incomplete: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_utf8_hashes() {
+        let response = ScrapeResponse::from_bytes(
+            b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei0e10:downloadedi1e10:incompletei2eeee",
+        )
+        .unwrap();
+
+        assert_eq!(response.files[b"aaaaaaaaaaaaaaaaaaaa"].downloaded, 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            ScrapeResponse::from_bytes(b"d14:failure reason6:bannede"),
+            Err(TrackerError::Failure(reason)) if reason == "banned"
+        ));
+        assert!(matches!(
+            ScrapeResponse::from_bytes(
+                b"d5:filesd3:abcd8:completei0e10:downloadedi1e10:incompletei2eeee"
+            ),
+            Err(TrackerError::InvalidField { field: "files" })
+        ));
+        assert!(matches!(
+            ScrapeResponse::from_bytes(b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei0eeee"),
+            Err(TrackerError::MissingField {
+                field: "downloaded"
+            })
+        ));
+        assert!(matches!(
+            ScrapeResponse::from_bytes(b"de"),
+            Err(TrackerError::MissingField { field: "files" })
+        ));
+    }
+}
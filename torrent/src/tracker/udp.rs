@@ -0,0 +1,365 @@
+use std::{
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use super::{
+    parse_compact_v4, parse_compact_v6, AnnounceParams, Event, TrackerError, TrackerResponse,
+};
+
+/// Magic constant identifying a connect request
+const PROTOCOL_ID: u64 = 0x41727101980;
+
+const ACTION_CONNECT: u32 = 0;
+const ACTION_ANNOUNCE: u32 = 1;
+const ACTION_ERROR: u32 = 3;
+
+/// Largest response accepted, enough for hundreds of peers
+const MAX_RESPONSE_LEN: usize = 8192;
+
+/// Connection id obtained from the tracker, with the time it was received
+#[derive(Debug, Clone, Copy)]
+struct Connection {
+    id: u64,
+    received: Instant,
+}
+
+/// Client for a tracker using the UDP tracker protocol, from BEP 15
+#[derive(Debug)]
+pub struct UdpTracker {
+    socket: UdpSocket,
+    connection: Option<Connection>,
+    /// Identifies the client to the tracker across IP address changes
+    key: u32,
+}
+
+impl UdpTracker {
+    /// Time a connection id may be used for after it is received
+    pub const CONNECTION_LIFETIME: Duration = Duration::from_secs(60);
+    /// Time to wait for a response before failing
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+    /// Creates a client for the tracker at `addr`, binding a local socket of the same address family
+    pub fn new(addr: impl ToSocketAddrs) -> Result<Self, TrackerError> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrNotAvailable))?;
+
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => (std::net::Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        socket.set_read_timeout(Some(Self::DEFAULT_TIMEOUT))?;
+
+        Ok(Self {
+            socket,
+            connection: None,
+            key: rand::random(),
+        })
+    }
+
+    /// Sets the time to wait for each response before failing
+    pub fn set_timeout(&self, timeout: Duration) -> Result<(), TrackerError> {
+        Ok(self.socket.set_read_timeout(Some(timeout))?)
+    }
+
+    /// Announces to the tracker, first obtaining a new connection id if there is none or it has expired
+    pub fn announce(
+        &mut self,
+        info_hash: &[u8; 20],
+        params: &AnnounceParams,
+    ) -> Result<TrackerResponse, TrackerError> {
+        let connection_id = self.connection_id()?;
+        let transaction_id = rand::random();
+
+        let mut request = Vec::with_capacity(98);
+        request.extend_from_slice(&connection_id.to_be_bytes());
+        request.extend_from_slice(&ACTION_ANNOUNCE.to_be_bytes());
+        request.extend_from_slice(&u32::to_be_bytes(transaction_id));
+        request.extend_from_slice(info_hash);
+        request.extend_from_slice(params.peer_id.as_bytes());
+        request.extend_from_slice(&params.downloaded.to_be_bytes());
+        request.extend_from_slice(&params.left.to_be_bytes());
+        request.extend_from_slice(&params.uploaded.to_be_bytes());
+        request.extend_from_slice(&event_code(params.event).to_be_bytes());
+        // let the tracker use the address the request came from
+        request.extend_from_slice(&0u32.to_be_bytes());
+        request.extend_from_slice(&self.key.to_be_bytes());
+        // ask for the default number of peers
+        request.extend_from_slice(&(-1i32).to_be_bytes());
+        request.extend_from_slice(&params.port.to_be_bytes());
+
+        let response = self.request(&request, ACTION_ANNOUNCE, transaction_id)?;
+        if response.len() < 12 {
+            return Err(TrackerError::InvalidResponse);
+        }
+
+        let (counts, peers) = response.split_at(12);
+        let peers = match self.socket.peer_addr()? {
+            SocketAddr::V4(_) => parse_compact_v4(peers),
+            SocketAddr::V6(_) => parse_compact_v6(peers),
+        }
+        .ok_or(TrackerError::InvalidResponse)?;
+
+        Ok(TrackerResponse {
+            interval: read_u32(counts, 0),
+            min_interval: None,
+            complete: Some(read_u32(counts, 8)),
+            incomplete: Some(read_u32(counts, 4)),
+            peers,
+            peer_hosts: Vec::new(),
+            warning_message: None,
+            tracker_id: None,
+        })
+    }
+
+    /// Returns the current connection id, connecting again if there is none or it has expired
+    fn connection_id(&mut self) -> Result<u64, TrackerError> {
+        if let Some(connection) = self.connection {
+            if connection.received.elapsed() < Self::CONNECTION_LIFETIME {
+                return Ok(connection.id);
+            }
+        }
+
+        let transaction_id = rand::random();
+
+        let mut request = Vec::with_capacity(16);
+        request.extend_from_slice(&PROTOCOL_ID.to_be_bytes());
+        request.extend_from_slice(&ACTION_CONNECT.to_be_bytes());
+        request.extend_from_slice(&u32::to_be_bytes(transaction_id));
+
+        let response = self.request(&request, ACTION_CONNECT, transaction_id)?;
+        let id = u64::from_be_bytes(
+            response
+                .get(..8)
+                .ok_or(TrackerError::InvalidResponse)?
+                .try_into()
+                .expect("slice is 8 bytes"),
+        );
+
+        self.connection = Some(Connection {
+            id,
+            received: Instant::now(),
+        });
+
+        Ok(id)
+    }
+
+    /// Sends a request and waits for the response with a matching transaction id, returning the bytes after the header.
+    ///
+    /// Responses to other transactions are ignored, as they may be late responses to earlier requests
+    fn request(
+        &self,
+        request: &[u8],
+        action: u32,
+        transaction_id: u32,
+    ) -> Result<Vec<u8>, TrackerError> {
+        self.socket.send(request)?;
+
+        let mut buffer = vec![0; MAX_RESPONSE_LEN];
+        loop {
+            let len = self.socket.recv(&mut buffer)?;
+            let response = &buffer[..len];
+            if len < 8 {
+                return Err(TrackerError::InvalidResponse);
+            }
+            if read_u32(response, 4) != transaction_id {
+                continue;
+            }
+
+            return match read_u32(response, 0) {
+                ACTION_ERROR => Err(TrackerError::Failure(
+                    String::from_utf8_lossy(&response[8..]).into_owned(),
+                )),
+                received if received == action => Ok(response[8..].to_vec()),
+                _ => Err(TrackerError::InvalidResponse),
+            };
+        }
+    }
+}
+
+/// Returns the number identifying an event in an announce request
+fn event_code(event: Option<Event>) -> u32 {
+    match event {
+        None => 0,
+        Some(Event::Completed) => 1,
+        Some(Event::Started) => 2,
+        Some(Event::Stopped) => 3,
+    }
+}
+
+/// Reads a big endian `u32` at `offset`, which must be in bounds
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(
+        bytes[offset..offset + 4]
+            .try_into()
+            .expect("slice is 4 bytes"),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+    use crate::{peer::PeerId, tracker::Peer};
+
+    const INFO_HASH: [u8; 20] = [0xab; 20];
+
+    fn params() -> AnnounceParams {
+        AnnounceParams {
+            peer_id: PeerId(*b"-TR3000-000000000000"),
+            port: 6881,
+            uploaded: 1,
+            downloaded: 2,
+            left: 3,
+            event: Some(Event::Started),
+            tracker_id: None,
+        }
+    }
+
+    /// Runs a mock tracker answering `connects` connect requests and `announces` announce requests, in any order.
+    ///
+    /// Returns the address of the tracker, and a handle resolving to the connection ids of each announce
+    fn mock_tracker(connects: usize, announces: usize) -> (SocketAddr, JoinHandle<Vec<u64>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            let mut announced = Vec::new();
+            let mut connected = 0u64;
+
+            while (connected as usize) < connects || announced.len() < announces {
+                let (len, from) = socket.recv_from(&mut buffer).unwrap();
+                let request = &buffer[..len];
+                let transaction_id = &request[12..16];
+
+                match read_u32(request, 8) {
+                    ACTION_CONNECT => {
+                        assert_eq!(len, 16);
+                        assert_eq!(request[..8], PROTOCOL_ID.to_be_bytes());
+                        connected += 1;
+
+                        // a stale response that must be ignored
+                        let mut stale = ACTION_CONNECT.to_be_bytes().to_vec();
+                        stale.extend_from_slice(&[0xff; 4]);
+                        stale.extend_from_slice(&0u64.to_be_bytes());
+                        socket.send_to(&stale, from).unwrap();
+
+                        let mut response = ACTION_CONNECT.to_be_bytes().to_vec();
+                        response.extend_from_slice(transaction_id);
+                        response.extend_from_slice(&connected.to_be_bytes());
+                        socket.send_to(&response, from).unwrap();
+                    }
+                    ACTION_ANNOUNCE => {
+                        assert_eq!(len, 98);
+                        assert_eq!(request[16..36], INFO_HASH);
+                        assert_eq!(&request[36..56], b"-TR3000-000000000000");
+                        assert_eq!(read_u32(request, 80), 2);
+                        assert_eq!(request[96..98], 6881u16.to_be_bytes());
+                        announced.push(u64::from_be_bytes(request[..8].try_into().unwrap()));
+
+                        let mut response = ACTION_ANNOUNCE.to_be_bytes().to_vec();
+                        response.extend_from_slice(transaction_id);
+                        response.extend_from_slice(&1800u32.to_be_bytes());
+                        response.extend_from_slice(&3u32.to_be_bytes());
+                        response.extend_from_slice(&7u32.to_be_bytes());
+                        response.extend_from_slice(&[127, 0, 0, 1, 0x1a, 0xe1]);
+                        socket.send_to(&response, from).unwrap();
+                    }
+                    action => panic!("unexpected action {action}"),
+                }
+            }
+
+            announced
+        });
+
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_announce() {
+        let (addr, handle) = mock_tracker(1, 2);
+        let mut tracker = UdpTracker::new(addr).unwrap();
+
+        let response = tracker.announce(&INFO_HASH, &params()).unwrap();
+        assert_eq!(
+            response,
+            TrackerResponse {
+                interval: 1800,
+                min_interval: None,
+                complete: Some(7),
+                incomplete: Some(3),
+                peers: vec![Peer {
+                    ip: IpAddr::V4(Ipv4Addr::LOCALHOST),
+                    port: 6881
+                }],
+                peer_hosts: Vec::new(),
+                warning_message: None,
+                tracker_id: None,
+            }
+        );
+
+        // the connection id is reused while it is valid
+        tracker.announce(&INFO_HASH, &params()).unwrap();
+        assert_eq!(handle.join().unwrap(), [1, 1]);
+    }
+
+    #[test]
+    fn test_reconnect_after_expiry() {
+        let (addr, handle) = mock_tracker(2, 2);
+        let mut tracker = UdpTracker::new(addr).unwrap();
+
+        tracker.announce(&INFO_HASH, &params()).unwrap();
+
+        let connection = tracker.connection.as_mut().unwrap();
+        connection.received -= UdpTracker::CONNECTION_LIFETIME;
+
+        tracker.announce(&INFO_HASH, &params()).unwrap();
+        assert_eq!(handle.join().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            let (_, from) = socket.recv_from(&mut buffer).unwrap();
+
+            let mut response = ACTION_ERROR.to_be_bytes().to_vec();
+            response.extend_from_slice(&buffer[12..16]);
+            response.extend_from_slice(b"unknown torrent");
+            socket.send_to(&response, from).unwrap();
+        });
+
+        let mut tracker = UdpTracker::new(addr).unwrap();
+        assert!(matches!(
+            tracker.announce(&INFO_HASH, &params()),
+            Err(TrackerError::Failure(message)) if message == "unknown torrent"
+        ));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout() {
+        // bound but never answers
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut tracker = UdpTracker::new(socket.local_addr().unwrap()).unwrap();
+        tracker.set_timeout(Duration::from_millis(50)).unwrap();
+
+        assert!(matches!(
+            tracker.announce(&INFO_HASH, &params()),
+            Err(TrackerError::Io(_))
+        ));
+    }
+}